@@ -0,0 +1,134 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use favannat::{
+    network::{
+        net::{activations, Edge, Net, Node},
+        Evaluator, Fabricator,
+    },
+    ClosureFeedforwardFabricator, MatrixFeedforwardFabricator, MatrixFeedforwardOptions,
+    TopoFeedforwardFabricator,
+};
+
+/// Builds a layered net where every node only connects to a few nodes of the previous layer.
+fn sparse_net(inputs: usize, outputs: usize, hidden_layers: usize, layer_size: usize) -> Net {
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+
+    let mut previous_layer: Vec<usize> = (0..inputs).collect();
+    let mut next_id = inputs;
+
+    for id in 0..inputs {
+        nodes.push(Node::new(id, activations::LINEAR));
+    }
+
+    let mut hidden = Vec::new();
+    let layer_sizes =
+        std::iter::repeat_n(layer_size, hidden_layers).chain(std::iter::once(outputs));
+
+    for (layer_index, size) in layer_sizes.enumerate() {
+        let layer: Vec<usize> = (next_id..next_id + size).collect();
+        next_id += size;
+
+        for (position, &id) in layer.iter().enumerate() {
+            for offset in &[0, 7, 13] {
+                let start = previous_layer[(position + offset) % previous_layer.len()];
+                let weight =
+                    ((position * 31 + offset * 17 + layer_index) % 100) as f64 / 50.0 - 1.0;
+                edges.push(Edge::new(start, id, weight));
+            }
+            if layer_index < hidden_layers {
+                hidden.push(Node::new(id, activations::TANH));
+            } else {
+                hidden.push(Node::new(id, activations::SIGMOID));
+            }
+        }
+
+        previous_layer = layer;
+    }
+
+    nodes.extend(hidden);
+
+    Net::new(inputs, outputs, nodes, edges)
+}
+
+fn sparse_500_nodes(c: &mut Criterion) {
+    let net = sparse_net(10, 10, 8, 60);
+    let input = vec![0.5; 10];
+
+    let matrix = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+    let topo = TopoFeedforwardFabricator::fabricate(&net).unwrap();
+
+    let mut group = c.benchmark_group("sparse 500 nodes");
+    group.bench_function("matrix", |b| {
+        b.iter(|| matrix.evaluate(black_box(input.clone())))
+    });
+    group.bench_function("topo", |b| {
+        b.iter(|| topo.evaluate(black_box(input.clone())))
+    });
+    group.finish();
+}
+
+fn single_sample_20_nodes(c: &mut Criterion) {
+    let net = sparse_net(4, 4, 2, 6);
+    let input = vec![0.5; 4];
+
+    let matrix = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+    let closure = ClosureFeedforwardFabricator::fabricate(&net).unwrap();
+
+    let mut group = c.benchmark_group("single sample 20 nodes");
+    group.bench_function("matrix", |b| {
+        b.iter(|| matrix.evaluate(black_box(input.clone())))
+    });
+    group.bench_function("closure", |b| b.iter(|| closure(black_box(&input))));
+    group.finish();
+}
+
+fn activation_dispatch(c: &mut Criterion) {
+    let net = sparse_net(10, 10, 8, 60);
+    let input = vec![0.5; 10];
+
+    let pointer = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+    let kind = MatrixFeedforwardFabricator::fabricate_with(
+        &net,
+        &MatrixFeedforwardOptions {
+            dispatch_by_kind: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let mut group = c.benchmark_group("activation dispatch");
+    group.bench_function("pointer", |b| {
+        b.iter(|| pointer.evaluate(black_box(input.clone())))
+    });
+    group.bench_function("kind", |b| {
+        b.iter(|| kind.evaluate(black_box(input.clone())))
+    });
+    group.finish();
+}
+
+fn fabrication_2000_nodes(c: &mut Criterion) {
+    let net = sparse_net(20, 20, 20, 98);
+
+    c.bench_function("fabricate 2000 nodes", |b| {
+        b.iter(|| MatrixFeedforwardFabricator::fabricate(black_box(&net)).unwrap())
+    });
+}
+
+fn fabrication_direct_seed(c: &mut Criterion) {
+    // no hidden layers, every output connects straight to three inputs like a minimal NEAT seed
+    let net = sparse_net(100, 50, 0, 0);
+
+    c.bench_function("fabricate direct seed", |b| {
+        b.iter(|| MatrixFeedforwardFabricator::fabricate(black_box(&net)).unwrap())
+    });
+}
+
+criterion_group!(
+    benches,
+    sparse_500_nodes,
+    single_sample_20_nodes,
+    activation_dispatch,
+    fabrication_2000_nodes,
+    fabrication_direct_seed
+);
+criterion_main!(benches);
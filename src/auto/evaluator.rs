@@ -0,0 +1,30 @@
+use crate::{
+    network::{Evaluator, NetworkIO},
+    MatrixFeedforwardEvaluator, SparseMatrixFeedforwardEvaluator,
+};
+
+/// The evaluator chosen by [`super::fabricator::AutoFeedforwardFabricator`].
+///
+/// [`Evaluator`] is not object safe, so the backends are wrapped in an enum instead of a `Box<dyn Evaluator>`.
+// the dense evaluator is the common choice, boxing it would only add an indirection
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum AutoFeedforwardEvaluator {
+    Dense(MatrixFeedforwardEvaluator),
+    Sparse(SparseMatrixFeedforwardEvaluator),
+}
+
+impl AutoFeedforwardEvaluator {
+    pub fn is_dense(&self) -> bool {
+        matches!(self, AutoFeedforwardEvaluator::Dense(_))
+    }
+}
+
+impl Evaluator for AutoFeedforwardEvaluator {
+    fn evaluate<T: NetworkIO>(&self, input: T) -> T {
+        match self {
+            AutoFeedforwardEvaluator::Dense(evaluator) => evaluator.evaluate(input),
+            AutoFeedforwardEvaluator::Sparse(evaluator) => evaluator.evaluate(input),
+        }
+    }
+}
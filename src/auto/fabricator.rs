@@ -0,0 +1,125 @@
+use crate::{
+    network::{Aggregation, EdgeLike, Fabricator, NetworkLike, NodeLike},
+    MatrixFeedforwardFabricator, SparseMatrixFeedforwardFabricator,
+};
+
+use super::evaluator::AutoFeedforwardEvaluator;
+
+/// Chooses between the dense and the sparse matrix backend by the edge density of a net.
+///
+/// The density is the number of enabled edges divided by the squared number of nodes.
+/// Nets at or above [`AutoFeedforwardOptions::density_threshold`] are fabricated dense, all others sparse.
+/// Nets with aggregations other than sums are always fabricated dense as the sparse backend does not support them.
+pub struct AutoFeedforwardFabricator;
+
+/// Configures the choice made by [`AutoFeedforwardFabricator::fabricate_with`].
+///
+/// [`Fabricator::fabricate`] uses the default options.
+#[derive(Debug, Clone)]
+pub struct AutoFeedforwardOptions {
+    pub density_threshold: f64,
+}
+
+impl Default for AutoFeedforwardOptions {
+    fn default() -> Self {
+        Self {
+            density_threshold: 0.1,
+        }
+    }
+}
+
+impl AutoFeedforwardFabricator {
+    /// Computes the edge density the backend is chosen by.
+    pub fn density<N: NodeLike, E: EdgeLike>(net: &impl NetworkLike<N, E>) -> f64 {
+        let nodes = net.nodes().len();
+        if nodes == 0 {
+            return 0.0;
+        }
+        let edges = net.edges().iter().filter(|edge| edge.enabled()).count();
+
+        edges as f64 / (nodes * nodes) as f64
+    }
+
+    /// Fabricates like [`Fabricator::fabricate`] but configured by the given options.
+    pub fn fabricate_with<N: NodeLike, E: EdgeLike>(
+        net: &impl NetworkLike<N, E>,
+        options: &AutoFeedforwardOptions,
+    ) -> Result<AutoFeedforwardEvaluator, &'static str> {
+        let unsupported = net.nodes().iter().any(|node| {
+            !matches!(
+                node.aggregation(),
+                Aggregation::WeightedSum | Aggregation::Sum
+            )
+        });
+
+        if unsupported || Self::density(net) >= options.density_threshold {
+            MatrixFeedforwardFabricator::fabricate(net).map(AutoFeedforwardEvaluator::Dense)
+        } else {
+            SparseMatrixFeedforwardFabricator::fabricate(net).map(AutoFeedforwardEvaluator::Sparse)
+        }
+    }
+}
+
+impl<N, E> Fabricator<N, E> for AutoFeedforwardFabricator
+where
+    N: NodeLike,
+    E: EdgeLike,
+{
+    type Output = AutoFeedforwardEvaluator;
+
+    fn fabricate(net: &impl NetworkLike<N, E>) -> Result<Self::Output, &'static str> {
+        Self::fabricate_with(net, &AutoFeedforwardOptions::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{AutoFeedforwardFabricator, AutoFeedforwardOptions};
+    use crate::{
+        edges,
+        network::{net::Net, Evaluator},
+        nodes,
+    };
+
+    #[test]
+    fn evaluates_dense_and_sparse_nets() {
+        // 5 edges on 4 nodes
+        let dense_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(
+                0--0.5->2,
+                1--0.5->2,
+                0--0.5->3,
+                1--0.5->3,
+                2--0.5->3
+            ),
+        );
+        // 2 edges on 6 nodes
+        let sparse_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l', 'l', 'l'),
+            edges!(
+                0--0.5->1,
+                1--0.5->5
+            ),
+        );
+
+        let options = AutoFeedforwardOptions {
+            density_threshold: 0.2,
+        };
+
+        let dense = AutoFeedforwardFabricator::fabricate_with(&dense_net, &options).unwrap();
+        let sparse = AutoFeedforwardFabricator::fabricate_with(&sparse_net, &options).unwrap();
+
+        assert!(dense.is_dense());
+        assert!(!sparse.is_dense());
+
+        assert_eq!(dense.evaluate(dmatrix![2.0, 4.0]), dmatrix![4.5]);
+        assert_eq!(sparse.evaluate(dmatrix![4.0]), dmatrix![1.0]);
+    }
+}
@@ -0,0 +1,66 @@
+//! Helpers to query a net as a compositional pattern producing network (CPPN), as done by HyperNEAT to derive the weights of a substrate.
+
+use nalgebra::DMatrix;
+
+use crate::network::Evaluator;
+
+/// Queries `evaluator` for the weight of every connection between two layers of a substrate.
+///
+/// The source layer lies at `y = -1` with its nodes at the x coordinates `xs`,
+/// the target layer lies at `y = 1` with its nodes at the x coordinates `ys`.
+/// The evaluator is expected to take five inputs laid out as `[x1, y1, x2, y2, bias]`,
+/// where `(x1, y1)` is the source node, `(x2, y2)` the target node and `bias` is always `1.0`.
+///
+/// All `xs.len() * ys.len()` queries are evaluated as one batch. Entry `(i, j)` of the result holds the first output
+/// for the connection from source node `i` to target node `j`, further outputs are ignored.
+pub fn query_grid(evaluator: &impl Evaluator, xs: &[f64], ys: &[f64]) -> DMatrix<f64> {
+    let mut coordinates = DMatrix::zeros(xs.len() * ys.len(), 5);
+
+    for (i, &x1) in xs.iter().enumerate() {
+        for (j, &x2) in ys.iter().enumerate() {
+            coordinates
+                .row_mut(i * ys.len() + j)
+                .copy_from_slice(&[x1, -1.0, x2, 1.0, 1.0]);
+        }
+    }
+
+    let outputs = evaluator.evaluate(coordinates);
+
+    DMatrix::from_fn(xs.len(), ys.len(), |i, j| outputs[(i * ys.len() + j, 0)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::query_grid;
+    use crate::{
+        edges,
+        network::{net::Net, Fabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn queries_every_connection() {
+        let cppn = Net::new(
+            5,
+            1,
+            nodes!('l', 'l', 'l', 'l', 'l', 'l'),
+            edges!(
+                0--1.0->5,
+                1--0.25->5,
+                2--2.0->5,
+                3--0.125->5,
+                4--0.5->5
+            ),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&cppn).unwrap();
+
+        let grid = query_grid(&evaluator, &[-1.0, 0.0, 1.0], &[-1.0, 1.0]);
+
+        assert_eq!(grid.shape(), (3, 2));
+        // x1 + 2 * x2 plus the constant contributions of y1, y2 and bias
+        assert_eq!(grid[(0, 0)], -1.0 - 2.0 + 0.375);
+        assert_eq!(grid[(0, 1)], -1.0 + 2.0 + 0.375);
+        assert_eq!(grid[(2, 0)], 1.0 - 2.0 + 0.375);
+        assert_eq!(grid[(2, 1)], 1.0 + 2.0 + 0.375);
+    }
+}
@@ -0,0 +1,155 @@
+use nalgebra::DMatrix;
+
+use crate::network::{Evaluator, NetworkIO};
+
+/// Integer approximation of an activation function on fixed-point values.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FixedActivation {
+    Linear,
+    Relu,
+    Inverse,
+    Squared,
+    /// Linear interpolation between `values` sampled every `step` starting at `lower`, constant beyond both ends.
+    PiecewiseLinear {
+        lower: i32,
+        step: i32,
+        values: Vec<i32>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedStage {
+    pub rows: usize,
+    pub cols: usize,
+    /// Weights stored column-major, entry `(row, column)` is found at `column * rows + row`.
+    pub weights: Vec<i32>,
+    pub activations: Vec<FixedActivation>,
+}
+
+/// Evaluates a net with integer arithmetic only, see [`super::fabricator::FixedPointFabricator`].
+///
+/// Values are fixed-point numbers with [`FixedPointEvaluator::fractional_bits`] bits after the binary point,
+/// i.e. the integer `v` represents `v / 2^fractional_bits`.
+#[derive(Debug, Clone)]
+pub struct FixedPointEvaluator {
+    pub stages: Vec<FixedStage>,
+    /// Stage, column and output position of outputs computed before the last stage, see [`crate::MatrixFeedforwardEvaluator::stashed_outputs`].
+    pub stashed_outputs: Vec<(usize, usize, usize)>,
+    pub fractional_bits: u32,
+    /// Clamp results that leave the range of `i32` instead of letting them wrap around.
+    pub saturate: bool,
+}
+
+impl FixedPointEvaluator {
+    /// Evaluates a single sample of fixed-point inputs, this is what runs on the target.
+    pub fn evaluate_fixed(&self, input: &[i32]) -> Vec<i32> {
+        let mut state = input.to_vec();
+        let mut stashed = Vec::with_capacity(self.stashed_outputs.len());
+
+        for (index, stage) in self.stages.iter().enumerate() {
+            state = (0..stage.cols)
+                .map(|column| {
+                    let weights = &stage.weights[column * stage.rows..(column + 1) * stage.rows];
+                    let sum: i64 = state
+                        .iter()
+                        .zip(weights)
+                        .map(|(&value, &weight)| value as i64 * weight as i64)
+                        .sum();
+                    let value = self.narrow(self.rescale(sum));
+                    self.activate(&stage.activations[column], value)
+                })
+                .collect();
+            for &(stashed_stage, column, output) in &self.stashed_outputs {
+                if stashed_stage == index {
+                    stashed.push((output, state[column]));
+                }
+            }
+        }
+        for (output, value) in stashed {
+            state[output] = value;
+        }
+
+        state
+    }
+
+    /// Converts a value to fixed-point, rounding to the nearest representable one.
+    pub fn to_fixed(&self, value: f64) -> i32 {
+        let scaled = (value * (1i64 << self.fractional_bits) as f64).round();
+        if self.saturate {
+            scaled.clamp(i32::MIN as f64, i32::MAX as f64) as i32
+        } else {
+            scaled as i64 as i32
+        }
+    }
+
+    pub fn from_fixed(&self, value: i32) -> f64 {
+        value as f64 / (1i64 << self.fractional_bits) as f64
+    }
+
+    // drops the doubled fractional bits of a product, rounding to nearest
+    fn rescale(&self, product: i64) -> i64 {
+        (product + (1i64 << (self.fractional_bits - 1))) >> self.fractional_bits
+    }
+
+    fn narrow(&self, value: i64) -> i32 {
+        if self.saturate {
+            value.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+        } else {
+            value as i32
+        }
+    }
+
+    fn activate(&self, activation: &FixedActivation, value: i32) -> i32 {
+        match activation {
+            FixedActivation::Linear => value,
+            FixedActivation::Relu => value.max(0),
+            FixedActivation::Inverse => {
+                if self.saturate {
+                    value.saturating_neg()
+                } else {
+                    value.wrapping_neg()
+                }
+            }
+            FixedActivation::Squared => self.narrow(self.rescale(value as i64 * value as i64)),
+            FixedActivation::PiecewiseLinear {
+                lower,
+                step,
+                values,
+            } => {
+                let offset = value as i64 - *lower as i64;
+                if offset <= 0 {
+                    return values[0];
+                }
+                let index = (offset / *step as i64) as usize;
+                if index >= values.len() - 1 {
+                    return values[values.len() - 1];
+                }
+                let (left, right) = (values[index] as i64, values[index + 1] as i64);
+                let remainder = offset % *step as i64;
+                (left + (right - left) * remainder / *step as i64) as i32
+            }
+        }
+    }
+}
+
+/// Converts to fixed-point and back around [`FixedPointEvaluator::evaluate_fixed`], every row of the input is evaluated as an independent sample.
+impl Evaluator for FixedPointEvaluator {
+    fn evaluate<T: NetworkIO>(&self, input: T) -> T {
+        let input = NetworkIO::input(input);
+        let rows: Vec<Vec<f64>> = input
+            .row_iter()
+            .map(|row| {
+                let fixed: Vec<i32> = row.iter().map(|&value| self.to_fixed(value)).collect();
+                self.evaluate_fixed(&fixed)
+                    .into_iter()
+                    .map(|value| self.from_fixed(value))
+                    .collect()
+            })
+            .collect();
+        let columns = rows.first().map_or(0, Vec::len);
+
+        NetworkIO::output(DMatrix::from_fn(input.nrows(), columns, |row, column| {
+            rows[row][column]
+        }))
+    }
+}
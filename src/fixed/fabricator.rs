@@ -0,0 +1,196 @@
+use crate::{
+    matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+    network::{ActivationKind, EdgeLike, Fabricator, NetworkLike, NodeLike},
+};
+
+use super::evaluator::{FixedActivation, FixedPointEvaluator, FixedStage};
+
+/// Compiles a net into integer stages for targets without floating point unit.
+///
+/// The net is fabricated like [`MatrixFeedforwardFabricator`] does and its weights are rounded to fixed-point.
+/// Sigmoid, tanh and gaussian are replaced by piecewise-linear approximations sampled every `1/16`,
+/// which are off by less than `0.01`. Together with rounding every weighted sum this bounds the error of a single node
+/// to about `0.01` plus the input error amplified by the absolute sum of its weights, with the default options.
+/// Custom activations and aggregations other than sums are not supported.
+pub struct FixedPointFabricator;
+
+/// Configures the evaluator built by [`FixedPointFabricator::fabricate_with`].
+///
+/// [`Fabricator::fabricate`] uses the default options.
+#[derive(Debug, Clone)]
+pub struct FixedPointOptions {
+    /// Number of bits after the binary point, between 4 and 30.
+    ///
+    /// More bits give more precision but leave less room for large values before results leave the range of `i32`.
+    pub fractional_bits: u32,
+    /// Clamp results that leave the range of `i32` instead of letting them wrap around.
+    pub saturate: bool,
+}
+
+impl Default for FixedPointOptions {
+    fn default() -> Self {
+        Self {
+            fractional_bits: 16,
+            saturate: true,
+        }
+    }
+}
+
+impl FixedPointFabricator {
+    /// Fabricates like [`Fabricator::fabricate`] but configured by the given options.
+    pub fn fabricate_with<N: NodeLike, E: EdgeLike>(
+        net: &impl NetworkLike<N, E>,
+        options: &FixedPointOptions,
+    ) -> Result<FixedPointEvaluator, &'static str> {
+        if !(4..=30).contains(&options.fractional_bits) {
+            return Err("fractional bits out of supported range 4 to 30");
+        }
+
+        let dense = MatrixFeedforwardFabricator::fabricate(net)?;
+        if dense.pooling.iter().any(|pooling| !pooling.is_empty()) {
+            return Err(
+                "aggregations other than sums are not supported by this fabricator, net invalid",
+            );
+        }
+
+        let mut evaluator = FixedPointEvaluator {
+            stages: Vec::with_capacity(dense.stages.len()),
+            stashed_outputs: dense.stashed_outputs.clone(),
+            fractional_bits: options.fractional_bits,
+            saturate: options.saturate,
+        };
+
+        for (stage_matrix, activation_kinds) in dense.stages.iter().zip(&dense.activation_kinds) {
+            let activations = activation_kinds
+                .iter()
+                .map(|&kind| fixed_activation(&evaluator, kind))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            evaluator.stages.push(FixedStage {
+                rows: stage_matrix.nrows(),
+                cols: stage_matrix.ncols(),
+                weights: stage_matrix
+                    .iter()
+                    .map(|&weight| evaluator.to_fixed(weight))
+                    .collect(),
+                activations,
+            });
+        }
+
+        Ok(evaluator)
+    }
+}
+
+// samples smooth activations over the range where they are not yet flat
+fn fixed_activation(
+    evaluator: &FixedPointEvaluator,
+    kind: ActivationKind,
+) -> Result<FixedActivation, &'static str> {
+    let (lower, upper) = match kind {
+        ActivationKind::Linear => return Ok(FixedActivation::Linear),
+        ActivationKind::Relu => return Ok(FixedActivation::Relu),
+        ActivationKind::Inverse => return Ok(FixedActivation::Inverse),
+        ActivationKind::Squared => return Ok(FixedActivation::Squared),
+        ActivationKind::Sigmoid => (-2.0, 2.0),
+        ActivationKind::Tanh => (-1.0, 1.0),
+        ActivationKind::Gaussian => (-4.0, 4.0),
+        ActivationKind::Custom => {
+            return Err("custom activations are not supported by this fabricator, net invalid")
+        }
+    };
+    let activation = kind.function().unwrap();
+    let step = 1.0 / 16.0;
+    let samples = ((upper - lower) / step) as usize + 1;
+
+    Ok(FixedActivation::PiecewiseLinear {
+        lower: evaluator.to_fixed(lower),
+        step: evaluator.to_fixed(step),
+        values: (0..samples)
+            .map(|sample| evaluator.to_fixed(activation(lower + sample as f64 * step)))
+            .collect(),
+    })
+}
+
+impl<N, E> Fabricator<N, E> for FixedPointFabricator
+where
+    N: NodeLike,
+    E: EdgeLike,
+{
+    type Output = FixedPointEvaluator;
+
+    fn fabricate(net: &impl NetworkLike<N, E>) -> Result<Self::Output, &'static str> {
+        Self::fabricate_with(net, &FixedPointOptions::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FixedPointFabricator, FixedPointOptions};
+    use crate::{
+        edges,
+        network::{net::Net, Evaluator, Fabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    fn tanh_net() -> Net {
+        Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 't', 't', 't', 's'),
+            edges!(
+                0--0.5->2,
+                1---0.8->2,
+                0--0.3->3,
+                1--0.6->3,
+                2--1.5->4,
+                3---0.7->4,
+                2--0.9->5,
+                3--0.4->5
+            ),
+        )
+    }
+
+    #[test]
+    fn matches_floating_point_within_bound() {
+        let reference = MatrixFeedforwardFabricator::fabricate(&tanh_net()).unwrap();
+        let fixed = FixedPointFabricator::fabricate(&tanh_net()).unwrap();
+
+        for input in &[[0.0, 0.0], [0.7, -1.2], [-2.0, 3.5], [0.1, 0.2]] {
+            let expected: Vec<f64> = reference.evaluate(input.to_vec());
+            let result: Vec<f64> = fixed.evaluate(input.to_vec());
+
+            for (a, b) in result.iter().zip(&expected) {
+                assert!((a - b).abs() < 0.03, "{} != {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn saturates_instead_of_wrapping() {
+        let some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--4.0->1));
+        let options = |saturate| FixedPointOptions {
+            fractional_bits: 16,
+            saturate,
+        };
+
+        let saturating = FixedPointFabricator::fabricate_with(&some_net, &options(true)).unwrap();
+        let wrapping = FixedPointFabricator::fabricate_with(&some_net, &options(false)).unwrap();
+        let large = saturating.to_fixed(8192.0);
+
+        assert_eq!(saturating.evaluate_fixed(&[large]), vec![i32::MAX]);
+        assert_eq!(wrapping.evaluate_fixed(&[large]), vec![i32::MIN]);
+    }
+
+    #[test]
+    fn rejects_fractional_bits_out_of_range() {
+        let options = FixedPointOptions {
+            fractional_bits: 31,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            FixedPointFabricator::fabricate_with(&tanh_net(), &options).unwrap_err(),
+            "fractional bits out of supported range 4 to 30"
+        );
+    }
+}
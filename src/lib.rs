@@ -3,29 +3,123 @@
 //! See [`network::net`] for an examplatory implementation.
 //!
 //! Networks accept any value that implements the [`network::NetworkIO`] trait.
+//! It is implemented for `f64` and `f32` values, so float literals in inputs need an `f64` suffix, e.g. `vec![1.0f64]`,
+//! whenever nothing else determines their type.
 //!
-//! The feature `ndarray` implements `NetworkIO` from `ndarray::Array1` when enabled.
+//! All evaluators are `Send` and `Sync`. As [`Evaluator::evaluate`] takes `&self`, a fabricated feedforward evaluator
+//! can be shared between threads, e.g. behind an `Arc`, and evaluated concurrently. Stateful evaluators need `&mut self`
+//! and thus one instance per thread or a lock.
+//!
+//! The feature `ndarray` implements `NetworkIO` for `ndarray::Array1` of `f64` and `f32` when enabled.
+//!
+//! The feature `ndarray-backend` provides [`NdarrayFeedforwardEvaluator`], which evaluates the stages of a [`MatrixFeedforwardEvaluator`] with `ndarray`.
+//! nalgebra remains a dependency as fabrication and [`network::NetworkIO`] are built on it.
+//!
+//! The feature `serde` derives `Serialize` and `Deserialize` for [`network::ActivationKind`] and [`network::Aggregation`]
+//! and implements them for [`MatrixFeedforwardEvaluator`], which can also be encoded in a compact binary form with `bincode`.
+//!
+//! The feature `sharpneat` reads genomes exported by SharpNEAT, see [`sharpneat::parse`].
+//!
+//! The feature `testing` provides helpers in [`testing`] to test code that depends on fabrication.
+//!
+//! The feature `profile` records how long each stage of a [`MatrixFeedforwardEvaluator`] took in its most recent evaluation.
+//!
+//! The feature `petgraph` allows to convert networks into a `petgraph::Graph` to run graph algorithms on them.
 
+pub mod auto;
+pub mod cppn;
+pub mod fixed;
 pub mod matrix;
+#[cfg(feature = "ndarray-backend")]
+pub mod ndarray_matrix;
 pub mod neat_original;
 pub mod network;
+#[cfg(feature = "sharpneat")]
+pub mod sharpneat;
 pub mod sparse_matrix;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod topo;
+pub mod wrapper;
+
+pub use auto::{
+    evaluator::AutoFeedforwardEvaluator,
+    fabricator::{AutoFeedforwardFabricator, AutoFeedforwardOptions},
+};
+
+pub use fixed::{
+    evaluator::FixedPointEvaluator,
+    fabricator::{FixedPointFabricator, FixedPointOptions},
+};
 
 pub use matrix::{
-    feedforward::{evaluator::MatrixFeedforwardEvaluator, fabricator::MatrixFeedforwardFabricator},
-    recurrent::{evaluator::MatrixRecurrentEvaluator, fabricator::MatrixRecurrentFabricator},
+    feedforward::{
+        ensemble::{EnsembleEvaluator, EnsembleMode},
+        evaluator::MatrixFeedforwardEvaluator,
+        fabricator::{MatrixFeedforwardFabricator, MatrixFeedforwardOptions},
+        normalizing::{NormalizingFeedforwardEvaluator, SelfNormalizingMatrixEvaluator},
+        population::PopulationFabricator,
+        scratch::EvalScratch,
+        vector_activation::{VectorActivation, VectorActivationKind},
+    },
+    recurrent::{
+        evaluator::{MatrixRecurrentBatchEvaluator, MatrixRecurrentEvaluator, RecurrentCheckpoint},
+        fabricator::{MatrixRecurrentFabricator, MatrixRecurrentOptions},
+    },
+};
+
+#[cfg(feature = "ndarray-backend")]
+pub use ndarray_matrix::feedforward::{
+    evaluator::NdarrayFeedforwardEvaluator, fabricator::NdarrayFeedforwardFabricator,
 };
 
 pub use sparse_matrix::{
     feedforward::{
-        evaluator::SparseMatrixFeedforwardEvaluator, fabricator::SparseMatrixFeedforwardFabricator,
+        evaluator::SparseMatrixFeedforwardEvaluator,
+        fabricator::{SparseMatrixFeedforwardFabricator, SparseMatrixFeedforwardOptions},
     },
     recurrent::{
         evaluator::SparseMatrixRecurrentEvaluator, fabricator::SparseMatrixRecurrentFabricator,
     },
 };
 
+pub use wrapper::{
+    clamped::ClampedEvaluator, feedback::FeedbackEvaluator,
+    saturating::SaturatingRecurrentEvaluator, softmax::SoftmaxEvaluator,
+};
+
+pub use topo::{
+    closure::{ClosureFeedforwardEvaluator, ClosureFeedforwardFabricator},
+    evaluator::TopoFeedforwardEvaluator,
+    fabricator::TopoFeedforwardFabricator,
+};
+
 pub use network::{Evaluator, Fabricator, StatefulEvaluator, StatefulFabricator};
 
 type Matrix = Vec<Vec<f64>>;
 type Transformations = Vec<fn(f64) -> f64>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn evaluators_are_send_and_sync() {
+        assert_send_sync::<MatrixFeedforwardEvaluator>();
+        assert_send_sync::<SparseMatrixFeedforwardEvaluator>();
+        assert_send_sync::<MatrixRecurrentEvaluator>();
+        assert_send_sync::<SparseMatrixRecurrentEvaluator>();
+        assert_send_sync::<TopoFeedforwardEvaluator>();
+        assert_send_sync::<ClosureFeedforwardEvaluator>();
+        assert_send_sync::<AutoFeedforwardEvaluator>();
+        assert_send_sync::<FixedPointEvaluator>();
+        assert_send_sync::<EnsembleEvaluator>();
+        assert_send_sync::<SelfNormalizingMatrixEvaluator>();
+        assert_send_sync::<NormalizingFeedforwardEvaluator>();
+        assert_send_sync::<MatrixRecurrentBatchEvaluator>();
+        assert_send_sync::<SaturatingRecurrentEvaluator<MatrixRecurrentEvaluator>>();
+        assert_send_sync::<neat_original::evaluator::NeatOriginalEvaluator>();
+    }
+}
@@ -0,0 +1,199 @@
+use nalgebra::DMatrix;
+
+use crate::network::ActivationKind;
+
+use super::evaluator::MatrixFeedforwardEvaluator;
+
+impl MatrixFeedforwardEvaluator {
+    /// Number of values expected per sample.
+    pub fn input_dim(&self) -> usize {
+        self.input_nodes.len()
+    }
+
+    /// Number of values returned per sample, including any columns kept behind the outputs of the net.
+    pub fn output_dim(&self) -> usize {
+        match self.stages.last() {
+            Some(last_stage) => last_stage.ncols(),
+            None => self.input_nodes.len(),
+        }
+    }
+
+    /// Fuses two evaluators into one that feeds the outputs of `self` into the inputs of `next`.
+    ///
+    /// The stages of `next` are appended to those of `self`, outputs of `self` that are computed before its last stage are carried forward to it.
+    /// Inputs of `next` take the node ids of the outputs of `self` in [`MatrixFeedforwardEvaluator::stage_nodes`],
+    /// all other ids of `next` are shifted behind the largest id of `self`.
+    /// Weight groups of both evaluators are kept, groups sharing an id are merged.
+    /// The output vector activation of `next` is kept, `self` must not have one as it would have to apply between stages.
+    pub fn then(mut self, next: Self) -> Result<Self, &'static str> {
+        if self.output_dim() != next.input_dim() {
+            return Err("outputs of first evaluator do not match inputs of second evaluator");
+        }
+        if self.output_vector_activation.is_some() {
+            return Err("output vector activation of first evaluator can not be composed");
+        }
+
+        let output_nodes = match self.stage_nodes.last() {
+            Some(last_nodes) => last_nodes.clone(),
+            None => self.input_nodes.clone(),
+        };
+        let offset = self
+            .input_nodes
+            .iter()
+            .chain(self.stage_nodes.iter().flatten())
+            .max()
+            .map_or(Some(0), |id| id.checked_add(1))
+            .ok_or("node ids of composed evaluators exceed usize")?;
+        let relabel = |id: usize| match next.input_nodes.iter().position(|&input| input == id) {
+            Some(position) => Ok(output_nodes[position]),
+            None => id
+                .checked_add(offset)
+                .ok_or("node ids of composed evaluators exceed usize"),
+        };
+        let next_stage_nodes = next
+            .stage_nodes
+            .iter()
+            .map(|nodes| nodes.iter().map(|&id| relabel(id)).collect())
+            .collect::<Result<Vec<Vec<usize>>, _>>()?;
+
+        self.carry_stashed_outputs();
+
+        let stage_offset = self.stages.len();
+        self.stashed_outputs.extend(
+            next.stashed_outputs
+                .iter()
+                .map(|&(stage, column, output)| (stage + stage_offset, column, output)),
+        );
+        self.stages.extend(next.stages);
+        self.transformations.extend(next.transformations);
+        self.activation_kinds.extend(next.activation_kinds);
+        self.stage_nodes.extend(next_stage_nodes);
+        self.pooling.extend(next.pooling);
+        self.biases.extend(next.biases);
+        for (group, tied_weights) in next.weight_groups {
+            self.weight_groups
+                .entry(group)
+                .or_default()
+                .extend(tied_weights.into_iter().map(|mut tied_weight| {
+                    tied_weight.stage += stage_offset;
+                    tied_weight
+                }));
+        }
+        self.dispatch_by_kind &= next.dispatch_by_kind;
+        self.outputs = next.outputs;
+        self.output_vector_activation = next.output_vector_activation;
+
+        Ok(self)
+    }
+
+    // replaces reinserting outputs computed before the last stage by carrying them through all following stages
+    fn carry_stashed_outputs(&mut self) {
+        let last = match self.stages.len() {
+            0 => return,
+            stages => stages - 1,
+        };
+
+        for (stage, column, output) in std::mem::take(&mut self.stashed_outputs) {
+            let node = self.stage_nodes[stage][column];
+            let mut carried_column = column;
+
+            for carrying_stage in stage + 1..last {
+                let columns = self.stages[carrying_stage].ncols();
+                let rows = self.stages[carrying_stage].nrows();
+                let mut carry = DMatrix::zeros(rows, columns + 1);
+                carry
+                    .columns_mut(0, columns)
+                    .copy_from(&self.stages[carrying_stage]);
+                carry[(carried_column, columns)] = 1.0;
+                self.stages[carrying_stage] = carry;
+
+                self.transformations[carrying_stage].push(|val| val);
+                self.activation_kinds[carrying_stage].push(ActivationKind::Linear);
+                self.stage_nodes[carrying_stage].push(node);
+                if let Some(bias) = self.biases[carrying_stage].take() {
+                    self.biases[carrying_stage] = Some(bias.insert_column(columns, 0.0));
+                }
+
+                // the following stage receives the carried value as an additional row
+                let following = &mut self.stages[carrying_stage + 1];
+                *following = following.clone().insert_row(following.nrows(), 0.0);
+
+                carried_column = columns;
+            }
+
+            self.stages[last][(carried_column, output)] = 1.0;
+            if let Some(bias) = &mut self.biases[last] {
+                bias[output] = 0.0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use crate::{
+        edges,
+        network::{net::Net, Evaluator, Fabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn composes_doubling_with_tripling() {
+        let doubling = MatrixFeedforwardFabricator::fabricate(&Net::new(
+            1,
+            1,
+            nodes!('l', 'l'),
+            edges!(0--2.0->1),
+        ))
+        .unwrap();
+        let tripling = MatrixFeedforwardFabricator::fabricate(&Net::new(
+            1,
+            1,
+            nodes!('l', 'l'),
+            edges!(0--3.0->1),
+        ))
+        .unwrap();
+
+        let composed = doubling.then(tripling).unwrap();
+
+        assert_eq!(composed.evaluate(dmatrix![1.0]), dmatrix![6.0]);
+    }
+
+    #[test]
+    fn carries_early_outputs_into_next_evaluator() {
+        // output 3 is computed in the first stage, output 4 only in the third
+        let first_net = Net::new(
+            1,
+            2,
+            nodes!('l', 't', 's', 'l', 'l'),
+            edges!(0--1.0->3, 0--0.5->1, 1--0.5->2, 2--1.0->4),
+        );
+        let second_net = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--10.0->2));
+
+        let first = MatrixFeedforwardFabricator::fabricate(&first_net).unwrap();
+        let second = MatrixFeedforwardFabricator::fabricate(&second_net).unwrap();
+        assert!(!first.stashed_outputs.is_empty());
+
+        let expected = second.evaluate(first.evaluate(dmatrix![0.5; -2.0]));
+        let composed = first.then(second).unwrap();
+
+        assert_eq!(composed.evaluate(dmatrix![0.5; -2.0]), expected);
+    }
+
+    #[test]
+    fn rejects_mismatching_dimensions() {
+        let net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--2.0->1));
+        let wide_net = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--1.0->2));
+
+        let composed = MatrixFeedforwardFabricator::fabricate(&net)
+            .unwrap()
+            .then(MatrixFeedforwardFabricator::fabricate(&wide_net).unwrap());
+
+        assert_eq!(
+            composed.err(),
+            Some("outputs of first evaluator do not match inputs of second evaluator")
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use crate::network::ActivationKind;
+
+use super::evaluator::MatrixFeedforwardEvaluator;
+
+impl MatrixFeedforwardEvaluator {
+    /// Rescales stages so values computed by linear nodes stay near unity, e.g. along deep linear chains.
+    ///
+    /// Every column of a stage that computes a linear or ReLU node is scaled so its largest weight is close to one,
+    /// and the rows of the next stage that consume the column are scaled inversely, which leaves the result unchanged.
+    /// Scales are powers of two, so scaling itself introduces no rounding error.
+    ///
+    /// Only activations that commute with positive scaling can be treated like this, nodes with sigmoid, tanh or any other
+    /// nonlinear activation keep their weights and thereby break up the sections that are conditioned.
+    /// Outputs, carried values and pooled nodes are never scaled.
+    pub fn condition_linear_stages(&mut self) {
+        for stage in 0..self.stages.len().saturating_sub(1) {
+            for column in 0..self.stages[stage].ncols() {
+                let homogeneous = matches!(
+                    self.activation_kinds[stage][column],
+                    ActivationKind::Linear | ActivationKind::Relu
+                );
+                let stashed =
+                    self.stashed_outputs
+                        .iter()
+                        .any(|&(stashed_stage, stashed_column, _)| {
+                            stashed_stage == stage && stashed_column == column
+                        });
+                let pooled = self.pooling[stage]
+                    .iter()
+                    .any(|pooling| pooling.column == column);
+                if !homogeneous || stashed || pooled || self.is_carry(stage, column) {
+                    continue;
+                }
+
+                let largest = self.stages[stage]
+                    .column(column)
+                    .iter()
+                    .fold(0.0f64, |largest, weight| largest.max(weight.abs()));
+                if largest == 0.0 || !largest.is_finite() {
+                    continue;
+                }
+                let scale = 2.0f64.powi(-(largest.log2().round() as i32));
+
+                self.stages[stage].column_mut(column).scale_mut(scale);
+                if let Some(bias) = &mut self.biases[stage] {
+                    bias[column] *= scale;
+                }
+                // tied weights keep their group weight but are applied with the same scales
+                for tied_weight in self.weight_groups.values_mut().flatten() {
+                    if tied_weight.stage == stage && tied_weight.column == column {
+                        tied_weight.scale *= scale;
+                    } else if tied_weight.stage == stage + 1 && tied_weight.row == column {
+                        tied_weight.scale /= scale;
+                    }
+                }
+
+                self.stages[stage + 1]
+                    .row_mut(column)
+                    .scale_mut(1.0 / scale);
+                for pooling in self.pooling[stage + 1].iter_mut() {
+                    for (row, weight) in pooling.inputs.iter_mut() {
+                        if *row == column {
+                            *weight /= scale;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        network::{
+            net::{activations, Edge, Net, Node},
+            Evaluator, Fabricator,
+        },
+        MatrixFeedforwardFabricator, MatrixFeedforwardOptions,
+    };
+
+    #[test]
+    fn conditioned_chain_matches_unconditioned() {
+        let depth = 40;
+        let nodes = (0..=depth)
+            .map(|id| Node::new(id, activations::LINEAR))
+            .collect();
+        let edges = (0..depth).map(|id| Edge::new(id, id + 1, 10.0)).collect();
+        let deep_net = Net::new(1, 1, nodes, edges);
+
+        let plain = MatrixFeedforwardFabricator::fabricate(&deep_net).unwrap();
+        let conditioned = MatrixFeedforwardFabricator::fabricate_with(
+            &deep_net,
+            &MatrixFeedforwardOptions {
+                condition_linear_stages: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // intermediate weights no longer compound
+        assert!(conditioned.stages[..depth - 1]
+            .iter()
+            .all(|stage| stage.iter().all(|weight| weight.abs() < 2.0)));
+
+        for input in &[1.0, -0.3, 1e-5] {
+            let expected: Vec<f64> = plain.evaluate(vec![*input]);
+            let result: Vec<f64> = conditioned.evaluate(vec![*input]);
+
+            assert!((result[0] - expected[0]).abs() <= expected[0].abs() * 1e-12);
+        }
+    }
+}
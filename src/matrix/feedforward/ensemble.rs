@@ -0,0 +1,146 @@
+use nalgebra::DMatrix;
+
+use crate::network::{Evaluator, NetworkIO};
+
+use super::evaluator::MatrixFeedforwardEvaluator;
+
+/// How [`EnsembleEvaluator`] combines the outputs of its members.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnsembleMode {
+    Mean,
+    Sum,
+    Max,
+}
+
+/// Evaluates several [`MatrixFeedforwardEvaluator`]s on the same input and combines their outputs element-wise.
+#[derive(Debug, Clone)]
+pub struct EnsembleEvaluator {
+    pub evaluators: Vec<MatrixFeedforwardEvaluator>,
+    pub mode: EnsembleMode,
+}
+
+impl EnsembleEvaluator {
+    /// Fails if no evaluators are given or they differ in input or output dimension.
+    pub fn new(
+        evaluators: Vec<MatrixFeedforwardEvaluator>,
+        mode: EnsembleMode,
+    ) -> Result<Self, &'static str> {
+        let first = evaluators
+            .first()
+            .ok_or("no evaluators present, ensemble invalid")?;
+        let expected = (first.input_dim(), first.output_dim());
+
+        if evaluators
+            .iter()
+            .any(|evaluator| (evaluator.input_dim(), evaluator.output_dim()) != expected)
+        {
+            return Err("evaluators differ in input or output dimension, ensemble invalid");
+        }
+
+        Ok(Self { evaluators, mode })
+    }
+}
+
+impl MatrixFeedforwardEvaluator {
+    /// Combines `evaluators` into an [`EnsembleEvaluator`] that averages their outputs.
+    pub fn ensemble(evaluators: Vec<Self>) -> Result<EnsembleEvaluator, &'static str> {
+        EnsembleEvaluator::new(evaluators, EnsembleMode::Mean)
+    }
+}
+
+impl Evaluator for EnsembleEvaluator {
+    fn evaluate<T: NetworkIO>(&self, input: T) -> T {
+        let input: DMatrix<f64> = NetworkIO::input(input);
+
+        let mut outputs = self
+            .evaluators
+            .iter()
+            .map(|evaluator| evaluator.evaluate(input.clone()));
+
+        // construction guarantees at least one member
+        let first: DMatrix<f64> = outputs.next().unwrap();
+
+        let combined = match self.mode {
+            EnsembleMode::Mean => {
+                outputs.fold(first, |sum, output| sum + output) / self.evaluators.len() as f64
+            }
+            EnsembleMode::Sum => outputs.fold(first, |sum, output| sum + output),
+            EnsembleMode::Max => outputs.fold(first, |max, output| max.zip_map(&output, f64::max)),
+        };
+
+        NetworkIO::output(combined)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{EnsembleEvaluator, EnsembleMode};
+    use crate::{
+        edges,
+        network::{
+            net::{Edge, Net},
+            Evaluator, Fabricator,
+        },
+        nodes, MatrixFeedforwardEvaluator, MatrixFeedforwardFabricator,
+    };
+
+    fn scaling_net(weight: f64) -> Net {
+        Net::new(1, 1, nodes!('l', 'l'), vec![Edge::new(0, 1, weight)])
+    }
+
+    #[test]
+    fn averaging_identical_evaluators_matches_single() {
+        let some_net = scaling_net(0.5);
+
+        let single = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let ensemble = MatrixFeedforwardEvaluator::ensemble(vec![
+            MatrixFeedforwardFabricator::fabricate(&some_net).unwrap(),
+            MatrixFeedforwardFabricator::fabricate(&some_net).unwrap(),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            ensemble.evaluate(dmatrix![5.0]),
+            single.evaluate(dmatrix![5.0])
+        );
+    }
+
+    #[test]
+    fn combines_by_mode() {
+        let members = || {
+            vec![
+                MatrixFeedforwardFabricator::fabricate(&scaling_net(1.0)).unwrap(),
+                MatrixFeedforwardFabricator::fabricate(&scaling_net(3.0)).unwrap(),
+            ]
+        };
+
+        let sum = EnsembleEvaluator::new(members(), EnsembleMode::Sum).unwrap();
+        let max = EnsembleEvaluator::new(members(), EnsembleMode::Max).unwrap();
+        let mean = EnsembleEvaluator::new(members(), EnsembleMode::Mean).unwrap();
+
+        assert_eq!(sum.evaluate(vec![2.0]), vec![8.0]);
+        assert_eq!(max.evaluate(vec![2.0]), vec![6.0]);
+        assert_eq!(mean.evaluate(vec![2.0]), vec![4.0]);
+    }
+
+    #[test]
+    fn rejects_mismatched_dimensions() {
+        let wide_net = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--1.0->2));
+
+        let result = MatrixFeedforwardEvaluator::ensemble(vec![
+            MatrixFeedforwardFabricator::fabricate(&scaling_net(1.0)).unwrap(),
+            MatrixFeedforwardFabricator::fabricate(&wide_net).unwrap(),
+        ]);
+
+        assert_eq!(
+            result.err(),
+            Some("evaluators differ in input or output dimension, ensemble invalid")
+        );
+        assert_eq!(
+            MatrixFeedforwardEvaluator::ensemble(Vec::new()).err(),
+            Some("no evaluators present, ensemble invalid")
+        );
+    }
+}
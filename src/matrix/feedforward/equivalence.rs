@@ -0,0 +1,124 @@
+use super::{evaluator::MatrixFeedforwardEvaluator, vector_activation::VectorActivationKind};
+
+/// Tells if both slices have the same length and every pair of values differs by at most `eps`.
+///
+/// Equal infinities and two NaNs count as equal.
+pub(crate) fn approx_eq_values(a: &[f64], b: &[f64], eps: f64) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(&a, &b)| a == b || (a - b).abs() <= eps || (a.is_nan() && b.is_nan()))
+}
+
+/// Tells if both stages apply the very same activation functions, compared by address.
+pub(crate) fn same_transformations(
+    a: &[crate::Transformations],
+    b: &[crate::Transformations],
+) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).all(|(a, b)| {
+            a.len() == b.len() && a.iter().zip(b).all(|(&a, &b)| a as usize == b as usize)
+        })
+}
+
+// built-in vector activations match by kind, custom ones by address like `same_transformations`
+fn same_vector_activation(
+    a: Option<VectorActivationKind>,
+    b: Option<VectorActivationKind>,
+) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(VectorActivationKind::Custom(a)), Some(VectorActivationKind::Custom(b))) => {
+            a as usize == b as usize
+        }
+        (Some(a), Some(b)) => a.name().is_some() && a.name() == b.name(),
+        _ => false,
+    }
+}
+
+impl MatrixFeedforwardEvaluator {
+    /// Tells if both evaluators compute the same function in the same way, allowing weights and biases to differ by at most `eps`.
+    ///
+    /// Everything else, the shape of the stages, activations, node ids, pooling, weight groups, stashed outputs and the output vector activation, has to match exactly.
+    pub fn approx_eq(&self, other: &Self, eps: f64) -> bool {
+        self.stages.len() == other.stages.len()
+            && self.stages.iter().zip(&other.stages).all(|(a, b)| {
+                a.shape() == b.shape() && approx_eq_values(a.as_slice(), b.as_slice(), eps)
+            })
+            && self.biases.len() == other.biases.len()
+            && self
+                .biases
+                .iter()
+                .zip(&other.biases)
+                .all(|(a, b)| match (a, b) {
+                    (Some(a), Some(b)) => approx_eq_values(a.as_slice(), b.as_slice(), eps),
+                    (None, None) => true,
+                    _ => false,
+                })
+            && self.pooling.len() == other.pooling.len()
+            && self.pooling.iter().zip(&other.pooling).all(|(a, b)| {
+                a.len() == b.len()
+                    && a.iter().zip(b).all(|(a, b)| {
+                        a.column == b.column
+                            && a.aggregation == b.aggregation
+                            && a.inputs.len() == b.inputs.len()
+                            && a.inputs
+                                .iter()
+                                .zip(&b.inputs)
+                                .all(|(a, b)| a.0 == b.0 && approx_eq_values(&[a.1], &[b.1], eps))
+                    })
+            })
+            && same_transformations(&self.transformations, &other.transformations)
+            && self.activation_kinds == other.activation_kinds
+            && self.input_nodes == other.input_nodes
+            && self.stage_nodes == other.stage_nodes
+            && self.dispatch_by_kind == other.dispatch_by_kind
+            && self.stashed_outputs == other.stashed_outputs
+            && self.weight_groups == other.weight_groups
+            && self.outputs == other.outputs
+            && same_vector_activation(
+                self.output_vector_activation,
+                other.output_vector_activation,
+            )
+    }
+}
+
+/// Exact equality, see [`MatrixFeedforwardEvaluator::approx_eq`] to tolerate rounding differences.
+impl PartialEq for MatrixFeedforwardEvaluator {
+    fn eq(&self, other: &Self) -> bool {
+        self.approx_eq(other, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        edges,
+        network::{net::Net, Fabricator},
+        nodes, MatrixFeedforwardFabricator, SparseMatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn fabricating_twice_yields_equal_evaluators() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 't', 's', 'g'),
+            edges!(0--0.5->2, 1---1.5->3, 2--2.0->4, 3--0.25->4, 0--1.0->4),
+        );
+
+        let first = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let mut second = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert!(first.approx_eq(&second, 1e-12));
+        assert!(first == second);
+
+        second.stages[0][(0, 0)] += 1e-9;
+        assert!(first != second);
+        assert!(first.approx_eq(&second, 1e-6));
+
+        let first = SparseMatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let second = SparseMatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert!(first.approx_eq(&second, 1e-12));
+        assert!(first == second);
+    }
+}
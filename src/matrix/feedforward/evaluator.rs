@@ -1,23 +1,1401 @@
-use nalgebra::DMatrix;
+use std::{collections::HashMap, ops::Index};
 
-use crate::network::{Evaluator, NetworkIO};
+use nalgebra::{DMatrix, DVector, RowDVector};
 
-#[derive(Debug)]
+use crate::network::{
+    net::activations::{GAUSSIAN, INVERSE, LINEAR, RELU, SIGMOID, SQUARED, TANH},
+    ActivationKind, Aggregation, Evaluator, NetworkIO,
+};
+
+/// A column of a stage that pools or multiplies its weighted inputs, see [`Aggregation`].
+///
+/// Neither is a matrix multiplication, so the column is empty in the stage matrix
+/// and its value is set by a separate per-node step before activations are applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolingColumn {
+    pub column: usize,
+    pub aggregation: Aggregation,
+    /// Rows of the previous state with the weight applied to them.
+    pub inputs: Vec<(usize, f64)>,
+}
+
+/// An entry of a stage matrix holding the weight of an edge of a weight group, see [`crate::network::EdgeLike::weight_group`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TiedWeight {
+    pub stage: usize,
+    pub row: usize,
+    pub column: usize,
+    /// Factor the group weight is multiplied by, the reciprocal of the number of inputs for [`Aggregation::Mean`] and `1.0` otherwise.
+    pub scale: f64,
+}
+
+impl PoolingColumn {
+    pub(crate) fn pool(
+        &self,
+        previous: &impl Index<(usize, usize), Output = f64>,
+        row: usize,
+    ) -> f64 {
+        self.aggregation.aggregate(
+            self.inputs
+                .iter()
+                .map(|&(index, weight)| previous[(row, index)] * weight),
+        )
+    }
+
+    // partial derivatives of the pooled value with respect to each entry of `inputs`
+    fn input_gradients(&self, previous: &DMatrix<f64>, row: usize) -> Vec<f64> {
+        let weighted: Vec<f64> = self
+            .inputs
+            .iter()
+            .map(|&(index, weight)| previous[(row, index)] * weight)
+            .collect();
+
+        match self.aggregation {
+            Aggregation::Product => (0..weighted.len())
+                .map(|position| {
+                    let others: f64 = weighted
+                        .iter()
+                        .enumerate()
+                        .filter(|&(other, _)| other != position)
+                        .map(|(_, value)| value)
+                        .product();
+                    others * self.inputs[position].1
+                })
+                .collect(),
+            _ => {
+                // only the input that determined the pooled value receives gradient
+                let pooled = self.pool(previous, row);
+                let selected = weighted.iter().position(|&value| value == pooled);
+                self.inputs
+                    .iter()
+                    .enumerate()
+                    .map(|(position, &(_, weight))| {
+                        if Some(position) == selected {
+                            weight
+                        } else {
+                            0.0
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct MatrixFeedforwardEvaluator {
     pub stages: Vec<DMatrix<f64>>,
     pub transformations: Vec<crate::Transformations>,
+    pub activation_kinds: Vec<Vec<ActivationKind>>,
+    /// Node ids in the order the first stage expects its inputs.
+    pub input_nodes: Vec<usize>,
+    /// Node ids corresponding to the columns of each stage.
+    pub stage_nodes: Vec<Vec<usize>>,
+    /// Apply activations by matching on [`MatrixFeedforwardEvaluator::activation_kinds`] instead of calling [`MatrixFeedforwardEvaluator::transformations`].
+    pub dispatch_by_kind: bool,
+    /// Stage, column and output position of outputs computed before the last stage.
+    ///
+    /// Their values are set aside after the computing stage and reinserted into the output instead of being carried through all following stages.
+    pub stashed_outputs: Vec<(usize, usize, usize)>,
+    /// Columns of each stage that pool their inputs instead of summing them.
+    pub pooling: Vec<Vec<PoolingColumn>>,
+    /// Constant added to the columns of each stage before activations are applied, see [`MatrixFeedforwardEvaluator::fold_constants`].
+    pub biases: Vec<Option<RowDVector<f64>>>,
+    /// Stage matrix entries of every weight group, see [`MatrixFeedforwardEvaluator::set_group_weight`].
+    pub weight_groups: HashMap<usize, Vec<TiedWeight>>,
+    /// Number of outputs of the net, they come first in the last stage followed by any other columns kept there.
+    pub outputs: usize,
+    /// Applied to the outputs after all stages, see [`MatrixFeedforwardEvaluator::set_output_vector_activation`].
+    pub output_vector_activation: Option<super::vector_activation::VectorActivationKind>,
+    /// Durations of every stage of the most recent evaluation, see [`MatrixFeedforwardEvaluator::last_stage_timings`].
+    #[cfg(feature = "profile")]
+    pub stage_timings: super::profile::StageTimings,
+}
+
+impl MatrixFeedforwardEvaluator {
+    /// Evaluates like [`Evaluator::evaluate`] but returns an error instead of panicking if the input length does not match the net.
+    pub fn try_evaluate<T: NetworkIO>(&self, input: T) -> Result<T, &'static str> {
+        let input = NetworkIO::input(input);
+        if input.ncols() != self.input_nodes.len() {
+            return Err("input length does not match number of inputs of the net");
+        }
+
+        Ok(NetworkIO::output(self.propagate(
+            input,
+            &self.transformations,
+            &self.activation_kinds,
+        )))
+    }
+
+    /// Evaluates a single sample given as column vector and returns the outputs as column vector as well.
+    ///
+    /// This is the transpose of the row oriented convention of [`NetworkIO`].
+    pub fn evaluate_column(&self, input: DVector<f64>) -> DVector<f64> {
+        self.propagate(
+            DMatrix::from_row_slice(1, input.len(), input.as_slice()),
+            &self.transformations,
+            &self.activation_kinds,
+        )
+        .row(0)
+        .transpose()
+    }
+
+    /// Evaluates a single sample and returns the outputs together with the state after every stage.
+    ///
+    /// The trace holds a single row matrix per stage whose columns are the nodes listed in [`Self::stage_nodes`] for that stage.
+    /// Values carried past a stage appear in every stage they are carried through, so the same node can show up more than once.
+    /// The last entry equals the output.
+    pub fn evaluate_with_trace(&self, input: Vec<f64>) -> (Vec<f64>, Vec<DMatrix<f64>>) {
+        let mut trace = Vec::with_capacity(self.stages.len());
+        let output = self.propagate_traced(
+            DMatrix::from_row_slice(1, input.len(), &input),
+            &self.transformations,
+            &self.activation_kinds,
+            Some(&mut trace),
+        );
+        (output.iter().cloned().collect(), trace)
+    }
+
+    /// Evaluates a single sample and returns the outputs together with the values of the outputs before their activation was applied.
+    ///
+    /// Pre-activation values include biases and are the pooled values for outputs with pooling aggregations.
+    /// Outputs computed before the last stage report the values of the stage computing them.
+    /// Outputs include the output vector activation, if any, pre-activation values are taken before either activation.
+    pub fn evaluate_with_preactivation(&self, input: Vec<f64>) -> (Vec<f64>, Vec<f64>) {
+        let mut state = DMatrix::from_row_slice(1, input.len(), &input);
+        assert_eq!(
+            state.ncols(),
+            self.input_nodes.len(),
+            "expected {} inputs, got {}",
+            self.input_nodes.len(),
+            state.ncols()
+        );
+
+        let mut preactivation = state.clone();
+        let mut stashed = Vec::with_capacity(self.stashed_outputs.len());
+
+        for (stage, stage_matrix) in self.stages.iter().enumerate() {
+            state = self.apply_stage(stage, state, stage_matrix);
+            preactivation = state.clone();
+            self.activate(
+                &mut state,
+                &self.transformations[stage],
+                &self.activation_kinds[stage],
+            );
+            for &(stashed_stage, column, output) in &self.stashed_outputs {
+                if stashed_stage == stage {
+                    stashed.push((output, state[(0, column)], preactivation[(0, column)]));
+                }
+            }
+        }
+        for (output, value, raw) in stashed {
+            state[(0, output)] = value;
+            preactivation[(0, output)] = raw;
+        }
+        self.apply_output_vector_activation(&mut state);
+
+        (
+            state.iter().cloned().collect(),
+            preactivation.iter().cloned().collect(),
+        )
+    }
+
+    /// Evaluates like [`Evaluator::evaluate`] but applies the given activations instead of the compiled ones for the given node ids.
+    ///
+    /// Carried values are not affected, only the stage computing the node applies the override.
+    pub fn evaluate_with_activation_overrides<T: NetworkIO>(
+        &self,
+        input: T,
+        overrides: &HashMap<usize, fn(f64) -> f64>,
+    ) -> T {
+        let mut transformations = self.transformations.clone();
+        let mut activation_kinds = self.activation_kinds.clone();
+
+        for (stage, (transformations, activation_kinds)) in transformations
+            .iter_mut()
+            .zip(activation_kinds.iter_mut())
+            .enumerate()
+        {
+            for (column, (transformation, activation_kind)) in transformations
+                .iter_mut()
+                .zip(activation_kinds.iter_mut())
+                .enumerate()
+            {
+                if self.is_carry(stage, column) {
+                    continue;
+                }
+                if let Some(&activation) = overrides.get(&self.stage_nodes[stage][column]) {
+                    *transformation = activation;
+                    // make kind dispatch fall back to the override
+                    *activation_kind = ActivationKind::Custom;
+                }
+            }
+        }
+
+        NetworkIO::output(self.propagate(
+            NetworkIO::input(input),
+            &transformations,
+            &activation_kinds,
+        ))
+    }
+
+    /// Replaces the activation of a single column of a stage, see [`Self::stage_nodes`] for the node it computes.
+    ///
+    /// Panics if the stage or column does not exist.
+    pub fn set_activation(&mut self, stage: usize, column: usize, f: fn(f64) -> f64) {
+        self.transformations[stage][column] = f;
+        self.activation_kinds[stage][column] = ActivationKind::from_function(f);
+    }
+
+    /// Sets the weight of every edge of `group` at once, unknown groups are ignored.
+    ///
+    /// Edges into nodes that pool their inputs are not tracked and keep their weight.
+    pub fn set_group_weight(&mut self, group: usize, w: f64) {
+        for tied_weight in self.weight_groups.get(&group).into_iter().flatten() {
+            self.stages[tied_weight.stage][(tied_weight.row, tied_weight.column)] =
+                w * tied_weight.scale;
+        }
+    }
+
+    /// Replaces every activation `from` by `to` and returns how many columns were changed.
+    ///
+    /// Columns that only carry values forward are left alone, so replacing [`LINEAR`] only affects nodes of the net.
+    pub fn replace_activation(&mut self, from: fn(f64) -> f64, to: fn(f64) -> f64) -> usize {
+        let mut replaced = 0;
+        for stage in 0..self.stages.len() {
+            for column in 0..self.transformations[stage].len() {
+                // function pointers are compared by address, like the built-in activations are identified
+                if self.transformations[stage][column] as usize == from as usize
+                    && !self.is_carry(stage, column)
+                {
+                    self.set_activation(stage, column, to);
+                    replaced += 1;
+                }
+            }
+        }
+        replaced
+    }
+
+    /// Tells if every stage only applies linear activations and no pooling or biases, making the whole net a single linear map.
+    pub fn is_linear(&self) -> bool {
+        self.output_vector_activation.is_none()
+            && self
+                .activation_kinds
+                .iter()
+                .flatten()
+                .all(|&kind| kind == ActivationKind::Linear)
+            && self.pooling.iter().all(Vec::is_empty)
+            && self.biases.iter().all(Option::is_none)
+    }
+
+    /// Multiplies all stages into one matrix that maps inputs to outputs, if the net [`MatrixFeedforwardEvaluator::is_linear`].
+    pub fn collapse_linear(&self) -> Option<DMatrix<f64>> {
+        if !self.is_linear() {
+            return None;
+        }
+
+        let mut products = Vec::with_capacity(self.stages.len());
+        let mut product = DMatrix::identity(self.input_nodes.len(), self.input_nodes.len());
+        for stage_matrix in &self.stages {
+            product *= stage_matrix;
+            products.push(product.clone());
+        }
+
+        // stashed outputs are taken from the product up to their computing stage
+        for &(stage, column, output) in &self.stashed_outputs {
+            product.set_column(output, &products[stage].column(column));
+        }
+
+        Some(product)
+    }
+
+    /// Evaluates a single sample held in `buf` and writes the output back into it.
+    ///
+    /// The allocation of `buf` is reused whenever its capacity fits the output, which is always the case if the net has no more outputs than inputs.
+    /// Otherwise `buf` grows like any `Vec` would. Intermediate stage results are still allocated.
+    pub fn evaluate_in_place(&self, buf: &mut Vec<f64>) {
+        let output = self.propagate(
+            DMatrix::from_row_slice(1, buf.len(), buf),
+            &self.transformations,
+            &self.activation_kinds,
+        );
+
+        buf.clear();
+        buf.extend(output.iter());
+    }
+
+    /// Evaluates a single sample given as pairs of input node id and value, inputs that are not given are zero.
+    ///
+    /// Panics if an id is not an input of the net.
+    pub fn evaluate_partial(&self, inputs: &[(usize, f64)]) -> Vec<f64> {
+        let mut input = vec![0.0; self.input_nodes.len()];
+        for &(id, value) in inputs {
+            let position = self
+                .input_nodes
+                .iter()
+                .position(|&node| node == id)
+                .expect("node id not an input of the net");
+            input[position] = value;
+        }
+
+        self.evaluate(input)
+    }
+
+    /// Evaluates a single sample but only computes what is needed for the outputs with the given node ids, returned in the requested order.
+    ///
+    /// Walking back from the requested outputs, only columns that reach them through a nonzero weight or a pooled input are computed.
+    /// Stages are still visited in full, so there is nothing to save where the requested outputs share most of their dependencies
+    /// with the skipped ones, the result is then the same as [`Evaluator::evaluate`] at about the same cost.
+    /// An output vector activation depends on all outputs, so all of them are computed when one is set.
+    ///
+    /// Panics if an id is not an output of the net, columns kept behind the outputs can not be requested.
+    pub fn evaluate_outputs(&self, input: Vec<f64>, output_ids: &[usize]) -> Vec<f64> {
+        let last_nodes = match self.stage_nodes.last() {
+            Some(last_nodes) => last_nodes,
+            None => &self.input_nodes,
+        };
+        // stashed outputs are reinserted at their output position, so they are among these as well
+        let last_nodes = &last_nodes[..self.outputs.min(last_nodes.len())];
+        let positions: Vec<usize> = output_ids
+            .iter()
+            .map(|id| {
+                last_nodes
+                    .iter()
+                    .position(|node| node == id)
+                    .expect("output id not an output of the net")
+            })
+            .collect();
+
+        let needed = if self.output_vector_activation.is_some() {
+            let all_outputs: Vec<usize> = (0..self.outputs).chain(positions.clone()).collect();
+            self.needed_columns(&all_outputs)
+        } else {
+            self.needed_columns(&positions)
+        };
+
+        let mut state = DMatrix::from_row_slice(1, input.len(), &input);
+        let mut stashed = Vec::with_capacity(self.stashed_outputs.len());
+
+        for (stage, stage_matrix) in self.stages.iter().enumerate() {
+            let mut next = DMatrix::zeros(state.nrows(), stage_matrix.ncols());
+            for column in (0..stage_matrix.ncols()).filter(|&column| needed[stage + 1][column]) {
+                let pooling = self.pooling[stage]
+                    .iter()
+                    .find(|pooling| pooling.column == column);
+                for row in 0..state.nrows() {
+                    let mut value = match pooling {
+                        Some(pooling) => pooling.pool(&state, row),
+                        None => state.row(row).transpose().dot(&stage_matrix.column(column)),
+                    };
+                    if let Some(bias) = &self.biases[stage] {
+                        value += bias[column];
+                    }
+                    next[(row, column)] = if self.dispatch_by_kind {
+                        dispatch(
+                            self.activation_kinds[stage][column],
+                            self.transformations[stage][column],
+                            value,
+                        )
+                    } else {
+                        self.transformations[stage][column](value)
+                    };
+                }
+            }
+            for &(stashed_stage, column, output) in &self.stashed_outputs {
+                if stashed_stage == stage {
+                    stashed.push((output, next[(0, column)]));
+                }
+            }
+            state = next;
+        }
+        for (output, value) in stashed {
+            state[(0, output)] = value;
+        }
+        self.apply_output_vector_activation(&mut state);
+
+        positions
+            .into_iter()
+            .map(|position| state[(0, position)])
+            .collect()
+    }
+
+    /// Lists the ids of the inputs each output depends on, indexed by output position.
+    ///
+    /// An output depends on an input if there is a path of nonzero stage matrix entries or pooled inputs between them.
+    /// There is one entry per value returned by [`Evaluator::evaluate`].
+    pub fn output_dependencies(&self) -> Vec<Vec<usize>> {
+        (0..self.output_dim())
+            .map(|position| {
+                self.needed_columns(&[position])[0]
+                    .iter()
+                    .zip(&self.input_nodes)
+                    .filter(|(&needed, _)| needed)
+                    .map(|(_, &id)| id)
+                    .collect()
+            })
+            .collect()
+    }
+
+    // marks every column the outputs at the given positions depend on, walking back from the stage computing them
+    // the first entry marks the inputs, the following ones the columns of each stage
+    fn needed_columns(&self, positions: &[usize]) -> Vec<Vec<bool>> {
+        let mut needed: Vec<Vec<bool>> = std::iter::once(self.input_nodes.len())
+            .chain(self.stages.iter().map(|stage_matrix| stage_matrix.ncols()))
+            .map(|columns| vec![false; columns])
+            .collect();
+        for &position in positions {
+            match self
+                .stashed_outputs
+                .iter()
+                .find(|&&(_, _, output)| output == position)
+            {
+                Some(&(stage, column, _)) => needed[stage + 1][column] = true,
+                None => {
+                    if let Some(last) = needed.last_mut() {
+                        last[position] = true;
+                    }
+                }
+            }
+        }
+        for stage in (0..self.stages.len()).rev() {
+            for column in 0..self.stages[stage].ncols() {
+                if !needed[stage + 1][column] {
+                    continue;
+                }
+                for (row, &weight) in self.stages[stage].column(column).iter().enumerate() {
+                    if weight != 0.0 {
+                        needed[stage][row] = true;
+                    }
+                }
+                for pooling in self.pooling[stage].iter().filter(|p| p.column == column) {
+                    for &(row, _) in &pooling.inputs {
+                        needed[stage][row] = true;
+                    }
+                }
+            }
+        }
+
+        needed
+    }
+
+    /// Adds an edge to the fabricated stages without fabricating the net again.
+    ///
+    /// This only works if `start_id` is already available to the stage computing `end_id`, i.e. the edge fits into a single stage matrix entry.
+    /// Otherwise an error is returned and the net has to be fabricated again. A weight for an already present edge is added to it.
+    /// The end node is assumed to sum its inputs, nodes with [`Aggregation::Mean`] are not recognized as such and end up wrong.
+    pub fn try_add_edge(
+        &mut self,
+        start_id: usize,
+        end_id: usize,
+        weight: f64,
+    ) -> Result<(), &'static str> {
+        // the first stage a node appears in computes it, later ones carry or reinsert it
+        let (stage, column) = self
+            .stage_nodes
+            .iter()
+            .enumerate()
+            .find_map(|(stage, nodes)| {
+                nodes
+                    .iter()
+                    .position(|&node| node == end_id)
+                    .map(|column| (stage, column))
+            })
+            .ok_or("end node not computed by any stage, fabricate again")?;
+
+        if self.is_carry(stage, column) {
+            return Err("end node is an input, fabricate again");
+        }
+        if self.pooling[stage]
+            .iter()
+            .any(|pooling| pooling.column == column)
+        {
+            return Err("end node pools its inputs, fabricate again");
+        }
+
+        let previous_nodes = if stage == 0 {
+            &self.input_nodes
+        } else {
+            &self.stage_nodes[stage - 1]
+        };
+        let row = previous_nodes
+            .iter()
+            .position(|&node| node == start_id)
+            .ok_or(
+                "start node not available to the stage computing the end node, fabricate again",
+            )?;
+
+        self.stages[stage][(row, column)] += weight;
+
+        Ok(())
+    }
+
+    /// Applies `f` to every weight of the compiled stages in place.
+    ///
+    /// Only nonzero entries of columns that compute a node and the weights of pooling columns are passed to `f`,
+    /// the identity entries of carried values are left untouched.
+    pub fn perturb_weights(&mut self, mut f: impl FnMut(f64) -> f64) {
+        for pooling in self.pooling.iter_mut().flatten() {
+            for (_, weight) in pooling.inputs.iter_mut() {
+                if *weight != 0.0 {
+                    *weight = f(*weight);
+                }
+            }
+        }
+        for stage in 0..self.stages.len() {
+            for column in 0..self.stages[stage].ncols() {
+                if self.is_carry(stage, column) {
+                    continue;
+                }
+                for weight in self.stages[stage].column_mut(column).iter_mut() {
+                    if *weight != 0.0 {
+                        *weight = f(*weight);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the gradient of the output with respect to the input.
+    ///
+    /// `output_grad` holds the gradient of some loss with respect to each output, one row per row of `input`.
+    /// The forward pass is repeated to cache the pre-activations, then the gradient is propagated back through the transposed stages.
+    /// Activations without a known derivative, see [`ActivationKind::derivative`], are differentiated numerically by central differences.
+    /// The built-in output vector activations are differentiated exactly, custom ones make this panic.
+    pub fn backward(&self, input: &DMatrix<f64>, output_grad: &DMatrix<f64>) -> DMatrix<f64> {
+        // forward pass caching the inputs and pre-activations of every stage
+        let mut stage_inputs = Vec::with_capacity(self.stages.len());
+        let mut pre_activations = Vec::with_capacity(self.stages.len());
+        let mut state = input.clone();
+        let mut stashed = Vec::with_capacity(self.stashed_outputs.len());
+
+        for (stage, (stage_matrix, transformations)) in
+            self.stages.iter().zip(&self.transformations).enumerate()
+        {
+            stage_inputs.push(state.clone());
+            state = self.apply_stage(stage, state, stage_matrix);
+            pre_activations.push(state.clone());
+            for (mut column, activation) in state.column_iter_mut().zip(transformations) {
+                column.apply(|value| *value = activation(*value));
+            }
+            for &(stashed_stage, column, output) in &self.stashed_outputs {
+                if stashed_stage == stage {
+                    stashed.push((output, state.column(column).clone_owned()));
+                }
+            }
+        }
+        for (output, values) in stashed {
+            state.set_column(output, &values);
+        }
+
+        let mut grad = output_grad.clone();
+        // state holds the outputs before the output vector activation
+        self.output_vector_activation_backward(&state, &mut grad);
+
+        // stashed outputs replace their final column, so their gradient flows into the computing stage instead
+        let mut stashed_grads = Vec::with_capacity(self.stashed_outputs.len());
+
+        for &(stage, column, output) in &self.stashed_outputs {
+            stashed_grads.push((stage, column, grad.column(output).clone_owned()));
+            grad.column_mut(output).fill(0.0);
+        }
+
+        for (stage, ((stage_matrix, pre_activation), (transformations, activation_kinds))) in self
+            .stages
+            .iter()
+            .zip(&pre_activations)
+            .zip(self.transformations.iter().zip(&self.activation_kinds))
+            .enumerate()
+            .rev()
+        {
+            for (stashed_stage, column, stashed_grad) in &stashed_grads {
+                if *stashed_stage == stage {
+                    let mut grad_column = grad.column_mut(*column);
+                    grad_column += stashed_grad;
+                }
+            }
+
+            for ((mut grad_column, pre_activation), (&activation, activation_kind)) in grad
+                .column_iter_mut()
+                .zip(pre_activation.column_iter())
+                .zip(transformations.iter().zip(activation_kinds))
+            {
+                let derivative = activation_kind.derivative();
+                for (grad_value, &value) in grad_column.iter_mut().zip(pre_activation.iter()) {
+                    *grad_value *= match derivative {
+                        Some(derivative) => derivative(value),
+                        None => numerical_derivative(activation, value),
+                    };
+                }
+            }
+
+            let mut previous_grad = &grad * stage_matrix.transpose();
+
+            for pooling in &self.pooling[stage] {
+                for row in 0..grad.nrows() {
+                    let input_gradients = pooling.input_gradients(&stage_inputs[stage], row);
+                    for (&(index, _), input_gradient) in pooling.inputs.iter().zip(input_gradients)
+                    {
+                        previous_grad[(row, index)] += grad[(row, pooling.column)] * input_gradient;
+                    }
+                }
+            }
+
+            grad = previous_grad;
+        }
+
+        grad
+    }
+
+    /// Returns the derivative of every output with respect to every input at a single sample, one row per output and one column per input.
+    ///
+    /// Rows are computed by [`MatrixFeedforwardEvaluator::backward`] with one unit gradient per output,
+    /// all of them in a single batched pass over copies of the sample.
+    ///
+    /// Panics if `input` is not a single row.
+    pub fn jacobian(&self, input: &DMatrix<f64>) -> DMatrix<f64> {
+        assert_eq!(input.nrows(), 1, "expected a single sample");
+
+        let outputs = self.output_dim();
+        let samples = DMatrix::from_fn(outputs, input.ncols(), |_, column| input[(0, column)]);
+
+        self.backward(&samples, &DMatrix::identity(outputs, outputs))
+    }
+
+    // multiplies the state with the stage matrix, fills in pooled columns and adds the bias
+    pub(crate) fn apply_stage(
+        &self,
+        stage: usize,
+        state: DMatrix<f64>,
+        stage_matrix: &DMatrix<f64>,
+    ) -> DMatrix<f64> {
+        let pooling = &self.pooling[stage];
+        let mut next = if pooling.is_empty() {
+            state * stage_matrix
+        } else {
+            let mut next = &state * stage_matrix;
+            for pooling in pooling {
+                for row in 0..state.nrows() {
+                    next[(row, pooling.column)] = pooling.pool(&state, row);
+                }
+            }
+            next
+        };
+        if let Some(bias) = &self.biases[stage] {
+            for mut row in next.row_iter_mut() {
+                row += bias;
+            }
+        }
+        next
+    }
+
+    /// Names the activation applied last to each output, see [`ActivationKind::name`], with `"custom"` for activations that are not built-in.
+    ///
+    /// Outputs computed before the last stage report the activation of the stage computing them.
+    pub fn output_activations(&self) -> Vec<&'static str> {
+        let last_kinds = match self.activation_kinds.last() {
+            Some(last_kinds) => last_kinds,
+            None => return vec!["linear"; self.input_nodes.len()],
+        };
+
+        (0..last_kinds.len())
+            .map(|position| {
+                let kind = match self
+                    .stashed_outputs
+                    .iter()
+                    .find(|&&(_, _, output)| output == position)
+                {
+                    Some(&(stage, column, _)) => self.activation_kinds[stage][column],
+                    None => last_kinds[position],
+                };
+                kind.name().unwrap_or("custom")
+            })
+            .collect()
+    }
+
+    /// Tells when the node with the given id becomes available, `0` for inputs and `stage + 1` for nodes computed by `stages[stage]`.
+    ///
+    /// Nodes carried through later stages report the stage computing them. Returns `None` for ids the evaluator does not know,
+    /// e.g. of nodes that no output depends on.
+    pub fn node_stage(&self, id: usize) -> Option<usize> {
+        if self.input_nodes.contains(&id) {
+            return Some(0);
+        }
+        self.stage_nodes
+            .iter()
+            .enumerate()
+            .find_map(|(stage, nodes)| {
+                nodes
+                    .iter()
+                    .enumerate()
+                    .any(|(column, &node)| node == id && !self.is_carry(stage, column))
+                    .then(|| stage + 1)
+            })
+    }
+
+    /// Counts the columns of all stages that only carry an already available value forward.
+    ///
+    /// Every edge skipping stages adds one such identity column to each stage it skips, so this quantifies the overhead of skip connections.
+    /// Outputs computed before the last stage are set aside instead of carried and do not count.
+    pub fn carry_count(&self) -> usize {
+        let last = self.stages.len().saturating_sub(1);
+        (0..self.stages.len())
+            .flat_map(|stage| (0..self.stages[stage].ncols()).map(move |column| (stage, column)))
+            .filter(|&(stage, column)| {
+                self.is_carry(stage, column)
+                    && !(stage == last
+                        && self
+                            .stashed_outputs
+                            .iter()
+                            .any(|&(_, _, output)| output == column))
+            })
+            .count()
+    }
+
+    /// Tells if a column of a stage only carries an already available value forward.
+    pub(crate) fn is_carry(&self, stage: usize, column: usize) -> bool {
+        let previous_nodes = if stage == 0 {
+            &self.input_nodes
+        } else {
+            &self.stage_nodes[stage - 1]
+        };
+        previous_nodes.contains(&self.stage_nodes[stage][column])
+    }
+
+    // every row of the state is an independent sample, so activations apply per column
+    pub(crate) fn activate(
+        &self,
+        state: &mut DMatrix<f64>,
+        transformations: &[fn(f64) -> f64],
+        activation_kinds: &[ActivationKind],
+    ) {
+        if self.dispatch_by_kind {
+            for ((mut column, &activation_kind), &activation) in state
+                .column_iter_mut()
+                .zip(activation_kinds)
+                .zip(transformations)
+            {
+                column.apply(|value| *value = dispatch(activation_kind, activation, *value));
+            }
+        } else {
+            for (mut column, activation) in state.column_iter_mut().zip(transformations) {
+                column.apply(|value| *value = activation(*value));
+            }
+        }
+    }
+
+    fn propagate(
+        &self,
+        state: DMatrix<f64>,
+        transformations: &[crate::Transformations],
+        activation_kinds: &[Vec<ActivationKind>],
+    ) -> DMatrix<f64> {
+        self.propagate_traced(state, transformations, activation_kinds, None)
+    }
+
+    // performs evaluation by sequentially matrix multiplying and transforming the state with every stage,
+    // pushing the state after every stage onto `trace` if given
+    fn propagate_traced(
+        &self,
+        mut state: DMatrix<f64>,
+        transformations: &[crate::Transformations],
+        activation_kinds: &[Vec<ActivationKind>],
+        mut trace: Option<&mut Vec<DMatrix<f64>>>,
+    ) -> DMatrix<f64> {
+        assert_eq!(
+            state.ncols(),
+            self.input_nodes.len(),
+            "expected {} inputs, got {}",
+            self.input_nodes.len(),
+            state.ncols()
+        );
+
+        let mut stashed = Vec::with_capacity(self.stashed_outputs.len());
+        #[cfg(feature = "profile")]
+        let mut clock = super::profile::StageClock::start(self.stages.len());
+
+        for (stage, ((stage_matrix, transformations), activation_kinds)) in self
+            .stages
+            .iter()
+            .zip(transformations)
+            .zip(activation_kinds)
+            .enumerate()
+        {
+            state = self.apply_stage(stage, state, stage_matrix);
+            self.activate(&mut state, transformations, activation_kinds);
+            for &(stashed_stage, column, output) in &self.stashed_outputs {
+                if stashed_stage == stage {
+                    stashed.push((output, state.column(column).clone_owned()));
+                }
+            }
+            if let Some(trace) = trace.as_mut() {
+                trace.push(state.clone());
+            }
+            #[cfg(feature = "profile")]
+            clock.lap();
+        }
+        for (output, values) in stashed {
+            state.set_column(output, &values);
+        }
+        self.apply_output_vector_activation(&mut state);
+        // the last traced state holds placeholders for stashed outputs, make it equal the result
+        if let Some(last) = trace.and_then(|trace| trace.last_mut()) {
+            last.copy_from(&state);
+        }
+        #[cfg(feature = "profile")]
+        clock.stop(self);
+        state
+    }
+}
+
+fn numerical_derivative(activation: fn(f64) -> f64, value: f64) -> f64 {
+    let step = 1e-6;
+    (activation(value + step) - activation(value - step)) / (2.0 * step)
+}
+
+// calls built-in activations directly so they can be inlined
+#[inline]
+pub(crate) fn dispatch(
+    activation_kind: ActivationKind,
+    activation: fn(f64) -> f64,
+    value: f64,
+) -> f64 {
+    match activation_kind {
+        ActivationKind::Linear => LINEAR(value),
+        ActivationKind::Sigmoid => SIGMOID(value),
+        ActivationKind::Tanh => TANH(value),
+        ActivationKind::Gaussian => GAUSSIAN(value),
+        ActivationKind::Inverse => INVERSE(value),
+        ActivationKind::Relu => RELU(value),
+        ActivationKind::Squared => SQUARED(value),
+        ActivationKind::Custom => activation(value),
+    }
 }
 
 impl Evaluator for MatrixFeedforwardEvaluator {
     fn evaluate<T: NetworkIO>(&self, state: T) -> T {
-        let mut state = NetworkIO::input(state);
-        // performs evaluation by sequentially matrix multiplying and transforming the state with every stage
-        for (stage_matrix, transformations) in self.stages.iter().zip(&self.transformations) {
-            state *= stage_matrix;
-            for (value, activation) in state.iter_mut().zip(transformations) {
-                *value = activation(*value);
+        NetworkIO::output(self.propagate(
+            NetworkIO::input(state),
+            &self.transformations,
+            &self.activation_kinds,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use nalgebra::{dmatrix, DMatrix, DVector};
+
+    use crate::{
+        edges,
+        network::{net::activations, net::Net, Aggregation, Evaluator, Fabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn backward_matches_finite_differences() {
+        let some_net = Net::new(
+            2,
+            2,
+            nodes!('t', 't', 't', 't', 't'),
+            edges!(
+                0--0.3->2,
+                1---0.2->2,
+                0--0.5->3,
+                1--0.1->3,
+                2--0.7->4,
+                1---0.4->4
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        // output 3 is computed early and stashed
+        assert_eq!(evaluator.stashed_outputs.len(), 1);
+
+        let input = dmatrix![0.2, -0.1; -0.3, 0.4];
+        let output_grad = dmatrix![1.0, -0.5; 0.3, 2.0];
+
+        let gradient = evaluator.backward(&input, &output_grad);
+
+        // loss is the sum of outputs weighted by the output gradient
+        let loss = |input: DMatrix<f64>| -> f64 {
+            evaluator.evaluate(input).component_mul(&output_grad).sum()
+        };
+
+        let step = 1e-6;
+        for row in 0..input.nrows() {
+            for column in 0..input.ncols() {
+                let mut plus = input.clone();
+                plus[(row, column)] += step;
+                let mut minus = input.clone();
+                minus[(row, column)] -= step;
+
+                let numerical = (loss(plus) - loss(minus)) / (2.0 * step);
+
+                assert!((gradient[(row, column)] - numerical).abs() < 1e-6);
             }
         }
-        NetworkIO::output(state)
+    }
+
+    #[test]
+    fn backward_routes_gradient_through_pooled_input() {
+        let mut nodes = nodes!('l', 'l', 'l');
+        nodes[2].set_aggregation(Aggregation::Max);
+        let some_net = Net::new(
+            2,
+            1,
+            nodes,
+            edges!(
+                0--2.0->2,
+                1--0.5->2
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(
+            evaluator.backward(&dmatrix![1.0, 1.0; 1.0, 8.0], &dmatrix![1.0; 1.0]),
+            dmatrix![2.0, 0.0; 0.0, 0.5]
+        );
+    }
+
+    #[test]
+    fn collapses_linear_net() {
+        let some_net = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 'l', 'l', 'l'),
+            edges!(
+                0--0.5->2,
+                1---0.5->2,
+                0--2.0->3,
+                2--3.0->4,
+                1--0.25->4
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert!(evaluator.is_linear());
+        assert_eq!(evaluator.stages.len(), 2);
+
+        let collapsed = evaluator.collapse_linear().unwrap();
+
+        for input in &[dmatrix![1.0, 2.0], dmatrix![-3.0, 0.5]] {
+            assert_eq!(input * &collapsed, evaluator.evaluate(input.clone()));
+        }
+
+        let nonlinear_net = Net::new(1, 1, nodes!('l', 't'), edges!(0--1.0->1));
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&nonlinear_net).unwrap();
+        assert!(!evaluator.is_linear());
+        assert!(evaluator.collapse_linear().is_none());
+    }
+
+    #[test]
+    fn backward_through_product_node() {
+        let mut nodes = nodes!('l', 'l', 'l');
+        nodes[2].set_aggregation(Aggregation::Product);
+        let some_net = Net::new(
+            2,
+            1,
+            nodes,
+            edges!(
+                0--2.0->2,
+                1--0.5->2
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        // output is x * y, so the gradient is (y, x)
+        assert_eq!(
+            evaluator.backward(&dmatrix![3.0, 4.0], &dmatrix![1.0]),
+            dmatrix![4.0, 3.0]
+        );
+    }
+
+    #[test]
+    fn column_output_is_transposed_row_output() {
+        let some_net = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 't', 's'),
+            edges!(0--0.5->2, 1---1.5->2, 0--2.0->3),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let column = evaluator.evaluate_column(DVector::from_vec(vec![0.25, 1.0]));
+        let row = evaluator.evaluate(dmatrix![0.25, 1.0]);
+
+        assert_eq!(column, row.transpose());
+    }
+
+    #[test]
+    fn replaces_tanh_by_linear() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 't', 't', 's'),
+            edges!(0--0.5->1, 0--2.0->2, 1--1.0->3, 2--1.0->3, 0--1.0->3),
+        );
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(
+            evaluator.replace_activation(activations::TANH, activations::LINEAR),
+            2
+        );
+        assert_eq!(
+            evaluator.replace_activation(activations::TANH, activations::LINEAR),
+            0
+        );
+        assert_eq!(
+            evaluator.evaluate(dmatrix![1.0]),
+            dmatrix![activations::SIGMOID(0.5 + 2.0 + 1.0)]
+        );
+
+        evaluator.set_activation(1, 0, activations::LINEAR);
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![3.5]);
+    }
+
+    #[test]
+    fn jacobian_matches_finite_differences() {
+        let some_net = Net::new(
+            3,
+            2,
+            nodes!('t', 't', 't', 't', 't', 't'),
+            edges!(
+                0--0.4->3,
+                1---0.6->3,
+                2--0.2->4,
+                3--0.8->4,
+                0---0.3->5,
+                3--0.5->5
+            ),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let input = dmatrix![0.1, -0.4, 0.3];
+        let jacobian = evaluator.jacobian(&input);
+        assert_eq!(jacobian.shape(), (2, 3));
+
+        let step = 1e-6;
+        for column in 0..input.ncols() {
+            let mut plus = input.clone();
+            plus[(0, column)] += step;
+            let mut minus = input.clone();
+            minus[(0, column)] -= step;
+
+            let numerical = (evaluator.evaluate(plus) - evaluator.evaluate(minus)) / (2.0 * step);
+
+            for output in 0..2 {
+                assert!((jacobian[(output, column)] - numerical[(0, output)]).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn updates_tied_weights_at_once() {
+        let mut edges = edges!(0--0.5->2, 2--0.5->3, 1--1.0->3);
+        edges[0].set_weight_group(Some(7));
+        edges[1].set_weight_group(Some(7));
+        let some_net = Net::new(2, 1, nodes!('l', 'l', 'l', 'l'), edges);
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(evaluator.evaluate(dmatrix![4.0, 1.0]), dmatrix![2.0]);
+
+        evaluator.set_group_weight(7, 3.0);
+        // both tied edges, in different stages, now weight by 3.0
+        assert_eq!(evaluator.evaluate(dmatrix![4.0, 1.0]), dmatrix![37.0]);
+
+        evaluator.set_group_weight(8, 0.0);
+        assert_eq!(evaluator.evaluate(dmatrix![4.0, 1.0]), dmatrix![37.0]);
+    }
+
+    #[test]
+    fn returns_outputs_before_activation() {
+        let some_net = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 't', 's', 's'),
+            edges!(0--0.5->2, 1---1.5->2, 2--2.0->3, 0--0.75->4),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let (activated, preactivation) = evaluator.evaluate_with_preactivation(vec![1.0, 0.25]);
+
+        assert_eq!(activated, evaluator.evaluate(vec![1.0, 0.25]));
+        assert_eq!(preactivation[1], 0.75);
+        for (activated, preactivation) in activated.iter().zip(&preactivation) {
+            assert_eq!(*activated, activations::SIGMOID(*preactivation));
+        }
+    }
+
+    #[test]
+    fn records_one_state_per_stage() {
+        let some_net = Net::new(1, 1, nodes!('l', 't', 's'), edges!(0--0.5->1, 1--0.5->2));
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let (output, trace) = evaluator.evaluate_with_trace(vec![1.0]);
+
+        assert_eq!(trace.len(), evaluator.stages.len());
+        assert_eq!(trace.len(), 2);
+        assert_eq!(trace.last().unwrap().as_slice(), output.as_slice());
+    }
+
+    #[test]
+    fn names_output_activations() {
+        let some_net = Net::new(
+            1,
+            2,
+            nodes!('l', 's', 't', 'l'),
+            edges!(0--0.5->1, 1--0.5->3, 0--0.5->2),
+        );
+        let tanh_net = Net::new(1, 1, nodes!('l', 't'), edges!(0--0.5->1));
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let tanh_evaluator = MatrixFeedforwardFabricator::fabricate(&tanh_net).unwrap();
+
+        assert_eq!(tanh_evaluator.output_activations(), vec!["tanh"]);
+        // the tanh output is computed before the last stage
+        assert_eq!(evaluator.output_activations(), vec!["tanh", "linear"]);
+    }
+
+    #[test]
+    fn lists_inputs_each_output_depends_on() {
+        let some_net = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 't', 's', 'l'),
+            edges!(
+                0--0.5->3,
+                1--0.0->3,
+                0--1.0->2,
+                1--1.0->2,
+                2--1.0->4
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(evaluator.output_dependencies(), vec![vec![0], vec![0, 1]]);
+    }
+
+    #[test]
+    fn evaluates_single_output_of_two() {
+        let some_net = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 't', 's', 'l', 'g'),
+            edges!(
+                0--0.5->2,
+                1--0.8->3,
+                2--1.5->4,
+                3---0.7->4,
+                2--0.3->5,
+                0--0.9->5
+            ),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let input = vec![0.7, -1.2];
+        let full: Vec<f64> = evaluator.evaluate(input.clone());
+
+        assert_eq!(
+            evaluator.evaluate_outputs(input.clone(), &[4]),
+            vec![full[0]]
+        );
+        assert_eq!(
+            evaluator.evaluate_outputs(input.clone(), &[5]),
+            vec![full[1]]
+        );
+        assert_eq!(
+            evaluator.evaluate_outputs(input, &[5, 4]),
+            vec![full[1], full[0]]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "output id not an output of the net")]
+    fn rejects_columns_behind_outputs_in_evaluate_outputs() {
+        // node 1 has no consumers and is kept behind the output
+        let some_net = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(0--2.0->1, 0--0.5->2));
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(evaluator.stage_nodes.last().unwrap(), &vec![2, 1]);
+
+        evaluator.evaluate_outputs(vec![3.0], &[1]);
+    }
+
+    #[test]
+    fn adds_edge_within_stage() {
+        let sparse_net = Net::new(2, 1, nodes!('l', 'l', 's'), edges!(0--0.5->2));
+        let full_net = Net::new(2, 1, nodes!('l', 'l', 's'), edges!(0--0.5->2, 1---0.8->2));
+
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&sparse_net).unwrap();
+        let expected = MatrixFeedforwardFabricator::fabricate(&full_net).unwrap();
+
+        evaluator.try_add_edge(1, 2, -0.8).unwrap();
+
+        assert_eq!(
+            evaluator.evaluate(dmatrix![0.7, 1.3]),
+            expected.evaluate(dmatrix![0.7, 1.3])
+        );
+    }
+
+    #[test]
+    fn refuses_edge_spanning_stages() {
+        let some_net = Net::new(1, 1, nodes!('l', 't', 's'), edges!(0--0.5->1, 1--0.5->2));
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(
+            evaluator.try_add_edge(0, 2, 1.0),
+            Err("start node not available to the stage computing the end node, fabricate again")
+        );
+        assert_eq!(
+            evaluator.try_add_edge(1, 0, 1.0),
+            Err("end node not computed by any stage, fabricate again")
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_input_length() {
+        let some_net = Net::new(2, 1, nodes!('l', 'l', 's'), edges!(0--0.5->2, 1--0.5->2));
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(
+            evaluator.try_evaluate(vec![1.0, 2.0, 3.0]),
+            Err("input length does not match number of inputs of the net")
+        );
+        assert!(evaluator.try_evaluate(vec![1.0, 2.0]).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 2 inputs, got 1")]
+    fn panics_on_wrong_input_length() {
+        let some_net = Net::new(2, 1, nodes!('l', 'l', 's'), edges!(0--0.5->2, 1--0.5->2));
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        evaluator.evaluate(vec![1.0]);
+    }
+
+    #[test]
+    fn zero_fills_inputs_not_given() {
+        let some_net = Net::new(2, 1, nodes!('l', 'l', 's'), edges!(0--0.5->2, 1--0.8->2));
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(
+            evaluator.evaluate_partial(&[(1, 0.7)]),
+            evaluator.evaluate(vec![0.0, 0.7])
+        );
+    }
+
+    #[test]
+    fn evaluates_in_place() {
+        let same_size_net = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(
+                0--0.5->2,
+                1--2.0->3
+            ),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&same_size_net).unwrap();
+
+        let mut buf = vec![4.0, 3.0];
+        let allocation = buf.as_ptr();
+        evaluator.evaluate_in_place(&mut buf);
+
+        assert_eq!(buf, vec![2.0, 6.0]);
+        assert_eq!(buf.as_ptr(), allocation);
+
+        let growing_net = Net::new(
+            1,
+            2,
+            nodes!('l', 'l', 'l'),
+            edges!(
+                0--0.5->1,
+                0--2.0->2
+            ),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&growing_net).unwrap();
+
+        let mut buf = vec![4.0];
+        evaluator.evaluate_in_place(&mut buf);
+
+        assert_eq!(buf, vec![2.0, 8.0]);
+    }
+
+    #[test]
+    fn perturbing_weights_scales_linear_output() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l'),
+            edges!(
+                0--0.5->2,
+                1--0.25->2
+            ),
+        );
+
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(evaluator.evaluate(dmatrix![4.0, 4.0]), dmatrix![3.0]);
+
+        evaluator.perturb_weights(|weight| weight * 2.0);
+
+        assert_eq!(evaluator.evaluate(dmatrix![4.0, 4.0]), dmatrix![6.0]);
+    }
+
+    #[test]
+    fn perturbing_weights_skips_carries() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l'),
+            edges!(
+                0--0.5->1,
+                1--0.5->2,
+                0--0.5->2
+            ),
+        );
+
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        evaluator.perturb_weights(|weight| weight * 2.0);
+
+        // 5.0 * 1.0 * 1.0 + 5.0 * 1.0, a scaled carry would contribute twice
+        assert_eq!(evaluator.evaluate(dmatrix![5.0]), dmatrix![10.0]);
+    }
+
+    #[test]
+    fn overrides_activation_of_hidden_node() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l'),
+            edges!(
+                0---1.0->1,
+                1--1.0->2,
+                0--1.0->2
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(evaluator.evaluate(dmatrix![5.0]), dmatrix![0.0]);
+
+        let mut overrides: HashMap<usize, fn(f64) -> f64> = HashMap::new();
+        overrides.insert(1, activations::RELU);
+
+        assert_eq!(
+            evaluator.evaluate_with_activation_overrides(dmatrix![5.0], &overrides),
+            dmatrix![5.0]
+        );
+        // input is carried alongside hidden node and must not be affected
+        overrides.insert(0, activations::INVERSE);
+        assert_eq!(
+            evaluator.evaluate_with_activation_overrides(dmatrix![5.0], &overrides),
+            dmatrix![5.0]
+        );
     }
 }
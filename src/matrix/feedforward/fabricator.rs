@@ -1,9 +1,63 @@
-use crate::network::{EdgeLike, Fabricator, NetworkLike, NodeLike};
+use crate::network::{
+    net::{activations::LINEAR, Edge, Net, Node},
+    ordered_enabled_edges, ActivationKind, Aggregation, EdgeLike, Fabricator, NetworkLike,
+    NodeLike,
+};
 use nalgebra::{DMatrix, DVector};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
+use super::evaluator::{MatrixFeedforwardEvaluator, PoolingColumn, TiedWeight};
+
+/// Compiles a net into a sequence of matrix stages.
+///
+/// Nodes with a pooling or multiplying [`Aggregation`] like [`Aggregation::Max`] or [`Aggregation::Product`] can not be part of a matrix multiplication,
+/// they get an empty column and are filled in by a per-node step, see [`super::evaluator::PoolingColumn`].
+///
+/// Outputs are returned in ascending order of their node ids, not in the order [`NetworkLike::outputs`] lists them,
+/// see [`MatrixFeedforwardFabricator::fabricate_preserving_output_order`] to keep the declared order.
 pub struct MatrixFeedforwardFabricator;
 
+/// Configures the evaluator built by [`MatrixFeedforwardFabricator::fabricate_with`].
+///
+/// [`Fabricator::fabricate`] uses the default options.
+#[derive(Debug, Clone, Default)]
+pub struct MatrixFeedforwardOptions {
+    /// Apply activations by matching on their [`ActivationKind`] instead of calling through `fn` pointers.
+    ///
+    /// This lets the compiler inline the built-in activations, custom activations still go through their `fn` pointer.
+    pub dispatch_by_kind: bool,
+    /// Skip checking edge weights for `NaN` and infinity.
+    ///
+    /// By default fabrication fails on such weights as they silently corrupt every dependent output.
+    /// When allowed, `NaN` weights end up as `0.0` in the stage matrices as `NaN` marks missing connections during fabrication.
+    pub allow_non_finite_weights: bool,
+    /// Inputs that always receive the same value, given as input index and value.
+    ///
+    /// Everything that only depends on them is computed once during fabrication, see [`MatrixFeedforwardEvaluator::fold_constants`].
+    /// The evaluator then expects only the remaining inputs, in their original order.
+    pub constant_inputs: Vec<(usize, f64)>,
+    /// Rescale stages to keep values of linear sections near unity, see [`MatrixFeedforwardEvaluator::condition_linear_stages`].
+    pub condition_linear_stages: bool,
+    /// Apply no activation to outputs, regardless of the activations declared by the output nodes.
+    ///
+    /// Nodes depending on an output receive its raw value as well.
+    pub linear_outputs: bool,
+    /// Return outputs in the order [`NetworkLike::outputs`] lists them instead of ascending by node id.
+    pub preserve_output_order: bool,
+    /// Fuse chains of linear hidden nodes into single edges before fabrication, which can save stages.
+    ///
+    /// A hidden node is fused away only if its activation is linear, it has exactly one enabled incoming and one enabled outgoing edge,
+    /// neither edge belongs to a weight group and its start and end are not already connected. It then only passes on a scaled value, so the edge from start to end
+    /// with the product of both weights computes the same regardless of the aggregation of either node.
+    /// Fused nodes no longer appear in [`MatrixFeedforwardEvaluator::stage_nodes`].
+    pub merge_linear_chains: bool,
+    /// Bias weights by node id, wired from an additional input appended after all inputs of the net.
+    ///
+    /// The evaluator then expects one more input than the net has, which has to be `1.0` for the weights to act as biases.
+    /// Set it as constant input, see [`MatrixFeedforwardOptions::constant_inputs`], to fold it away again.
+    pub implicit_bias: HashMap<usize, f64>,
+}
+
 impl MatrixFeedforwardFabricator {
     fn get_matrix(dynamic_matrix: Vec<Vec<f64>>) -> DMatrix<f64> {
         let columns = dynamic_matrix
@@ -13,20 +67,39 @@ impl MatrixFeedforwardFabricator {
 
         DMatrix::from_columns(&columns)
     }
-}
 
-impl<N, E> Fabricator<N, E> for MatrixFeedforwardFabricator
-where
-    N: NodeLike,
-    E: EdgeLike,
-{
-    type Output = super::evaluator::MatrixFeedforwardEvaluator;
+    /// Fabricates like [`Fabricator::fabricate`] but configured by the given options.
+    pub fn fabricate_with<N: NodeLike, E: EdgeLike>(
+        net: &impl NetworkLike<N, E>,
+        options: &MatrixFeedforwardOptions,
+    ) -> Result<MatrixFeedforwardEvaluator, &'static str> {
+        if !options.implicit_bias.is_empty() {
+            return Self::fabricate_with(
+                &Self::add_bias_input(net, &options.implicit_bias)?,
+                &MatrixFeedforwardOptions {
+                    implicit_bias: HashMap::new(),
+                    ..options.clone()
+                },
+            );
+        }
+        if options.merge_linear_chains {
+            return Self::fabricate_with(
+                &Self::merge_linear_chains(net),
+                &MatrixFeedforwardOptions {
+                    merge_linear_chains: false,
+                    ..options.clone()
+                },
+            );
+        }
 
-    fn fabricate(net: &impl NetworkLike<N, E>) -> Result<Self::Output, &'static str> {
         // build dependency graph by collecting incoming edges per node
-        let mut dependency_graph: HashMap<usize, Vec<&E>> = HashMap::new();
+        // ordered by node id so fabrication is reproducible
+        let mut dependency_graph: BTreeMap<usize, Vec<&E>> = BTreeMap::new();
 
-        for edge in net.edges() {
+        for edge in ordered_enabled_edges(net) {
+            if !options.allow_non_finite_weights && !edge.weight().is_finite() {
+                return Err("non-finite edge weight present, net invalid");
+            }
             dependency_graph
                 .entry(edge.end())
                 .and_modify(|dependencies| dependencies.push(edge))
@@ -40,38 +113,146 @@ where
         // keep track of dependencies present
         let mut dependency_count = dependency_graph.len();
 
+        // look up nodes by id once instead of scanning all nodes for every computed node
+        let nodes_by_id: HashMap<usize, &N> = net
+            .nodes()
+            .into_iter()
+            .map(|node| (node.id(), node))
+            .collect();
+
         // println!("initial dependency_graph {:#?}", dependency_graph);
 
         // contains list of matrices (stages) that form the computable net
         let mut compute_stages: Vec<crate::Matrix> = Vec::new();
         // contains activation functions corresponding to each stage
         let mut stage_transformations: Vec<crate::Transformations> = Vec::new();
+        // contains the kinds of the activation functions corresponding to each stage
+        let mut stage_activation_kinds: Vec<Vec<ActivationKind>> = Vec::new();
         // set available nodes a.k.a net input
         let mut available_nodes = net.inputs();
         // sort via Ord implementation of provided nodes to guarantee each input will be processed by the same node every time
         available_nodes.sort_unstable();
         // reduce nodes to ids
         let mut available_nodes: Vec<usize> = available_nodes.iter().map(|n| n.id()).collect();
+        // remember input order
+        let input_nodes = available_nodes.clone();
+        // contains node ids corresponding to the columns of each stage
+        let mut stage_nodes: Vec<Vec<usize>> = Vec::new();
+        // contains stage and column of outputs computed before the last stage
+        let mut stashed_nodes: HashMap<usize, (usize, usize)> = HashMap::new();
+        // contains stage, column and output position of outputs to reinsert after the last stage
+        let mut stashed_outputs: Vec<(usize, usize, usize)> = Vec::new();
+        // contains the columns of each stage that pool their inputs instead of summing them
+        let mut stage_pooling: Vec<Vec<PoolingColumn>> = Vec::new();
+        // group, stage, row, node and scale of every tied weight, columns are looked up once the stages are final
+        let mut tied_weights: Vec<(usize, usize, usize, usize, f64)> = Vec::new();
 
         // println!("available_nodes {:?}", available_nodes);
 
         // set wanted nodes a.k.a net output
         let mut wanted_nodes = net.outputs();
         // sort via Ord implementation of provided nodes to guarantee each output will appear in the same order every time
-        wanted_nodes.sort_unstable();
+        if !options.preserve_output_order {
+            wanted_nodes.sort_unstable();
+        }
         // reduce nodes to ids
         let wanted_nodes: Vec<usize> = wanted_nodes.iter().map(|n| n.id()).collect();
+        // an output listed twice would claim two columns in the reordering of the last stage
+        let mut distinct_wanted_nodes = HashSet::new();
+        if !wanted_nodes
+            .iter()
+            .all(|id| distinct_wanted_nodes.insert(*id))
+        {
+            return Err("duplicate output ids present, net invalid");
+        }
 
         // println!("wanted_nodes {:?}", wanted_nodes);
 
+        // minimal seeds only connect inputs directly to outputs, their single stage is built without resolving dependencies
+        let input_ids: HashSet<usize> = input_nodes.iter().cloned().collect();
+        let is_direct = net.hidden().is_empty()
+            && dependency_graph.len() == wanted_nodes.len()
+            && wanted_nodes.iter().all(|id| {
+                !input_ids.contains(id)
+                    && nodes_by_id[id].aggregation().is_linear()
+                    && dependency_graph.get(id).is_some_and(|dependencies| {
+                        dependencies
+                            .iter()
+                            .all(|edge| input_ids.contains(&edge.start()))
+                    })
+            });
+        if is_direct {
+            let input_indices: HashMap<usize, usize> = input_nodes
+                .iter()
+                .enumerate()
+                .map(|(index, &id)| (id, index))
+                .collect();
+            let mut stage_matrix: crate::Matrix = Vec::new();
+            let mut transformations: crate::Transformations = Vec::new();
+            let mut activation_kinds: Vec<ActivationKind> = Vec::new();
+
+            for id in &wanted_nodes {
+                let mut column = vec![f64::NAN; input_nodes.len()];
+                for edge in &dependency_graph[id] {
+                    column[input_indices[&edge.start()]] = edge.weight();
+                }
+                let node = nodes_by_id[id];
+                // scale and fill exactly like the general path below
+                let count = column.iter().filter(|weight| !weight.is_nan()).count();
+                let scale = if node.aggregation() == Aggregation::Mean {
+                    1.0 / count as f64
+                } else {
+                    1.0
+                };
+                for edge in &dependency_graph[id] {
+                    if let Some(group) = edge.weight_group() {
+                        tied_weights.push((group, 0, input_indices[&edge.start()], *id, scale));
+                    }
+                }
+                for weight in &mut column {
+                    if weight.is_nan() {
+                        *weight = 0.0;
+                    } else if node.aggregation() == Aggregation::Mean {
+                        *weight /= count as f64;
+                    }
+                }
+                stage_matrix.push(column);
+                if options.linear_outputs {
+                    transformations.push(LINEAR);
+                    activation_kinds.push(ActivationKind::Linear);
+                } else {
+                    transformations.push(node.activation());
+                    activation_kinds.push(node.activation_kind());
+                }
+            }
+
+            compute_stages.push(stage_matrix);
+            stage_transformations.push(transformations);
+            stage_activation_kinds.push(activation_kinds);
+            stage_pooling.push(Vec::new());
+            stage_nodes.push(wanted_nodes.clone());
+            dependency_graph.clear();
+        }
+
         // gather compute stages by finding computable nodes and required carries until all dependencies are resolved
         while !dependency_graph.is_empty() {
             // setup new compute stage
             let mut stage_matrix: crate::Matrix = Vec::new();
             // setup new transformations
             let mut transformations: crate::Transformations = Vec::new();
+            // setup new activation kinds
+            let mut activation_kinds: Vec<ActivationKind> = Vec::new();
             // list of nodes becoming available by compute stage
             let mut next_available_nodes: Vec<usize> = Vec::new();
+            // nodes of this stage that pool their inputs, with the column their values end up in set later
+            let mut pooled_nodes: Vec<(usize, PoolingColumn)> = Vec::new();
+
+            // position of every available node in the state entering this stage
+            let available_indices: HashMap<usize, usize> = available_nodes
+                .iter()
+                .enumerate()
+                .map(|(index, &id)| (id, index))
+                .collect();
 
             for (&dependent_node, dependencies) in dependency_graph.iter() {
                 // marker if all dependencies are available
@@ -80,20 +261,58 @@ where
                 let mut compute_or_carry = vec![f64::NAN; available_nodes.len()];
                 // check every dependency
                 for &dependency in dependencies {
-                    let mut found = false;
-                    for (index, &id) in available_nodes.iter().enumerate() {
-                        if dependency.start() == id {
-                            // add weight to compute vector at position of input
-                            compute_or_carry[index] = dependency.weight();
-                            found = true;
-                        }
-                    }
-                    // if any dependency is not found the node is not computable yet
-                    if !found {
-                        computable = false;
+                    match available_indices.get(&dependency.start()) {
+                        // add weight to compute vector at position of input
+                        Some(&index) => compute_or_carry[index] = dependency.weight(),
+                        // if any dependency is not found the node is not computable yet
+                        None => computable = false,
                     }
                 }
                 if computable {
+                    let node = nodes_by_id[&dependent_node];
+                    let aggregation = node.aggregation();
+                    if aggregation.is_linear() {
+                        let scale = if aggregation == Aggregation::Mean {
+                            1.0 / compute_or_carry.iter().filter(|n| !n.is_nan()).count() as f64
+                        } else {
+                            1.0
+                        };
+                        for dependency in dependencies {
+                            if let Some(group) = dependency.weight_group() {
+                                tied_weights.push((
+                                    group,
+                                    compute_stages.len(),
+                                    available_indices[&dependency.start()],
+                                    dependent_node,
+                                    scale,
+                                ));
+                            }
+                        }
+                    }
+                    if aggregation == Aggregation::Mean {
+                        // a mean is a weighted sum with scaled down weights
+                        let count = compute_or_carry.iter().filter(|n| !n.is_nan()).count();
+                        for n in &mut compute_or_carry {
+                            *n /= count as f64;
+                        }
+                    } else if !aggregation.is_linear() {
+                        // pooling can not be expressed by the stage matrix, the column stays empty
+                        let inputs = compute_or_carry
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, weight)| !weight.is_nan())
+                            .map(|(index, &weight)| (index, weight))
+                            .collect();
+                        pooled_nodes.push((
+                            dependent_node,
+                            PoolingColumn {
+                                column: 0,
+                                aggregation,
+                                inputs,
+                            },
+                        ));
+                        compute_or_carry = vec![0.0; available_nodes.len()];
+                    }
                     // replace NAN with 0.0
                     for n in &mut compute_or_carry {
                         if n.is_nan() {
@@ -103,21 +322,21 @@ where
                     // add vec to compute stage
                     stage_matrix.push(compute_or_carry);
                     // add activation function to stage transformations
-                    transformations.push(
-                        net.nodes()
-                            .iter()
-                            .find(|&node| node.id() == dependent_node)
-                            .unwrap()
-                            .activation(),
-                    );
+                    if options.linear_outputs && wanted_nodes.contains(&dependent_node) {
+                        transformations.push(LINEAR);
+                        activation_kinds.push(ActivationKind::Linear);
+                    } else {
+                        transformations.push(node.activation());
+                        activation_kinds.push(node.activation_kind());
+                    }
                     // mark node as available in next iteration
                     next_available_nodes.push(dependent_node);
                 } else {
                     // figure out carries
                     for (index, &weight) in compute_or_carry.iter().enumerate() {
                         // if there is some partial dependency that is not carried yet
-                        if !next_available_nodes.contains(&available_nodes[index])
-                            && !weight.is_nan()
+                        if !weight.is_nan()
+                            && !next_available_nodes.contains(&available_nodes[index])
                         {
                             let mut carry = vec![0.0; available_nodes.len()];
                             carry[index] = 1.0;
@@ -125,6 +344,7 @@ where
                             stage_matrix.push(carry);
                             // add identity function for carried vector
                             transformations.push(|val| val);
+                            activation_kinds.push(ActivationKind::Linear);
                             // add node as available
                             next_available_nodes.push(available_nodes[index]);
                         }
@@ -136,6 +356,13 @@ where
             for wanted_node in wanted_nodes.iter() {
                 for (index, available_node) in available_nodes.iter().enumerate() {
                     if available_node == wanted_node {
+                        // set computed outputs aside instead of carrying them through every following stage
+                        if !compute_stages.is_empty() {
+                            stashed_nodes
+                                .entry(*wanted_node)
+                                .or_insert((compute_stages.len() - 1, index));
+                            continue;
+                        }
                         // carry only if not carried already
                         if !next_available_nodes.contains(available_node) {
                             let mut carry = vec![0.0; available_nodes.len()];
@@ -144,6 +371,7 @@ where
                             stage_matrix.push(carry);
                             // add identity function for carried vector
                             transformations.push(|val| val);
+                            activation_kinds.push(ActivationKind::Linear);
                             // add node as available
                             next_available_nodes.push(*available_node);
                         }
@@ -165,57 +393,319 @@ where
 
             // println!("next_available_nodes {:?}", next_available_nodes);
 
-            // reorder last stage according to net output order (invalidates next_available_nodes order which wont be used after this point)
+            // reorder last stage according to net output order
             if dependency_graph.is_empty() {
                 // println!("stage_matrix {:?}", stage_matrix);
 
-                let mut reordered_matrix = stage_matrix.clone();
-                let mut reordered_transformations = transformations.clone();
-
-                let mut matched_wanted_count = 0;
-
-                for ((available_node, column), transformation) in next_available_nodes
-                    .iter()
-                    .zip(stage_matrix.into_iter())
-                    .zip(transformations.into_iter())
-                {
-                    for (index, wanted_node) in wanted_nodes.iter().enumerate() {
-                        if available_node == wanted_node {
-                            reordered_matrix[index] = column;
-                            reordered_transformations[index] = transformation;
-                            matched_wanted_count += 1;
-                            break;
-                        }
+                let mut reordered_nodes = Vec::new();
+                let mut reordered_matrix = Vec::new();
+                let mut reordered_transformations: crate::Transformations = Vec::new();
+                let mut reordered_activation_kinds = Vec::new();
+
+                for (output_index, wanted_node) in wanted_nodes.iter().enumerate() {
+                    if let Some(index) = next_available_nodes
+                        .iter()
+                        .position(|available_node| available_node == wanted_node)
+                    {
+                        reordered_matrix.push(stage_matrix[index].clone());
+                        reordered_transformations.push(transformations[index]);
+                        reordered_activation_kinds.push(activation_kinds[index]);
+                    } else if let Some(&(stage, column)) = stashed_nodes.get(wanted_node) {
+                        // leave an empty column to reinsert the stashed value after evaluation
+                        reordered_matrix.push(vec![0.0; available_nodes.len()]);
+                        reordered_transformations.push(|val| val);
+                        reordered_activation_kinds.push(ActivationKind::Linear);
+                        stashed_outputs.push((stage, column, output_index));
+                    } else {
+                        return Err(
+                            "dependencies resolved but not all outputs computable, net invalid",
+                        );
                     }
+                    reordered_nodes.push(*wanted_node);
                 }
 
-                if matched_wanted_count < wanted_nodes.len() {
-                    return Err(
-                        "dependencies resolved but not all outputs computable, net invalid",
-                    );
+                // keep any remaining columns behind the outputs
+                for (index, available_node) in next_available_nodes.iter().enumerate() {
+                    if !wanted_nodes.contains(available_node) {
+                        reordered_nodes.push(*available_node);
+                        reordered_matrix.push(stage_matrix[index].clone());
+                        reordered_transformations.push(transformations[index]);
+                        reordered_activation_kinds.push(activation_kinds[index]);
+                    }
                 }
 
                 // println!("reordered_matrix {:?}", reordered_matrix);
 
+                next_available_nodes = reordered_nodes;
                 stage_matrix = reordered_matrix;
                 transformations = reordered_transformations;
+                activation_kinds = reordered_activation_kinds;
             }
 
+            // locate pooled nodes now that the column order is final
+            stage_pooling.push(
+                pooled_nodes
+                    .into_iter()
+                    .map(|(node, mut pooling)| {
+                        pooling.column = next_available_nodes
+                            .iter()
+                            .position(|&id| id == node)
+                            .unwrap();
+                        pooling
+                    })
+                    .collect(),
+            );
+
             // add resolved dependencies and transformations to compute stages
             compute_stages.push(stage_matrix);
             stage_transformations.push(transformations);
+            stage_activation_kinds.push(activation_kinds);
+            stage_nodes.push(next_available_nodes.clone());
 
             // set available nodes for next iteration
             available_nodes = next_available_nodes;
         }
 
-        Ok(super::evaluator::MatrixFeedforwardEvaluator {
+        let biases = vec![None; stage_nodes.len()];
+
+        let mut weight_groups: HashMap<usize, Vec<TiedWeight>> = HashMap::new();
+        for (group, stage, row, node, scale) in tied_weights {
+            let column = stage_nodes[stage]
+                .iter()
+                .position(|&id| id == node)
+                .unwrap();
+            weight_groups.entry(group).or_default().push(TiedWeight {
+                stage,
+                row,
+                column,
+                scale,
+            });
+        }
+
+        let evaluator = MatrixFeedforwardEvaluator {
             stages: compute_stages
                 .into_iter()
                 .map(MatrixFeedforwardFabricator::get_matrix)
                 .collect(),
             transformations: stage_transformations,
-        })
+            activation_kinds: stage_activation_kinds,
+            input_nodes,
+            stage_nodes,
+            dispatch_by_kind: options.dispatch_by_kind,
+            stashed_outputs,
+            pooling: stage_pooling,
+            biases,
+            weight_groups,
+            outputs: wanted_nodes.len(),
+            output_vector_activation: None,
+            #[cfg(feature = "profile")]
+            stage_timings: Default::default(),
+        };
+
+        let mut evaluator = if options.constant_inputs.is_empty() {
+            evaluator
+        } else {
+            evaluator.fold_constants(&options.constant_inputs)?
+        };
+        if options.condition_linear_stages {
+            evaluator.condition_linear_stages();
+        }
+
+        Ok(evaluator)
+    }
+
+    /// Fabricates like [`Fabricator::fabricate`] but returns outputs in the order [`NetworkLike::outputs`] lists them.
+    pub fn fabricate_preserving_output_order<N: NodeLike, E: EdgeLike>(
+        net: &impl NetworkLike<N, E>,
+    ) -> Result<MatrixFeedforwardEvaluator, &'static str> {
+        Self::fabricate_with(
+            net,
+            &MatrixFeedforwardOptions {
+                preserve_output_order: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    // copies the net with an additional input after all others that is connected to the given nodes with the given weights
+    fn add_bias_input<N: NodeLike, E: EdgeLike>(
+        net: &impl NetworkLike<N, E>,
+        bias_weights: &HashMap<usize, f64>,
+    ) -> Result<Net, &'static str> {
+        let input_ids: HashSet<usize> = net.inputs().iter().map(|node| node.id()).collect();
+        let node_ids: HashSet<usize> = net.nodes().iter().map(|node| node.id()).collect();
+        if bias_weights
+            .keys()
+            .any(|id| input_ids.contains(id) || !node_ids.contains(id))
+        {
+            return Err("implicit bias for input or unknown node, net invalid");
+        }
+
+        // inputs are ordered by id, so the bias input needs the highest one to come last
+        let bias_id = node_ids
+            .iter()
+            .max()
+            .map_or(Some(0), |id| id.checked_add(1))
+            .ok_or("no id left for bias input, net invalid")?;
+
+        let mut biased: Vec<(usize, f64)> = bias_weights
+            .iter()
+            .map(|(&id, &weight)| (id, weight))
+            .collect();
+        biased.sort_unstable_by_key(|&(id, _)| id);
+
+        Ok(Net::new(
+            net.inputs().len() + 1,
+            net.outputs().len(),
+            net.inputs()
+                .into_iter()
+                .map(Node::copy_of)
+                .chain(std::iter::once(Node::new(bias_id, LINEAR)))
+                .chain(net.hidden().into_iter().map(Node::copy_of))
+                .chain(net.outputs().into_iter().map(Node::copy_of))
+                .collect(),
+            ordered_enabled_edges(net)
+                .into_iter()
+                .map(Edge::copy_of)
+                .chain(
+                    biased
+                        .into_iter()
+                        .map(|(id, weight)| Edge::new(bias_id, id, weight)),
+                )
+                .collect(),
+        ))
+    }
+
+    // copies the net with linear hidden nodes passing a single value fused into one edge, see `MatrixFeedforwardOptions::merge_linear_chains`
+    fn merge_linear_chains<N: NodeLike, E: EdgeLike>(net: &impl NetworkLike<N, E>) -> Net {
+        let mut edges: Vec<Edge> = ordered_enabled_edges(net)
+            .into_iter()
+            .map(Edge::copy_of)
+            .collect();
+
+        let mut fused = HashSet::new();
+        for node in net.hidden() {
+            if node.activation_kind() != ActivationKind::Linear {
+                continue;
+            }
+            let incoming: Vec<usize> = (0..edges.len())
+                .filter(|&index| edges[index].end() == node.id())
+                .collect();
+            let outgoing: Vec<usize> = (0..edges.len())
+                .filter(|&index| edges[index].start() == node.id())
+                .collect();
+            if let ([incoming], [outgoing]) = (&incoming[..], &outgoing[..]) {
+                // a fused weight can not follow updates of tied weights, see `MatrixFeedforwardEvaluator::set_group_weight`
+                if edges[*incoming].weight_group().is_some()
+                    || edges[*outgoing].weight_group().is_some()
+                {
+                    continue;
+                }
+                let (start, end) = (edges[*incoming].start(), edges[*outgoing].end());
+                if edges
+                    .iter()
+                    .any(|edge| edge.start() == start && edge.end() == end)
+                {
+                    continue;
+                }
+                let weight = edges[*incoming].weight() * edges[*outgoing].weight();
+                // replace the incoming edge and drop the outgoing one, keeping the order of all others
+                edges[*incoming] = Edge::new(start, end, weight);
+                edges.remove(*outgoing);
+                fused.insert(node.id());
+            }
+        }
+
+        Net::new(
+            net.inputs().len(),
+            net.outputs().len(),
+            net.nodes()
+                .into_iter()
+                .filter(|node| !fused.contains(&node.id()))
+                .map(Node::copy_of)
+                .collect(),
+            edges,
+        )
+    }
+
+    /// Fabricates the part of the net that is computable instead of failing when some outputs can not be computed.
+    ///
+    /// Returns the evaluator together with the ids of all outputs that are not computable from the inputs.
+    /// The evaluator only has the computable outputs, so it returns fewer values than the net has outputs if any id is reported.
+    /// The remaining outputs keep their relative order.
+    pub fn fabricate_partial<N: NodeLike, E: EdgeLike>(
+        net: &impl NetworkLike<N, E>,
+    ) -> Result<(MatrixFeedforwardEvaluator, Vec<usize>), &'static str> {
+        let edges = net
+            .edges()
+            .into_iter()
+            .filter(|edge| edge.enabled())
+            .collect::<Vec<_>>();
+
+        // a node is computable once all its incoming edges start at computable nodes
+        let mut computable: HashSet<usize> = net.inputs().iter().map(|node| node.id()).collect();
+        loop {
+            let resolved = net
+                .nodes()
+                .into_iter()
+                .map(|node| node.id())
+                .filter(|id| !computable.contains(id))
+                .filter(|&id| {
+                    let mut incoming = edges.iter().filter(|edge| edge.end() == id).peekable();
+                    incoming.peek().is_some()
+                        && incoming.all(|edge| computable.contains(&edge.start()))
+                })
+                .collect::<Vec<_>>();
+
+            if resolved.is_empty() {
+                break;
+            }
+            computable.extend(resolved);
+        }
+
+        let mut outputs = net.outputs();
+        outputs.sort_unstable();
+        let (outputs, uncomputable): (Vec<&N>, Vec<&N>) = outputs
+            .into_iter()
+            .partition(|node| computable.contains(&node.id()));
+
+        let hidden = net
+            .hidden()
+            .into_iter()
+            .filter(|node| computable.contains(&node.id()));
+
+        let partial = Net::new(
+            net.inputs().len(),
+            outputs.len(),
+            net.inputs()
+                .into_iter()
+                .chain(hidden)
+                .chain(outputs)
+                .map(Node::copy_of)
+                .collect(),
+            edges
+                .iter()
+                .filter(|edge| computable.contains(&edge.end()))
+                .map(|edge| Edge::copy_of(*edge))
+                .collect(),
+        );
+
+        Ok((
+            Self::fabricate(&partial)?,
+            uncomputable.iter().map(|node| node.id()).collect(),
+        ))
+    }
+}
+
+impl<N, E> Fabricator<N, E> for MatrixFeedforwardFabricator
+where
+    N: NodeLike,
+    E: EdgeLike,
+{
+    type Output = MatrixFeedforwardEvaluator;
+
+    fn fabricate(net: &impl NetworkLike<N, E>) -> Result<Self::Output, &'static str> {
+        Self::fabricate_with(net, &MatrixFeedforwardOptions::default())
     }
 }
 
@@ -223,13 +713,226 @@ where
 mod tests {
     use nalgebra::dmatrix;
 
-    use super::MatrixFeedforwardFabricator;
+    use super::{MatrixFeedforwardFabricator, MatrixFeedforwardOptions};
     use crate::{
         edges,
-        network::{net::Net, Evaluator, Fabricator},
+        network::{
+            net::{activations, Edge, Net, Node},
+            ActivationKind, Aggregation, Evaluator, Fabricator,
+        },
         nodes,
     };
 
+    #[test]
+    fn max_aggregation_over_two_inputs() {
+        let mut nodes = nodes!('l', 'l', 'l', 'l', 'l');
+        nodes[2].set_aggregation(Aggregation::Max);
+        nodes[3].set_aggregation(Aggregation::Min);
+        nodes[4].set_aggregation(Aggregation::Mean);
+        let some_net = Net::new(
+            2,
+            3,
+            nodes,
+            edges!(
+                0--1.0->2,
+                1--0.5->2,
+                0--1.0->3,
+                1--0.5->3,
+                0--1.0->4,
+                1--0.5->4
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(
+            evaluator.evaluate(dmatrix![1.0, 4.0; 3.0, 4.0]),
+            dmatrix![2.0, 1.0, 1.5; 3.0, 2.0, 2.5]
+        );
+    }
+
+    #[test]
+    fn rejects_non_finite_weights() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l'),
+            vec![Edge::new(0, 2, 0.5), Edge::new(1, 2, f64::NAN)],
+        );
+
+        assert_eq!(
+            MatrixFeedforwardFabricator::fabricate(&some_net).err(),
+            Some("non-finite edge weight present, net invalid")
+        );
+
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l'),
+            vec![Edge::new(0, 2, 0.5), Edge::new(1, 2, f64::INFINITY)],
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate_with(
+            &some_net,
+            &MatrixFeedforwardOptions {
+                allow_non_finite_weights: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert!(evaluator.evaluate(dmatrix![1.0f64, 1.0])[0].is_infinite());
+    }
+
+    #[test]
+    fn fabricates_bitwise_identical_stages() {
+        let fabricate = || {
+            let some_net = Net::new(
+                2,
+                2,
+                nodes!('l', 'l', 't', 's', 'g', 'l', 'l'),
+                edges!(
+                    0--0.5->2,
+                    1--0.3->3,
+                    0--0.1->4,
+                    2--0.7->5,
+                    3--0.2->5,
+                    4--0.9->6,
+                    1--0.4->6,
+                    0--0.6->6
+                ),
+            );
+            MatrixFeedforwardFabricator::fabricate(&some_net).unwrap()
+        };
+
+        let reference = fabricate();
+
+        for _ in 0..10 {
+            let evaluator = fabricate();
+            assert_eq!(evaluator.stage_nodes, reference.stage_nodes);
+            for (stage, reference_stage) in evaluator.stages.iter().zip(&reference.stages) {
+                assert_eq!(stage.shape(), reference_stage.shape());
+                assert!(stage
+                    .iter()
+                    .zip(reference_stage.iter())
+                    .all(|(a, b)| a.to_bits() == b.to_bits()));
+            }
+        }
+    }
+
+    #[test]
+    fn product_node_multiplies_inputs() {
+        let mut nodes = nodes!('l', 'l', 'l');
+        nodes[2].set_aggregation(Aggregation::Product);
+        let some_net = Net::new(
+            2,
+            1,
+            nodes,
+            edges!(
+                0--1.0->2,
+                1--1.0->2
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(
+            evaluator.evaluate(dmatrix![3.0, 4.0; -0.5, 2.0]),
+            dmatrix![12.0; -1.0]
+        );
+    }
+
+    #[test]
+    fn skips_disabled_edges() {
+        let enabled_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l'),
+            edges!(
+                0--1.0->2,
+                1--1.0->2
+            ),
+        );
+
+        let mut edges = edges!(
+            0--1.0->2,
+            1--1.0->2
+        );
+        edges[1].set_enabled(false);
+        let disabled_net = Net::new(2, 1, nodes!('l', 'l', 'l'), edges);
+
+        let enabled = MatrixFeedforwardFabricator::fabricate(&enabled_net).unwrap();
+        let disabled = MatrixFeedforwardFabricator::fabricate(&disabled_net).unwrap();
+
+        assert_eq!(enabled.evaluate(dmatrix![2.0, 3.0]), dmatrix![5.0]);
+        assert_eq!(disabled.evaluate(dmatrix![2.0, 3.0]), dmatrix![2.0]);
+    }
+
+    #[test]
+    fn linear_outputs_bypass_output_activation() {
+        let some_net = Net::new(1, 1, nodes!('l', 't', 's'), edges!(0--1.0->1, 1--2.0->2));
+
+        let activated = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let raw = MatrixFeedforwardFabricator::fabricate_with(
+            &some_net,
+            &MatrixFeedforwardOptions {
+                linear_outputs: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let hidden = activations::TANH(0.5);
+        assert_eq!(
+            activated.evaluate(dmatrix![0.5]),
+            dmatrix![activations::SIGMOID(2.0 * hidden)]
+        );
+        assert_eq!(raw.evaluate(dmatrix![0.5]), dmatrix![2.0 * hidden]);
+    }
+
+    #[test]
+    fn fabrication_is_independent_of_node_order() {
+        let edges = || {
+            edges!(
+                0--0.5->2,
+                1---0.5->2,
+                0--1.5->3,
+                2--0.75->4,
+                3--0.25->4,
+                1--2.0->5
+            )
+        };
+        let ordered = Net::new(2, 2, nodes!('l', 'l', 't', 'g', 's', 'r'), edges());
+        let mut reversed_nodes = nodes!('l', 'l', 't', 'g', 's', 'r');
+        reversed_nodes[2..4].reverse();
+        let reversed = Net::new(2, 2, reversed_nodes, edges());
+
+        let ordered = MatrixFeedforwardFabricator::fabricate(&ordered).unwrap();
+        let reversed = MatrixFeedforwardFabricator::fabricate(&reversed).unwrap();
+
+        assert_eq!(ordered.stages, reversed.stages);
+        assert_eq!(ordered.activation_kinds, reversed.activation_kinds);
+        assert_eq!(
+            ordered.evaluate(dmatrix![0.5, -1.0]),
+            reversed.evaluate(dmatrix![0.5, -1.0])
+        );
+    }
+
+    #[test]
+    fn reports_error_on_duplicate_output() {
+        let nodes = vec![
+            Node::new(0, activations::LINEAR),
+            Node::new(1, activations::LINEAR),
+            Node::new(1, activations::LINEAR),
+        ];
+        let net = Net::new(1, 2, nodes, edges!(0--1.0->1));
+
+        assert_eq!(
+            MatrixFeedforwardFabricator::fabricate(&net).err(),
+            Some("duplicate output ids present, net invalid")
+        );
+    }
+
     #[test]
     fn reports_error_on_empty_edges() {
         let net = Net::new(1, 1, nodes!('l', 'l'), Vec::new());
@@ -250,6 +953,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn preserves_declared_output_order() {
+        let mut nodes = nodes!('l', 'l', 'l', 'l');
+        // declare outputs in descending id order 3, 2
+        nodes.swap(2, 3);
+        let some_net = Net::new(1, 2, nodes, edges!(0--2.0->1, 0--3.0->2, 1--1.0->3));
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![3.0, 2.0]);
+
+        let evaluator =
+            MatrixFeedforwardFabricator::fabricate_preserving_output_order(&some_net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![2.0, 3.0]);
+    }
+
+    #[test]
+    fn fabricates_computable_outputs_only() {
+        let some_net = Net::new(
+            1,
+            2,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(
+                0--0.5->2,
+                1--0.5->3
+            ),
+        );
+
+        let (evaluator, uncomputable) =
+            MatrixFeedforwardFabricator::fabricate_partial(&some_net).unwrap();
+
+        assert_eq!(uncomputable, vec![3]);
+        assert_eq!(evaluator.evaluate(dmatrix![4.0]), dmatrix![2.0]);
+    }
+
     // test uncomputable output
     #[test]
     fn reports_error_on_unresolvable_dependency() {
@@ -319,6 +1056,124 @@ mod tests {
         assert_eq!(result, dmatrix![1.25]);
     }
 
+    #[test]
+    fn direct_seed_matches_general_path() {
+        let mut nodes = nodes!('l', 'l', 'l', 's', 't', 'l');
+        nodes[5].set_aggregation(Aggregation::Mean);
+        // declare outputs out of id order as 5, 4, 3
+        nodes[3..].reverse();
+        let edges = || {
+            edges!(
+                0--0.5->3,
+                1---1.5->3,
+                2--2.0->4,
+                0--0.25->5,
+                2--0.75->5
+            )
+        };
+        let seed = Net::new(3, 3, nodes, edges());
+
+        // an unused hidden node forces the general path without changing the function
+        let mut general_nodes = nodes!('l', 'l', 'l', 's', 't', 'l', 'l');
+        general_nodes[5].set_aggregation(Aggregation::Mean);
+        general_nodes[3..].reverse();
+        let general = Net::new(3, 3, general_nodes, edges());
+
+        for options in &[
+            MatrixFeedforwardOptions::default(),
+            MatrixFeedforwardOptions {
+                linear_outputs: true,
+                preserve_output_order: true,
+                ..Default::default()
+            },
+        ] {
+            let direct = MatrixFeedforwardFabricator::fabricate_with(&seed, options).unwrap();
+            let expected = MatrixFeedforwardFabricator::fabricate_with(&general, options).unwrap();
+
+            assert_eq!(direct.stages.len(), 1);
+            assert!(direct == expected);
+        }
+    }
+
+    #[test]
+    fn implicit_bias_shifts_node() {
+        let some_net = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(0--2.0->1, 1--1.0->2));
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate_with(
+            &some_net,
+            &MatrixFeedforwardOptions {
+                implicit_bias: vec![(1, 0.5)].into_iter().collect(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(evaluator.input_nodes.len(), 2);
+        assert_eq!(evaluator.evaluate(dmatrix![2.0, 1.0]), dmatrix![4.5]);
+
+        assert!(MatrixFeedforwardFabricator::fabricate_with(
+            &some_net,
+            &MatrixFeedforwardOptions {
+                implicit_bias: vec![(0, 0.5)].into_iter().collect(),
+                ..Default::default()
+            },
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn merges_linear_chain_into_single_stage() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l'),
+            edges!(
+                0--0.5->1,
+                1--0.5->2
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate_with(
+            &some_net,
+            &MatrixFeedforwardOptions {
+                merge_linear_chains: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.stages.len() + 1,
+            MatrixFeedforwardFabricator::fabricate(&some_net)
+                .unwrap()
+                .stages
+                .len()
+        );
+        assert_eq!(evaluator.evaluate(dmatrix![5.0]), dmatrix![1.25]);
+    }
+
+    #[test]
+    fn keeps_tied_edges_of_linear_chains() {
+        let mut tied_edges = edges!(0--2.0->1, 1--3.0->2);
+        tied_edges[0].set_weight_group(Some(7));
+        tied_edges[1].set_weight_group(Some(7));
+        let some_net = Net::new(1, 1, nodes!('l', 'l', 'l'), tied_edges);
+
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate_with(
+            &some_net,
+            &MatrixFeedforwardOptions {
+                merge_linear_chains: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![6.0]);
+
+        evaluator.set_group_weight(7, 10.0);
+
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![100.0]);
+    }
+
     // test construction of carry for later needs
     #[test]
     fn simple_net_evaluator_3() {
@@ -364,6 +1219,50 @@ mod tests {
         assert_eq!(result, dmatrix![3.75, 2.5]);
     }
 
+    #[test]
+    fn reports_carry_count() {
+        let some_net = Net::new(
+            1,
+            2,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(
+                0--0.5->1,
+                1--0.5->2,
+                0--0.5->3,
+                0--0.5->2
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        // input 0 skips the stage computing node 1
+        assert_eq!(evaluator.carry_count(), 1);
+    }
+
+    #[test]
+    fn reports_node_stage() {
+        let some_net = Net::new(
+            1,
+            2,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(
+                0--0.5->1,
+                1--0.5->2,
+                0--0.5->3,
+                0--0.5->2
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(evaluator.node_stage(0), Some(0));
+        assert_eq!(evaluator.node_stage(1), Some(1));
+        // output 3 is computed early and set aside, output 2 needs node 1 and lands in the last stage
+        assert_eq!(evaluator.node_stage(3), Some(1));
+        assert_eq!(evaluator.node_stage(2), Some(evaluator.stages.len()));
+        assert_eq!(evaluator.node_stage(4), None);
+    }
+
     // test construction of carry for early result flipped order
     #[test]
     fn simple_net_evaluator_5() {
@@ -407,4 +1306,103 @@ mod tests {
 
         assert_eq!(result, dmatrix![2.5]);
     }
+
+    #[test]
+    fn stores_activation_kinds_alongside_transformations() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 't', 's'),
+            edges!(
+                0--0.5->1,
+                1--0.5->2,
+                0--0.5->2
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let mut first_stage_kinds = evaluator.activation_kinds[0].clone();
+        first_stage_kinds.sort_unstable();
+
+        assert_eq!(
+            first_stage_kinds,
+            vec![ActivationKind::Linear, ActivationKind::Tanh]
+        );
+        assert_eq!(evaluator.activation_kinds[1], vec![ActivationKind::Sigmoid]);
+    }
+
+    #[test]
+    fn dispatch_by_kind_matches_pointer_dispatch() {
+        let mut nodes = nodes!('l', 'l', 't', 's', 'g', 'r', 'q', 'i');
+        nodes.push(Node::new(8, |val| val * 3.0));
+
+        let some_net = Net::new(
+            2,
+            2,
+            nodes,
+            edges!(
+                0--0.5->2,
+                1---0.5->3,
+                0--0.7->4,
+                2--1.5->5,
+                3---0.3->5,
+                4--0.2->6,
+                5--0.9->6,
+                6--0.4->7,
+                1--0.8->8,
+                6--0.1->8
+            ),
+        );
+
+        let pointer = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let kind = MatrixFeedforwardFabricator::fabricate_with(
+            &some_net,
+            &MatrixFeedforwardOptions {
+                dispatch_by_kind: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert!(kind.dispatch_by_kind);
+
+        for input in &[dmatrix![1.0, -2.0], dmatrix![0.0, 0.5], dmatrix![-3.0, 4.0]] {
+            assert_eq!(
+                pointer.evaluate(input.clone()),
+                kind.evaluate(input.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn stashes_early_outputs_instead_of_carrying_them() {
+        let some_net = Net::new(
+            1,
+            2,
+            nodes!('l', 'l', 'l', 'l', 'l'),
+            edges!(
+                0--0.5->1,
+                1--0.5->2,
+                2--0.5->3,
+                0--0.5->4
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(
+            evaluator
+                .stages
+                .iter()
+                .map(|stage| stage.ncols())
+                .collect::<Vec<_>>(),
+            vec![2, 1, 2]
+        );
+        assert_eq!(evaluator.stashed_outputs.len(), 1);
+
+        let result = evaluator.evaluate(dmatrix![8.0]);
+
+        assert_eq!(result, dmatrix![1.0, 4.0]);
+    }
 }
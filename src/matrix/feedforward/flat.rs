@@ -0,0 +1,180 @@
+use crate::network::ActivationKind;
+
+use super::evaluator::MatrixFeedforwardEvaluator;
+
+/// Position of one stage within the buffers of a [`FlatIr`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlatStage {
+    /// Index of the first weight of the stage in [`FlatIr::weights`].
+    pub weight_offset: u32,
+    /// Index of the first column of the stage in [`FlatIr::activations`] and [`FlatIr::biases`].
+    pub column_offset: u32,
+    pub rows: u32,
+    pub cols: u32,
+}
+
+/// Flat, backend agnostic form of a [`MatrixFeedforwardEvaluator`] made of plain buffers, e.g. to upload them to a GPU.
+///
+/// Stage matrices are stored dense and column-major, so entry `(row, column)` of a stage is found at
+/// `weight_offset + column * rows + row`. Activations are given by their [`ActivationKind::code`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatIr {
+    pub inputs: u32,
+    pub stages: Vec<FlatStage>,
+    pub weights: Vec<f64>,
+    pub biases: Vec<f64>,
+    pub activations: Vec<u32>,
+    /// Stage, column and output position of outputs to reinsert after the last stage, see [`MatrixFeedforwardEvaluator::stashed_outputs`].
+    pub stashed_outputs: Vec<[u32; 3]>,
+}
+
+impl MatrixFeedforwardEvaluator {
+    /// Packs the stages into a [`FlatIr`].
+    ///
+    /// Custom activations and pooling columns are computed by Rust code that can not be expressed in the flat buffers,
+    /// evaluators using them are rejected.
+    pub fn to_flat_ir(&self) -> Result<FlatIr, &'static str> {
+        if self.pooling.iter().any(|pooling| !pooling.is_empty()) {
+            return Err(
+                "aggregations other than sums are not representable as flat ir, net invalid",
+            );
+        }
+        if self
+            .activation_kinds
+            .iter()
+            .flatten()
+            .any(|&kind| kind == ActivationKind::Custom)
+        {
+            return Err("custom activations are not representable as flat ir, net invalid");
+        }
+        if self.output_vector_activation.is_some() {
+            return Err("output vector activations are not representable as flat ir, net invalid");
+        }
+
+        let mut flat = FlatIr {
+            inputs: self.input_nodes.len() as u32,
+            stages: Vec::with_capacity(self.stages.len()),
+            weights: Vec::new(),
+            biases: Vec::new(),
+            activations: Vec::new(),
+            stashed_outputs: self
+                .stashed_outputs
+                .iter()
+                .map(|&(stage, column, output)| [stage as u32, column as u32, output as u32])
+                .collect(),
+        };
+
+        for (stage, stage_matrix) in self.stages.iter().enumerate() {
+            flat.stages.push(FlatStage {
+                weight_offset: flat.weights.len() as u32,
+                column_offset: flat.activations.len() as u32,
+                rows: stage_matrix.nrows() as u32,
+                cols: stage_matrix.ncols() as u32,
+            });
+            // nalgebra stores matrices column-major already
+            flat.weights.extend_from_slice(stage_matrix.as_slice());
+            match &self.biases[stage] {
+                Some(bias) => flat.biases.extend(bias.iter()),
+                None => flat
+                    .biases
+                    .extend(std::iter::repeat_n(0.0, stage_matrix.ncols())),
+            }
+            flat.activations.extend(
+                self.activation_kinds[stage]
+                    .iter()
+                    .map(|kind| kind.code() as u32),
+            );
+        }
+
+        Ok(flat)
+    }
+}
+
+impl FlatIr {
+    /// Evaluates a single sample on the CPU, serving as reference for backends consuming the flat buffers.
+    pub fn interpret(&self, input: &[f64]) -> Vec<f64> {
+        let mut state = input.to_vec();
+        let mut stashed = Vec::with_capacity(self.stashed_outputs.len());
+
+        for (index, stage) in self.stages.iter().enumerate() {
+            let (rows, weight_offset, column_offset) = (
+                stage.rows as usize,
+                stage.weight_offset as usize,
+                stage.column_offset as usize,
+            );
+            state = (0..stage.cols as usize)
+                .map(|column| {
+                    let weights = &self.weights
+                        [weight_offset + column * rows..weight_offset + (column + 1) * rows];
+                    let sum: f64 = state.iter().zip(weights).map(|(x, w)| x * w).sum();
+                    let code = self.activations[column_offset + column];
+                    let activation = ActivationKind::BUILT_IN[code as usize].function().unwrap();
+                    activation(sum + self.biases[column_offset + column])
+                })
+                .collect();
+            for &[stashed_stage, column, output] in &self.stashed_outputs {
+                if stashed_stage as usize == index {
+                    stashed.push((output as usize, state[column as usize]));
+                }
+            }
+        }
+        for (output, value) in stashed {
+            state[output] = value;
+        }
+
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        edges,
+        network::{net::Net, ActivationKind, Evaluator, Fabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn interpreted_flat_ir_matches_evaluate() {
+        let some_net = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 't', 's', 'r', 'g', 'l'),
+            edges!(
+                0--0.5->2,
+                1---0.8->3,
+                2--1.5->4,
+                3--0.7->4,
+                4--0.3->5,
+                0--0.9->6
+            ),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let flat = evaluator.to_flat_ir().unwrap();
+
+        assert_eq!(flat.inputs, 2);
+        assert_eq!(flat.stashed_outputs.len(), 1);
+
+        for input in &[[0.0, 0.0], [0.7, -1.2], [-2.0, 3.5]] {
+            let expected: Vec<f64> = evaluator.evaluate(input.to_vec());
+            let result = flat.interpret(input);
+
+            assert_eq!(result.len(), expected.len());
+            for (a, b) in result.iter().zip(&expected) {
+                assert!((a - b).abs() < 1e-12, "{} != {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_custom_activations() {
+        let some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--0.5->1));
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        evaluator.activation_kinds[0][0] = ActivationKind::Custom;
+
+        assert_eq!(
+            evaluator.to_flat_ir().unwrap_err(),
+            "custom activations are not representable as flat ir, net invalid"
+        );
+    }
+}
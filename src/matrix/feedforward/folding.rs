@@ -0,0 +1,177 @@
+use nalgebra::RowDVector;
+
+use super::evaluator::MatrixFeedforwardEvaluator;
+
+impl MatrixFeedforwardEvaluator {
+    /// Precomputes everything that only depends on the given constant inputs, given as input index and value.
+    ///
+    /// Contributions of constant values are moved into the biases of the consuming stage and columns that become constant are dropped,
+    /// except for outputs which are always kept.
+    /// The folded evaluator expects only the remaining inputs, in their original order.
+    /// Weight groups are dropped, as weights of folded edges only survive as part of the biases.
+    pub fn fold_constants(mut self, constants: &[(usize, f64)]) -> Result<Self, &'static str> {
+        if self.pooling.iter().any(|pooling| !pooling.is_empty()) {
+            return Err(
+                "constant folding of aggregations other than sums is not supported, net invalid",
+            );
+        }
+
+        // value of every column of the current state that is known at fabrication time
+        let mut known = vec![None; self.input_nodes.len()];
+        for &(index, value) in constants {
+            match known.get_mut(index) {
+                Some(slot) => *slot = Some(value),
+                None => return Err("constant input index out of range, net invalid"),
+            }
+        }
+
+        self.input_nodes = self
+            .input_nodes
+            .iter()
+            .zip(&known)
+            .filter(|(_, value)| value.is_none())
+            .map(|(&node, _)| node)
+            .collect();
+
+        self.weight_groups.clear();
+
+        for stage in 0..self.stages.len() {
+            let mut bias = self.biases[stage]
+                .take()
+                .unwrap_or_else(|| RowDVector::zeros(self.stages[stage].ncols()));
+            for (row, value) in known.iter().enumerate() {
+                if let Some(value) = value {
+                    bias += self.stages[stage].row(row) * *value;
+                }
+            }
+
+            let unknown_rows: Vec<usize> = (0..known.len())
+                .filter(|&row| known[row].is_none())
+                .collect();
+            let stage_matrix = self.stages[stage].select_rows(&unknown_rows);
+
+            // columns no remaining input reaches are constant, unless they are needed as outputs
+            let mut next_known = vec![None; stage_matrix.ncols()];
+            if stage + 1 < self.stages.len() {
+                for (column, next_known) in next_known.iter_mut().enumerate() {
+                    let stashed =
+                        self.stashed_outputs
+                            .iter()
+                            .any(|&(stashed_stage, stashed_column, _)| {
+                                stashed_stage == stage && stashed_column == column
+                            });
+                    if !stashed
+                        && stage_matrix
+                            .column(column)
+                            .iter()
+                            .all(|&weight| weight == 0.0)
+                    {
+                        *next_known = Some(self.transformations[stage][column](bias[column]));
+                    }
+                }
+            }
+
+            let kept: Vec<usize> = (0..next_known.len())
+                .filter(|&column| next_known[column].is_none())
+                .collect();
+
+            self.stages[stage] = stage_matrix.select_columns(&kept);
+            self.biases[stage] = if bias.iter().any(|&value| value != 0.0) {
+                Some(bias.select_columns(&kept))
+            } else {
+                None
+            };
+            self.transformations[stage] = kept
+                .iter()
+                .map(|&column| self.transformations[stage][column])
+                .collect();
+            self.activation_kinds[stage] = kept
+                .iter()
+                .map(|&column| self.activation_kinds[stage][column])
+                .collect();
+            self.stage_nodes[stage] = kept
+                .iter()
+                .map(|&column| self.stage_nodes[stage][column])
+                .collect();
+            for (stashed_stage, column, _) in self.stashed_outputs.iter_mut() {
+                if *stashed_stage == stage {
+                    // stashed columns are never dropped
+                    *column = kept.iter().position(|kept| kept == column).unwrap();
+                }
+            }
+
+            known = next_known;
+        }
+
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        edges,
+        network::{net::Net, Evaluator, Fabricator},
+        nodes, MatrixFeedforwardFabricator, MatrixFeedforwardOptions,
+    };
+
+    fn some_net() -> Net {
+        Net::new(
+            3,
+            2,
+            nodes!('l', 'l', 'l', 't', 's', 'g'),
+            edges!(
+                2--0.5->3,
+                0--0.5->4,
+                1---0.7->4,
+                3--1.5->4,
+                3--0.8->5,
+                2--0.3->5
+            ),
+        )
+    }
+
+    #[test]
+    fn folded_matches_unfolded_on_remaining_inputs() {
+        let some_net = some_net();
+
+        let unfolded = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let folded = MatrixFeedforwardFabricator::fabricate_with(
+            &some_net,
+            &MatrixFeedforwardOptions {
+                constant_inputs: vec![(2, 1.0)],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // the hidden node only depends on the constant input
+        assert!(folded.stage_nodes.iter().flatten().all(|&node| node != 3));
+
+        for (x, y) in &[(0.0, 0.0), (1.0, -1.0), (0.25, 3.0), (-2.0, 0.5)] {
+            let expected: Vec<f64> = unfolded.evaluate(vec![*x, *y, 1.0]);
+            let result = folded.evaluate(vec![*x, *y]);
+
+            assert_eq!(result.len(), expected.len());
+            for (a, b) in result.iter().zip(&expected) {
+                assert!((a - b).abs() < 1e-12, "{} != {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_constant_input_out_of_range() {
+        let result = MatrixFeedforwardFabricator::fabricate_with(
+            &some_net(),
+            &MatrixFeedforwardOptions {
+                constant_inputs: vec![(3, 1.0)],
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            result.unwrap_err(),
+            "constant input index out of range, net invalid"
+        );
+    }
+}
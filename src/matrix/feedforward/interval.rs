@@ -0,0 +1,150 @@
+use crate::network::{ActivationKind, Aggregation};
+
+use super::evaluator::{MatrixFeedforwardEvaluator, PoolingColumn};
+
+type Interval = (f64, f64);
+
+impl MatrixFeedforwardEvaluator {
+    /// Returns conservative bounds of every output given bounds of every input, by interval arithmetic through all stages.
+    ///
+    /// The bounds are exact for every single node but ignore that nodes share inputs, so they can be wider than the actual range.
+    /// Monotonic built-in activations, i.e. linear, sigmoid, tanh, inverse and relu, as well as squared map bounds exactly.
+    /// The gaussian yields its global range `(0.0, 1.0)` and custom activations yield unbounded intervals.
+    ///
+    /// Panics if not exactly one bound per input is given.
+    pub fn output_interval(&self, input_bounds: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        assert_eq!(
+            input_bounds.len(),
+            self.input_nodes.len(),
+            "expected {} input bounds, got {}",
+            self.input_nodes.len(),
+            input_bounds.len()
+        );
+
+        let mut state = input_bounds.to_vec();
+        let mut stashed = Vec::with_capacity(self.stashed_outputs.len());
+
+        for (stage, (stage_matrix, activation_kinds)) in
+            self.stages.iter().zip(&self.activation_kinds).enumerate()
+        {
+            let mut next: Vec<Interval> = stage_matrix
+                .column_iter()
+                .map(|weights| {
+                    weights.iter().zip(&state).fold(
+                        (0.0, 0.0),
+                        |(low, high), (&weight, &(input_low, input_high))| {
+                            if weight == 0.0 {
+                                (low, high)
+                            } else {
+                                let (a, b) = (weight * input_low, weight * input_high);
+                                (low + a.min(b), high + a.max(b))
+                            }
+                        },
+                    )
+                })
+                .collect();
+            for pooling in &self.pooling[stage] {
+                next[pooling.column] = pool_interval(pooling, &state);
+            }
+            if let Some(bias) = &self.biases[stage] {
+                for ((low, high), bias) in next.iter_mut().zip(bias.iter()) {
+                    *low += bias;
+                    *high += bias;
+                }
+            }
+            for (interval, &activation_kind) in next.iter_mut().zip(activation_kinds) {
+                *interval = activate_interval(activation_kind, *interval);
+            }
+            for &(stashed_stage, column, output) in &self.stashed_outputs {
+                if stashed_stage == stage {
+                    stashed.push((output, next[column]));
+                }
+            }
+            state = next;
+        }
+        for (output, interval) in stashed {
+            state[output] = interval;
+        }
+        state
+    }
+}
+
+fn pool_interval(pooling: &PoolingColumn, state: &[Interval]) -> Interval {
+    let mut weighted = pooling.inputs.iter().map(|&(index, weight)| {
+        let (a, b) = (state[index].0 * weight, state[index].1 * weight);
+        (a.min(b), a.max(b))
+    });
+    let first = match weighted.next() {
+        Some(first) => first,
+        None => return (0.0, 0.0),
+    };
+    weighted.fold(first, |(low, high), (value_low, value_high)| {
+        match pooling.aggregation {
+            Aggregation::Max => (low.max(value_low), high.max(value_high)),
+            Aggregation::Min => (low.min(value_low), high.min(value_high)),
+            Aggregation::Product => {
+                let corners = [
+                    low * value_low,
+                    low * value_high,
+                    high * value_low,
+                    high * value_high,
+                ];
+                (
+                    corners.iter().cloned().fold(f64::INFINITY, f64::min),
+                    corners.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+                )
+            }
+            // linear aggregations are part of the stage matrix and never pooled
+            _ => (low + value_low, high + value_high),
+        }
+    })
+}
+
+fn activate_interval(activation_kind: ActivationKind, (low, high): Interval) -> Interval {
+    match activation_kind {
+        ActivationKind::Inverse => (-high, -low),
+        ActivationKind::Squared => {
+            let (a, b) = (low * low, high * high);
+            if low <= 0.0 && high >= 0.0 {
+                (0.0, a.max(b))
+            } else {
+                (a.min(b), a.max(b))
+            }
+        }
+        ActivationKind::Gaussian => (0.0, 1.0),
+        ActivationKind::Custom => (f64::NEG_INFINITY, f64::INFINITY),
+        monotonic => {
+            let activation = monotonic
+                .function()
+                .expect("built-in activations have a function");
+            (activation(low), activation(high))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        edges,
+        network::{net::Net, Fabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn propagates_intervals_through_linear_net() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--2.0->2, 1---1.0->2, 2--0.5->3, 0---3.0->3),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        // node 2 lies in [2 * -1 - 1 * 3, 2 * 2 - 1 * 1] = [-5, 3]
+        // node 3 lies in [0.5 * -5 - 3 * 2, 0.5 * 3 - 3 * -1] = [-8.5, 4.5]
+        assert_eq!(
+            evaluator.output_interval(&[(-1.0, 2.0), (1.0, 3.0)]),
+            vec![(-8.5, 4.5)]
+        );
+    }
+}
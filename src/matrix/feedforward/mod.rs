@@ -1,2 +1,17 @@
+pub mod compose;
+pub mod conditioning;
+pub mod ensemble;
+pub mod equivalence;
 pub mod evaluator;
 pub mod fabricator;
+pub mod flat;
+pub mod folding;
+pub mod interval;
+pub mod normalizing;
+pub mod population;
+#[cfg(feature = "profile")]
+pub mod profile;
+pub mod scratch;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod vector_activation;
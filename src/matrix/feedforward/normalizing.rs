@@ -0,0 +1,296 @@
+use nalgebra::DMatrix;
+
+use crate::network::{Evaluator, NetworkIO, StatefulEvaluator};
+
+use super::evaluator::MatrixFeedforwardEvaluator;
+
+/// Normalizes the outputs of a [`MatrixFeedforwardEvaluator`] by subtracting their running mean.
+///
+/// The running mean of every output is updated with each evaluated row before it is subtracted,
+/// so rows of a batch are treated like consecutive evaluations.
+/// The statistics are the internal state of this evaluator and are cleared by [`SelfNormalizingMatrixEvaluator::reset`].
+#[derive(Debug, Clone)]
+pub struct SelfNormalizingMatrixEvaluator {
+    pub evaluator: MatrixFeedforwardEvaluator,
+    pub means: Vec<f64>,
+    pub count: usize,
+}
+
+impl SelfNormalizingMatrixEvaluator {
+    /// Clears the running statistics.
+    pub fn reset(&mut self) {
+        self.means.clear();
+        self.count = 0;
+    }
+}
+
+/// Wraps the evaluator with empty running statistics.
+impl From<MatrixFeedforwardEvaluator> for SelfNormalizingMatrixEvaluator {
+    fn from(evaluator: MatrixFeedforwardEvaluator) -> Self {
+        Self {
+            evaluator,
+            means: Vec::new(),
+            count: 0,
+        }
+    }
+}
+
+impl StatefulEvaluator for SelfNormalizingMatrixEvaluator {
+    fn evaluate<T: NetworkIO>(&mut self, input: T) -> T {
+        let mut output: DMatrix<f64> = self.evaluator.evaluate(NetworkIO::input(input));
+
+        self.means.resize(output.ncols(), 0.0);
+
+        for mut row in output.row_iter_mut() {
+            self.count += 1;
+            for (value, mean) in row.iter_mut().zip(self.means.iter_mut()) {
+                *mean += (*value - *mean) / self.count as f64;
+                *value -= *mean;
+            }
+        }
+
+        NetworkIO::output(output)
+    }
+
+    fn reset_internal_state(&mut self) {
+        self.reset();
+    }
+
+    fn map_internal_state(&mut self, f: &mut dyn FnMut(f64) -> f64) {
+        for mean in self.means.iter_mut() {
+            *mean = f(*mean);
+        }
+    }
+}
+
+/// Evaluates a [`MatrixFeedforwardEvaluator`] and normalizes every sample after each stage but the last, like layer normalization.
+///
+/// Each row of the state gets its mean subtracted and is divided by its standard deviation over the columns the stage computes,
+/// columns that only carry values of earlier stages are left as they are. This changes the function the net computes,
+/// it is a design choice for stabilizing deep nets, not an optimization that keeps results intact.
+/// Outputs computed before the last stage are taken before normalization.
+#[derive(Debug, Clone)]
+pub struct NormalizingFeedforwardEvaluator {
+    pub evaluator: MatrixFeedforwardEvaluator,
+    /// Added to the variance before taking the square root, keeps stages of constant values finite.
+    pub epsilon: f64,
+}
+
+/// Wraps the evaluator with an `epsilon` of `1e-5`.
+impl From<MatrixFeedforwardEvaluator> for NormalizingFeedforwardEvaluator {
+    fn from(evaluator: MatrixFeedforwardEvaluator) -> Self {
+        Self {
+            evaluator,
+            epsilon: 1e-5,
+        }
+    }
+}
+
+impl NormalizingFeedforwardEvaluator {
+    /// Evaluates a single sample and returns the outputs together with the state after every stage,
+    /// see [`MatrixFeedforwardEvaluator::evaluate_with_trace`]. All but the last state are normalized.
+    pub fn evaluate_with_trace(&self, input: Vec<f64>) -> (Vec<f64>, Vec<DMatrix<f64>>) {
+        let mut trace = Vec::with_capacity(self.evaluator.stages.len());
+        let output = self.propagate(NetworkIO::input(input), Some(&mut trace));
+        (output.iter().cloned().collect(), trace)
+    }
+
+    fn normalize(&self, stage: usize, state: &mut DMatrix<f64>) {
+        let computed: Vec<usize> = (0..state.ncols())
+            .filter(|&column| !self.evaluator.is_carry(stage, column))
+            .collect();
+        if computed.is_empty() {
+            return;
+        }
+        let columns = computed.len() as f64;
+        for mut row in state.row_iter_mut() {
+            let mean = computed.iter().map(|&column| row[column]).sum::<f64>() / columns;
+            let variance = computed
+                .iter()
+                .map(|&column| (row[column] - mean).powi(2))
+                .sum::<f64>()
+                / columns;
+            let deviation = (variance + self.epsilon).sqrt();
+            for &column in &computed {
+                row[column] = (row[column] - mean) / deviation;
+            }
+        }
+    }
+
+    fn propagate(
+        &self,
+        mut state: DMatrix<f64>,
+        mut trace: Option<&mut Vec<DMatrix<f64>>>,
+    ) -> DMatrix<f64> {
+        let evaluator = &self.evaluator;
+        assert_eq!(
+            state.ncols(),
+            evaluator.input_nodes.len(),
+            "expected {} inputs, got {}",
+            evaluator.input_nodes.len(),
+            state.ncols()
+        );
+
+        let mut stashed = Vec::with_capacity(evaluator.stashed_outputs.len());
+        let last_stage = evaluator.stages.len().saturating_sub(1);
+
+        for (stage, stage_matrix) in evaluator.stages.iter().enumerate() {
+            state = evaluator.apply_stage(stage, state, stage_matrix);
+            evaluator.activate(
+                &mut state,
+                &evaluator.transformations[stage],
+                &evaluator.activation_kinds[stage],
+            );
+            for &(stashed_stage, column, output) in &evaluator.stashed_outputs {
+                if stashed_stage == stage {
+                    stashed.push((output, state.column(column).clone_owned()));
+                }
+            }
+            if stage < last_stage {
+                self.normalize(stage, &mut state);
+            }
+            if let Some(trace) = trace.as_mut() {
+                trace.push(state.clone());
+            }
+        }
+        for (output, values) in stashed {
+            state.set_column(output, &values);
+        }
+        evaluator.apply_output_vector_activation(&mut state);
+        if let Some(last) = trace.and_then(|trace| trace.last_mut()) {
+            last.copy_from(&state);
+        }
+        state
+    }
+}
+
+impl Evaluator for NormalizingFeedforwardEvaluator {
+    fn evaluate<T: NetworkIO>(&self, input: T) -> T {
+        NetworkIO::output(self.propagate(NetworkIO::input(input), None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{NormalizingFeedforwardEvaluator, SelfNormalizingMatrixEvaluator};
+    use crate::{
+        edges,
+        network::{net::Net, Fabricator, StatefulEvaluator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn symmetric_inputs_normalize_to_zero() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l'),
+            edges!(
+                0--1.0->2,
+                1--1.0->2
+            ),
+        );
+
+        let mut evaluator: SelfNormalizingMatrixEvaluator =
+            MatrixFeedforwardFabricator::fabricate(&some_net)
+                .unwrap()
+                .into();
+
+        assert_eq!(evaluator.evaluate(vec![1.0, -1.0]), vec![0.0]);
+    }
+
+    #[test]
+    fn subtracts_running_mean_until_reset() {
+        let some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+
+        let mut evaluator: SelfNormalizingMatrixEvaluator =
+            MatrixFeedforwardFabricator::fabricate(&some_net)
+                .unwrap()
+                .into();
+
+        assert_eq!(evaluator.evaluate(vec![2.0]), vec![0.0]);
+        assert_eq!(evaluator.evaluate(vec![4.0]), vec![1.0]);
+        // rows of a batch update the mean in order
+        assert_eq!(evaluator.evaluate(dmatrix![6.0; 0.0]), dmatrix![2.0; -3.0]);
+
+        evaluator.reset();
+
+        assert_eq!(evaluator.evaluate(vec![4.0]), vec![0.0]);
+    }
+
+    #[test]
+    fn normalizes_state_between_stages() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 't', 'r', 'l', 'l'),
+            edges!(
+                0--0.5->2,
+                1--2.0->3,
+                0--1.5->4,
+                2--1.0->5,
+                3--1.0->5,
+                4--1.0->5
+            ),
+        );
+
+        let evaluator: NormalizingFeedforwardEvaluator =
+            MatrixFeedforwardFabricator::fabricate(&some_net)
+                .unwrap()
+                .into();
+
+        let (output, trace) = evaluator.evaluate_with_trace(vec![1.0, 3.0]);
+        let normalized = &trace[0];
+        let columns = normalized.ncols() as f64;
+        let mean = normalized.sum() / columns;
+        let variance = normalized
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            / columns;
+
+        assert!(mean.abs() < 1e-9);
+        assert!((variance - 1.0).abs() < 1e-4);
+        // the last stage computes the output from the normalized values
+        assert_eq!(output, vec![normalized.sum()]);
+    }
+
+    #[test]
+    fn leaves_carried_columns_alone() {
+        // input 0 is carried past the stages computing nodes 2, 3 and 4
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l', 'l', 'l', 'l'),
+            edges!(
+                0--0.5->2,
+                1--2.0->3,
+                2--1.0->4,
+                3--1.0->4,
+                4--1.0->5,
+                0--1.0->5
+            ),
+        );
+
+        let evaluator: NormalizingFeedforwardEvaluator =
+            MatrixFeedforwardFabricator::fabricate(&some_net)
+                .unwrap()
+                .into();
+
+        let (_, trace) = evaluator.evaluate_with_trace(vec![1.0, 3.0]);
+        let normalized = &trace[0];
+        let (carried, computed): (Vec<usize>, Vec<usize>) =
+            (0..normalized.ncols()).partition(|&column| evaluator.evaluator.is_carry(0, column));
+        assert_eq!(carried.len(), 1);
+        assert_eq!(computed.len(), 2);
+
+        assert_eq!(normalized[(0, carried[0])], 1.0);
+        let sum = computed
+            .iter()
+            .map(|&column| normalized[(0, column)])
+            .sum::<f64>();
+        assert!(sum.abs() < 1e-9);
+    }
+}
@@ -0,0 +1,151 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::network::{EdgeLike, Fabricator, NetworkLike, NodeLike};
+
+use super::{
+    evaluator::MatrixFeedforwardEvaluator, fabricator::MatrixFeedforwardFabricator,
+    vector_activation::VectorActivationKind,
+};
+
+/// Fabricates a whole population of nets, sharing one evaluator between structurally identical nets.
+///
+/// Two nets are structurally identical if they fabricate into the same stages: bitwise equal weights,
+/// the same activation per column and the same node ids, pooling, biases and stashed outputs.
+/// Common ancestry often leaves many genomes in a population like that.
+///
+/// Sharing happens for whole evaluators only, the layout of every stage depends on all stages around it,
+/// so equal stages inside otherwise different evaluators are not detected.
+pub struct PopulationFabricator;
+
+impl PopulationFabricator {
+    /// Fabricates every net with [`MatrixFeedforwardFabricator`] and returns the evaluators in the order of `nets`.
+    ///
+    /// Fails with the error of the first net that fails to fabricate.
+    pub fn fabricate<N: NodeLike, E: EdgeLike, T: NetworkLike<N, E>>(
+        nets: &[&T],
+    ) -> Result<Vec<Arc<MatrixFeedforwardEvaluator>>, &'static str> {
+        let mut fabricated: HashMap<Vec<u64>, Arc<MatrixFeedforwardEvaluator>> = HashMap::new();
+
+        nets.iter()
+            .map(|net| {
+                let evaluator = MatrixFeedforwardFabricator::fabricate(*net)?;
+                Ok(fabricated
+                    .entry(fingerprint(&evaluator))
+                    .or_insert_with(|| Arc::new(evaluator))
+                    .clone())
+            })
+            .collect()
+    }
+}
+
+// exact encoding of everything that determines the result of an evaluation, used instead of a hash to rule out collisions
+fn fingerprint(evaluator: &MatrixFeedforwardEvaluator) -> Vec<u64> {
+    let mut fingerprint = vec![evaluator.dispatch_by_kind as u64];
+
+    fingerprint.push(evaluator.input_nodes.len() as u64);
+    fingerprint.extend(evaluator.input_nodes.iter().map(|&id| id as u64));
+
+    for stage in 0..evaluator.stages.len() {
+        let stage_matrix = &evaluator.stages[stage];
+        fingerprint.extend([stage_matrix.nrows() as u64, stage_matrix.ncols() as u64]);
+        fingerprint.extend(stage_matrix.iter().map(|weight| weight.to_bits()));
+        fingerprint.extend(evaluator.stage_nodes[stage].iter().map(|&id| id as u64));
+        fingerprint.extend(
+            evaluator.transformations[stage]
+                .iter()
+                .map(|&activation| activation as usize as u64),
+        );
+        fingerprint.extend(
+            evaluator.activation_kinds[stage]
+                .iter()
+                .map(|&kind| kind as u64),
+        );
+
+        fingerprint.push(evaluator.pooling[stage].len() as u64);
+        for pooling in &evaluator.pooling[stage] {
+            fingerprint.extend([
+                pooling.column as u64,
+                pooling.aggregation as u64,
+                pooling.inputs.len() as u64,
+            ]);
+            for &(index, weight) in &pooling.inputs {
+                fingerprint.extend([index as u64, weight.to_bits()]);
+            }
+        }
+
+        match &evaluator.biases[stage] {
+            Some(bias) => {
+                fingerprint.push(1);
+                fingerprint.extend(bias.iter().map(|value| value.to_bits()));
+            }
+            None => fingerprint.push(0),
+        }
+    }
+
+    for &(stage, column, output) in &evaluator.stashed_outputs {
+        fingerprint.extend([stage as u64, column as u64, output as u64]);
+    }
+
+    // sorted by group, the order of a hash map is not stable
+    let mut groups: Vec<_> = evaluator.weight_groups.iter().collect();
+    groups.sort_by_key(|&(&group, _)| group);
+    fingerprint.push(groups.len() as u64);
+    for (&group, tied_weights) in groups {
+        fingerprint.extend([group as u64, tied_weights.len() as u64]);
+        for tied_weight in tied_weights {
+            fingerprint.extend([
+                tied_weight.stage as u64,
+                tied_weight.row as u64,
+                tied_weight.column as u64,
+                tied_weight.scale.to_bits(),
+            ]);
+        }
+    }
+
+    fingerprint.push(evaluator.outputs as u64);
+    fingerprint.extend(match evaluator.output_vector_activation {
+        None => [0, 0],
+        Some(VectorActivationKind::Softmax) => [1, 0],
+        Some(VectorActivationKind::L2Normalize) => [2, 0],
+        Some(VectorActivationKind::Custom(f)) => [3, f as usize as u64],
+    });
+
+    fingerprint
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::PopulationFabricator;
+    use crate::{edges, network::net::Net, nodes};
+
+    #[test]
+    fn shares_evaluators_of_identical_nets() {
+        let some_net = Net::new(2, 1, nodes!('l', 'l', 's'), edges!(0--0.5->2, 1--0.5->2));
+        let same_net = Net::new(2, 1, nodes!('l', 'l', 's'), edges!(0--0.5->2, 1--0.5->2));
+        let other_net = Net::new(2, 1, nodes!('l', 'l', 's'), edges!(0--0.5->2, 1--0.7->2));
+
+        let evaluators =
+            PopulationFabricator::fabricate(&[&some_net, &same_net, &other_net]).unwrap();
+
+        assert_eq!(evaluators.len(), 3);
+        assert!(Arc::ptr_eq(&evaluators[0], &evaluators[1]));
+        assert!(!Arc::ptr_eq(&evaluators[0], &evaluators[2]));
+    }
+
+    #[test]
+    fn keeps_differently_tied_nets_apart() {
+        let mut tied_edges = edges!(0--0.5->2, 1--0.5->2);
+        tied_edges[0].set_weight_group(Some(1));
+        tied_edges[1].set_weight_group(Some(1));
+        let tied_net = Net::new(2, 1, nodes!('l', 'l', 's'), tied_edges);
+        let untied_net = Net::new(2, 1, nodes!('l', 'l', 's'), edges!(0--0.5->2, 1--0.5->2));
+
+        let evaluators = PopulationFabricator::fabricate(&[&tied_net, &untied_net]).unwrap();
+
+        assert!(!Arc::ptr_eq(&evaluators[0], &evaluators[1]));
+        assert_eq!(evaluators[0].weight_groups.len(), 1);
+        assert!(evaluators[1].weight_groups.is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+//! Per-stage timing of [`MatrixFeedforwardEvaluator`], enabled by the `profile` feature.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use super::evaluator::MatrixFeedforwardEvaluator;
+
+/// Durations of every stage of the most recent evaluation.
+///
+/// Evaluation only borrows the evaluator immutably, so the timings sit behind a lock to keep the evaluator `Sync`.
+#[derive(Debug, Default)]
+pub struct StageTimings(Mutex<Vec<Duration>>);
+
+impl Clone for StageTimings {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.0.lock().unwrap().clone()))
+    }
+}
+
+impl StageTimings {
+    pub(crate) fn record(&self, timings: Vec<Duration>) {
+        *self.0.lock().unwrap() = timings;
+    }
+}
+
+/// Measures stages one after another, see [`MatrixFeedforwardEvaluator::last_stage_timings`].
+pub(crate) struct StageClock {
+    timings: Vec<Duration>,
+    start: Instant,
+}
+
+impl StageClock {
+    pub(crate) fn start(stages: usize) -> Self {
+        Self {
+            timings: Vec::with_capacity(stages),
+            start: Instant::now(),
+        }
+    }
+
+    pub(crate) fn lap(&mut self) {
+        let now = Instant::now();
+        self.timings.push(now - self.start);
+        self.start = now;
+    }
+
+    pub(crate) fn stop(self, evaluator: &MatrixFeedforwardEvaluator) {
+        evaluator.stage_timings.record(self.timings);
+    }
+}
+
+impl MatrixFeedforwardEvaluator {
+    /// Returns how long each stage took during the most recent evaluation, empty before the first one.
+    ///
+    /// The timings are copied out as they are shared with concurrent evaluations.
+    /// When evaluating from several threads they belong to whichever evaluation finished last.
+    pub fn last_stage_timings(&self) -> Vec<Duration> {
+        self.stage_timings.0.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use crate::{
+        edges,
+        network::{net::Net, Evaluator, Fabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn records_one_timing_per_stage() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 't', 's', 'l'),
+            edges!(0--0.5->1, 1--0.5->2, 2--0.5->3),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert!(evaluator.last_stage_timings().is_empty());
+
+        evaluator.evaluate(dmatrix![1.0]);
+
+        assert_eq!(evaluator.last_stage_timings().len(), evaluator.stages.len());
+        assert_eq!(evaluator.stages.len(), 3);
+    }
+}
@@ -0,0 +1,167 @@
+use nalgebra::{DMatrix, DMatrixView, DMatrixViewMut};
+
+use super::evaluator::{dispatch, MatrixFeedforwardEvaluator};
+
+/// Reusable buffers for [`MatrixFeedforwardEvaluator::evaluate_with_scratch`].
+///
+/// Stages are computed alternating between two buffers, each needs room for the widest stage times the number of rows evaluated at once.
+/// Outputs computed before the last stage need one column each in a third buffer.
+/// [`EvalScratch::new`] sizes all of them for a given evaluator, a scratch that is too small grows during the first evaluation and stays that size.
+#[derive(Debug, Clone, Default)]
+pub struct EvalScratch {
+    buffers: [Vec<f64>; 2],
+    stashed: Vec<f64>,
+}
+
+impl EvalScratch {
+    /// Creates a scratch large enough to evaluate `rows` samples at once with `evaluator` without allocating.
+    pub fn new(evaluator: &MatrixFeedforwardEvaluator, rows: usize) -> Self {
+        let mut scratch = Self::default();
+        scratch.reserve(evaluator, rows);
+        scratch
+    }
+
+    fn reserve(&mut self, evaluator: &MatrixFeedforwardEvaluator, rows: usize) {
+        let widest = evaluator
+            .stages
+            .iter()
+            .map(|stage_matrix| stage_matrix.ncols())
+            .chain(std::iter::once(evaluator.input_nodes.len()))
+            .max()
+            .unwrap_or(0);
+
+        for buffer in self.buffers.iter_mut() {
+            if buffer.len() < rows * widest {
+                buffer.resize(rows * widest, 0.0);
+            }
+        }
+        if self.stashed.len() < rows * evaluator.stashed_outputs.len() {
+            self.stashed
+                .resize(rows * evaluator.stashed_outputs.len(), 0.0);
+        }
+    }
+}
+
+impl MatrixFeedforwardEvaluator {
+    /// Evaluates like [`crate::network::Evaluator::evaluate`] but keeps all intermediate results in `scratch`.
+    ///
+    /// With a scratch sized by [`EvalScratch::new`] for at least as many rows as `input` has, only the returned output is allocated.
+    pub fn evaluate_with_scratch(
+        &self,
+        input: &DMatrix<f64>,
+        scratch: &mut EvalScratch,
+    ) -> DMatrix<f64> {
+        assert_eq!(
+            input.ncols(),
+            self.input_nodes.len(),
+            "expected {} inputs, got {}",
+            self.input_nodes.len(),
+            input.ncols()
+        );
+
+        let rows = input.nrows();
+        scratch.reserve(self, rows);
+
+        let EvalScratch {
+            buffers: [current, next],
+            stashed,
+        } = scratch;
+        let (mut current, mut next) = (current, next);
+        current[..input.len()].copy_from_slice(input.as_slice());
+        let mut columns = input.ncols();
+
+        for (stage, stage_matrix) in self.stages.iter().enumerate() {
+            let state = DMatrixView::from_slice(&current[..rows * columns], rows, columns);
+            let mut result = DMatrixViewMut::from_slice(
+                &mut next[..rows * stage_matrix.ncols()],
+                rows,
+                stage_matrix.ncols(),
+            );
+
+            result.gemm(1.0, &state, stage_matrix, 0.0);
+            for pooling in &self.pooling[stage] {
+                for row in 0..rows {
+                    result[(row, pooling.column)] = pooling.pool(&state, row);
+                }
+            }
+            if let Some(bias) = &self.biases[stage] {
+                for (mut column, &bias) in result.column_iter_mut().zip(bias.iter()) {
+                    column.add_scalar_mut(bias);
+                }
+            }
+            for (column, mut values) in result.column_iter_mut().enumerate() {
+                let activation_kind = self.activation_kinds[stage][column];
+                let activation = self.transformations[stage][column];
+                if self.dispatch_by_kind {
+                    values.apply(|value| *value = dispatch(activation_kind, activation, *value));
+                } else {
+                    values.apply(|value| *value = activation(*value));
+                }
+            }
+            for (slot, &(stashed_stage, column, _)) in self.stashed_outputs.iter().enumerate() {
+                if stashed_stage == stage {
+                    stashed[slot * rows..(slot + 1) * rows]
+                        .copy_from_slice(result.column(column).as_slice());
+                }
+            }
+
+            columns = stage_matrix.ncols();
+            std::mem::swap(&mut current, &mut next);
+        }
+
+        let mut output = DMatrix::from_column_slice(rows, columns, &current[..rows * columns]);
+        for (slot, &(_, _, position)) in self.stashed_outputs.iter().enumerate() {
+            output
+                .column_mut(position)
+                .copy_from_slice(&stashed[slot * rows..(slot + 1) * rows]);
+        }
+        self.apply_output_vector_activation(&mut output);
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::EvalScratch;
+    use crate::{
+        edges,
+        network::{net::Net, Aggregation, Evaluator, Fabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn shared_scratch_matches_allocating_path() {
+        let mut nodes = nodes!('l', 'l', 't', 's', 'l', 'g', 'l');
+        nodes[3].set_aggregation(Aggregation::Max);
+        let some_net = Net::new(
+            2,
+            3,
+            nodes,
+            edges!(
+                0--0.5->2,
+                1---0.25->2,
+                0--1.0->3,
+                2--0.5->3,
+                0--2.0->4,
+                3--1.5->5,
+                2--0.75->6,
+                1--0.5->6
+            ),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let mut scratch = EvalScratch::new(&evaluator, 2);
+
+        for input in [
+            dmatrix![0.5, -1.0; 2.0, 0.25],
+            dmatrix![1.5, 3.0],
+            dmatrix![-0.5, 0.0; 0.0, -0.5],
+        ] {
+            assert_eq!(
+                evaluator.evaluate_with_scratch(&input, &mut scratch),
+                evaluator.evaluate(input)
+            );
+        }
+    }
+}
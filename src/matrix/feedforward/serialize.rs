@@ -0,0 +1,309 @@
+//! Serialization of [`MatrixFeedforwardEvaluator`], enabled by the `serde` feature.
+//!
+//! Activations are stored as their [`ActivationKind`] and looked up again when deserializing,
+//! so evaluators containing [`ActivationKind::Custom`] activations can not be serialized.
+//! The same holds for output vector activations other than the built-in ones.
+
+use bincode::Options;
+use nalgebra::{DMatrix, RowDVector};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::network::{ActivationKind, Aggregation};
+
+use super::{
+    evaluator::{MatrixFeedforwardEvaluator, PoolingColumn, TiedWeight},
+    vector_activation::VectorActivationKind,
+};
+
+type SerializedPooling = Vec<Vec<(usize, Aggregation, Vec<(usize, f64)>)>>;
+// group with stage, row, column and scale of its tied weights
+type SerializedWeightGroups = Vec<(usize, Vec<(usize, usize, usize, f64)>)>;
+
+// mirrors the evaluator with matrices as shape and column-major values and activations by kind only
+#[derive(Serialize, Deserialize)]
+struct SerializedEvaluator {
+    stages: Vec<(usize, usize, Vec<f64>)>,
+    activation_kinds: Vec<Vec<ActivationKind>>,
+    input_nodes: Vec<usize>,
+    stage_nodes: Vec<Vec<usize>>,
+    dispatch_by_kind: bool,
+    stashed_outputs: Vec<(usize, usize, usize)>,
+    pooling: SerializedPooling,
+    biases: Vec<Option<Vec<f64>>>,
+    weight_groups: SerializedWeightGroups,
+    outputs: usize,
+    output_vector_activation: Option<String>,
+}
+
+impl Serialize for MatrixFeedforwardEvaluator {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self
+            .activation_kinds
+            .iter()
+            .flatten()
+            .any(|&kind| kind == ActivationKind::Custom)
+        {
+            return Err(ser::Error::custom(
+                "custom activations can not be serialized",
+            ));
+        }
+
+        let output_vector_activation =
+            match self.output_vector_activation {
+                Some(kind) => Some(kind.name().map(|name| name.to_string()).ok_or_else(|| {
+                    ser::Error::custom("custom activations can not be serialized")
+                })?),
+                None => None,
+            };
+
+        // sorted by group so equal evaluators serialize equally
+        let mut weight_groups: SerializedWeightGroups = self
+            .weight_groups
+            .iter()
+            .map(|(&group, tied_weights)| {
+                let tied_weights = tied_weights
+                    .iter()
+                    .map(|tied_weight| {
+                        (
+                            tied_weight.stage,
+                            tied_weight.row,
+                            tied_weight.column,
+                            tied_weight.scale,
+                        )
+                    })
+                    .collect();
+                (group, tied_weights)
+            })
+            .collect();
+        weight_groups.sort_unstable_by_key(|&(group, _)| group);
+
+        SerializedEvaluator {
+            stages: self
+                .stages
+                .iter()
+                .map(|stage| (stage.nrows(), stage.ncols(), stage.as_slice().to_vec()))
+                .collect(),
+            activation_kinds: self.activation_kinds.clone(),
+            input_nodes: self.input_nodes.clone(),
+            stage_nodes: self.stage_nodes.clone(),
+            dispatch_by_kind: self.dispatch_by_kind,
+            stashed_outputs: self.stashed_outputs.clone(),
+            pooling: self
+                .pooling
+                .iter()
+                .map(|columns| {
+                    columns
+                        .iter()
+                        .map(|pooling| {
+                            (pooling.column, pooling.aggregation, pooling.inputs.clone())
+                        })
+                        .collect()
+                })
+                .collect(),
+            biases: self
+                .biases
+                .iter()
+                .map(|bias| bias.as_ref().map(|bias| bias.iter().cloned().collect()))
+                .collect(),
+            weight_groups,
+            outputs: self.outputs,
+            output_vector_activation,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MatrixFeedforwardEvaluator {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let serialized = SerializedEvaluator::deserialize(deserializer)?;
+
+        let transformations = serialized
+            .activation_kinds
+            .iter()
+            .map(|kinds| {
+                kinds
+                    .iter()
+                    .map(|kind| {
+                        kind.function().ok_or_else(|| {
+                            de::Error::custom("custom activations can not be deserialized")
+                        })
+                    })
+                    .collect::<Result<Vec<_>, D::Error>>()
+            })
+            .collect::<Result<Vec<_>, D::Error>>()?;
+
+        let output_vector_activation = match &serialized.output_vector_activation {
+            Some(name) => Some(
+                VectorActivationKind::from_name(name)
+                    .ok_or_else(|| de::Error::custom("unknown output vector activation"))?,
+            ),
+            None => None,
+        };
+
+        let stages = serialized
+            .stages
+            .into_iter()
+            .map(|(rows, columns, values)| {
+                if rows * columns != values.len() {
+                    return Err(de::Error::custom("stage shape does not match its values"));
+                }
+                Ok(DMatrix::from_vec(rows, columns, values))
+            })
+            .collect::<Result<Vec<_>, D::Error>>()?;
+
+        Ok(MatrixFeedforwardEvaluator {
+            stages,
+            transformations,
+            activation_kinds: serialized.activation_kinds,
+            input_nodes: serialized.input_nodes,
+            stage_nodes: serialized.stage_nodes,
+            dispatch_by_kind: serialized.dispatch_by_kind,
+            stashed_outputs: serialized.stashed_outputs,
+            pooling: serialized
+                .pooling
+                .into_iter()
+                .map(|columns| {
+                    columns
+                        .into_iter()
+                        .map(|(column, aggregation, inputs)| PoolingColumn {
+                            column,
+                            aggregation,
+                            inputs,
+                        })
+                        .collect()
+                })
+                .collect(),
+            biases: serialized
+                .biases
+                .into_iter()
+                .map(|bias| bias.map(RowDVector::from_vec))
+                .collect(),
+            weight_groups: serialized
+                .weight_groups
+                .into_iter()
+                .map(|(group, tied_weights)| {
+                    let tied_weights = tied_weights
+                        .into_iter()
+                        .map(|(stage, row, column, scale)| TiedWeight {
+                            stage,
+                            row,
+                            column,
+                            scale,
+                        })
+                        .collect();
+                    (group, tied_weights)
+                })
+                .collect(),
+            outputs: serialized.outputs,
+            output_vector_activation,
+            #[cfg(feature = "profile")]
+            stage_timings: Default::default(),
+        })
+    }
+}
+
+impl MatrixFeedforwardEvaluator {
+    /// Encodes the evaluator in a compact binary form using `bincode` with variable length integers.
+    ///
+    /// Fails if the evaluator contains custom activations.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, &'static str> {
+        bincode::DefaultOptions::new()
+            .serialize(self)
+            .map_err(|_| "custom activations can not be serialized")
+    }
+
+    /// Decodes an evaluator from the binary form created by [`MatrixFeedforwardEvaluator::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        bincode::DefaultOptions::new()
+            .deserialize(bytes)
+            .map_err(|_| "bytes do not encode an evaluator")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use crate::{
+        edges,
+        matrix::feedforward::{
+            evaluator::MatrixFeedforwardEvaluator, vector_activation::VectorActivationKind,
+        },
+        network::{net::Net, ActivationKind, Aggregation, Evaluator, Fabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn binary_form_round_trips_and_is_smaller_than_json() {
+        let mut nodes = nodes!('l', 'l', 't', 'g', 's', 'r');
+        nodes[3].set_aggregation(Aggregation::Max);
+        let some_net = Net::new(
+            2,
+            2,
+            nodes,
+            edges!(
+                0--0.5->2,
+                1---0.25->2,
+                0--0.75->3,
+                1--1.5->3,
+                2--1.0->4,
+                3--0.5->4,
+                2---2.0->5
+            ),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let bytes = evaluator.to_bytes().unwrap();
+        let json = serde_json::to_string(&evaluator).unwrap();
+        assert!(bytes.len() < json.len());
+
+        let decoded = MatrixFeedforwardEvaluator::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.stages, evaluator.stages);
+        assert_eq!(decoded.activation_kinds, evaluator.activation_kinds);
+        assert_eq!(decoded.stage_nodes, evaluator.stage_nodes);
+        assert_eq!(decoded.pooling, evaluator.pooling);
+        assert_eq!(
+            decoded.evaluate(dmatrix![0.3, -1.2]),
+            evaluator.evaluate(dmatrix![0.3, -1.2])
+        );
+    }
+
+    #[test]
+    fn rejects_custom_activations() {
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&Net::new(
+            1,
+            1,
+            nodes!('l', 's'),
+            edges!(0--0.5->1),
+        ))
+        .unwrap();
+        evaluator.activation_kinds[0][0] = ActivationKind::Custom;
+
+        assert_eq!(
+            evaluator.to_bytes().err(),
+            Some("custom activations can not be serialized")
+        );
+    }
+
+    #[test]
+    fn keeps_built_in_output_vector_activations() {
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&Net::new(
+            1,
+            2,
+            nodes!('l', 's', 't'),
+            edges!(0--0.5->1, 0---1.5->2),
+        ))
+        .unwrap();
+        evaluator.set_output_vector_activation(VectorActivationKind::L2Normalize);
+
+        let decoded =
+            MatrixFeedforwardEvaluator::from_bytes(&evaluator.to_bytes().unwrap()).unwrap();
+        assert!(decoded.approx_eq(&evaluator, 0.0));
+
+        evaluator.set_output_vector_activation(VectorActivationKind::Custom(|_| {}));
+        assert_eq!(
+            evaluator.to_bytes().err(),
+            Some("custom activations can not be serialized")
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use nalgebra::DMatrix;
+
+use super::evaluator::MatrixFeedforwardEvaluator;
+
+/// An activation acting on whole output rows instead of single values, see [`MatrixFeedforwardEvaluator::set_output_vector_activation`].
+pub type VectorActivation = fn(&mut DMatrix<f64>);
+
+/// Names the built-in vector activations, any other function is wrapped as [`VectorActivationKind::Custom`].
+///
+/// Like [`crate::network::ActivationKind`] this avoids recognizing built-in functions by comparing `fn` pointers.
+#[derive(Debug, Clone, Copy)]
+pub enum VectorActivationKind {
+    Softmax,
+    L2Normalize,
+    Custom(VectorActivation),
+}
+
+impl VectorActivationKind {
+    /// Returns the function computing the activation.
+    pub fn function(self) -> VectorActivation {
+        match self {
+            VectorActivationKind::Softmax => softmax,
+            VectorActivationKind::L2Normalize => l2_normalize,
+            VectorActivationKind::Custom(f) => f,
+        }
+    }
+
+    /// Returns the name of the built-in vector activation, [`VectorActivationKind::Custom`] has none.
+    pub fn name(self) -> Option<&'static str> {
+        match self {
+            VectorActivationKind::Softmax => Some("softmax"),
+            VectorActivationKind::L2Normalize => Some("l2_normalize"),
+            VectorActivationKind::Custom(_) => None,
+        }
+    }
+
+    /// Looks up the built-in kind with the given [`VectorActivationKind::name`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            VectorActivationKind::Softmax,
+            VectorActivationKind::L2Normalize,
+        ]
+        .iter()
+        .find(|kind| kind.name() == Some(name))
+        .cloned()
+    }
+}
+
+/// Turns every row into probabilities, subtracting the maximum of a row first to keep large values from overflowing.
+pub fn softmax(output: &mut DMatrix<f64>) {
+    softmax_with_temperature(output, 1.0);
+}
+
+// divides by `temperature` before exponentiating, shared with `crate::wrapper::softmax::SoftmaxEvaluator`
+pub(crate) fn softmax_with_temperature(output: &mut DMatrix<f64>, temperature: f64) {
+    for mut row in output.row_iter_mut() {
+        let max = row.max() / temperature;
+        row.apply(|value| *value = (*value / temperature - max).exp());
+        let sum = row.sum();
+        row /= sum;
+    }
+}
+
+/// Scales every row to unit euclidean length, rows of zeros are left as they are.
+pub fn l2_normalize(output: &mut DMatrix<f64>) {
+    for mut row in output.row_iter_mut() {
+        let norm = row.norm();
+        if norm > 0.0 {
+            row /= norm;
+        }
+    }
+}
+
+impl MatrixFeedforwardEvaluator {
+    /// Applies the activation of `kind` to the outputs of the net after all stages, e.g. [`softmax`] or [`l2_normalize`].
+    ///
+    /// It receives one sample per row and one column per output of the net, see [`MatrixFeedforwardEvaluator::outputs`],
+    /// any other columns kept behind the outputs are left as they are.
+    /// [`MatrixFeedforwardEvaluator::backward`] differentiates the built-in vector activations and panics on [`VectorActivationKind::Custom`],
+    /// [`MatrixFeedforwardEvaluator::output_interval`] describes the outputs before it.
+    pub fn set_output_vector_activation(&mut self, kind: VectorActivationKind) {
+        self.output_vector_activation = Some(kind);
+    }
+
+    // applies the output vector activation, if any, to the output columns of `output`
+    pub(crate) fn apply_output_vector_activation(&self, output: &mut DMatrix<f64>) {
+        if let Some(kind) = self.output_vector_activation {
+            let output_vector_activation = kind.function();
+            if output.ncols() == self.outputs {
+                output_vector_activation(output);
+            } else {
+                let mut outputs = output.columns(0, self.outputs).clone_owned();
+                output_vector_activation(&mut outputs);
+                output.columns_mut(0, self.outputs).copy_from(&outputs);
+            }
+        }
+    }
+
+    // turns the gradient with respect to the activated outputs into the gradient with respect to `output`, the values before activation
+    pub(crate) fn output_vector_activation_backward(
+        &self,
+        output: &DMatrix<f64>,
+        grad: &mut DMatrix<f64>,
+    ) {
+        let kind = match self.output_vector_activation {
+            Some(kind) => kind,
+            None => return,
+        };
+        let outputs = output.columns(0, self.outputs);
+        let mut grad = grad.columns_mut(0, self.outputs);
+
+        match kind {
+            VectorActivationKind::Softmax => {
+                let mut probabilities = outputs.clone_owned();
+                softmax(&mut probabilities);
+                for (mut grad, probabilities) in grad.row_iter_mut().zip(probabilities.row_iter()) {
+                    // the jacobian of softmax is diag(p) - p p^T
+                    let weighted = grad.dot(&probabilities);
+                    grad.zip_apply(&probabilities, |grad, probability| {
+                        *grad = probability * (*grad - weighted)
+                    });
+                }
+            }
+            VectorActivationKind::L2Normalize => {
+                for (mut grad, values) in grad.row_iter_mut().zip(outputs.row_iter()) {
+                    let norm = values.norm();
+                    // rows of zeros are passed through unchanged
+                    if norm > 0.0 {
+                        let normalized = values / norm;
+                        let weighted = grad.dot(&normalized);
+                        grad.zip_apply(&normalized, |grad, normalized| {
+                            *grad = (*grad - normalized * weighted) / norm
+                        });
+                    }
+                }
+            }
+            VectorActivationKind::Custom(_) => {
+                panic!("custom output vector activations can not be differentiated")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::VectorActivationKind;
+    use crate::{
+        edges,
+        network::{net::Net, Evaluator, Fabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn applies_softmax_over_outputs() {
+        let some_net = Net::new(
+            2,
+            3,
+            nodes!('l', 'l', 'l', 't', 's'),
+            edges!(0--1.0->2, 1--2.0->3, 0--0.5->4, 1---1.5->4),
+        );
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let input = dmatrix![0.5f64, -1.0; 3.0, 2.0];
+        let before = evaluator.evaluate(input.clone());
+
+        evaluator.set_output_vector_activation(VectorActivationKind::Softmax);
+        let after = evaluator.evaluate(input.clone());
+
+        for (before, after) in before.row_iter().zip(after.row_iter()) {
+            assert!((after.sum() - 1.0).abs() < 1e-12);
+            // softmax keeps the order of values within a row
+            assert_eq!(before.transpose().argmax().0, after.transpose().argmax().0);
+        }
+
+        evaluator.set_output_vector_activation(VectorActivationKind::L2Normalize);
+        let normalized = evaluator.evaluate(input.rows(0, 1).clone_owned());
+        assert!((normalized.norm() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn leaves_columns_behind_outputs_alone() {
+        // node 1 has no consumers and is kept behind the output
+        let some_net = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(0--2.0->1, 0--0.5->2));
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(evaluator.output_dim(), 2);
+        assert_eq!(evaluator.outputs, 1);
+
+        evaluator.set_output_vector_activation(VectorActivationKind::Softmax);
+
+        assert_eq!(evaluator.evaluate(dmatrix![3.0]), dmatrix![1.0, 6.0]);
+    }
+
+    #[test]
+    fn every_path_applies_and_differentiates_vector_activation() {
+        let some_net = Net::new(
+            2,
+            3,
+            nodes!('l', 'l', 't', 'l', 't', 's'),
+            edges!(0--0.5->2, 1---1.0->2, 2--1.5->3, 0--0.25->4, 2--0.75->5),
+        );
+        let input = dmatrix![0.4f64, -0.3];
+
+        for vector_activation in [
+            VectorActivationKind::Softmax,
+            VectorActivationKind::L2Normalize,
+        ] {
+            let mut evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+            evaluator.set_output_vector_activation(vector_activation);
+            let expected = evaluator.evaluate(input.clone());
+
+            let (outputs, _) = evaluator.evaluate_with_preactivation(vec![0.4, -0.3]);
+            assert_eq!(outputs, expected.iter().cloned().collect::<Vec<f64>>());
+            let ids = evaluator.stage_nodes.last().unwrap().clone();
+            assert_eq!(
+                evaluator.evaluate_outputs(vec![0.4, -0.3], &ids[1..2]),
+                vec![expected[1]]
+            );
+
+            let jacobian = evaluator.jacobian(&input);
+            let step = 1e-6;
+            for column in 0..input.ncols() {
+                let mut plus = input.clone();
+                plus[(0, column)] += step;
+                let mut minus = input.clone();
+                minus[(0, column)] -= step;
+                let numerical =
+                    (evaluator.evaluate(plus) - evaluator.evaluate(minus)) / (2.0 * step);
+
+                for output in 0..3 {
+                    assert!((jacobian[(output, column)] - numerical[(0, output)]).abs() < 1e-6);
+                }
+            }
+        }
+    }
+}
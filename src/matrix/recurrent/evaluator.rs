@@ -1,39 +1,286 @@
 use nalgebra::DMatrix;
 
 use crate::{
-    matrix::feedforward::evaluator::MatrixFeedforwardEvaluator,
-    network::{Evaluator, NetworkIO, StatefulEvaluator},
+    matrix::feedforward::{evaluator::MatrixFeedforwardEvaluator, scratch::EvalScratch},
+    network::{net::Net, Evaluator, NetworkIO, StatefulEvaluator},
 };
 
-#[derive(Debug)]
+/// Opaque snapshot of the internal state of a recurrent evaluator.
+///
+/// Taking and restoring a checkpoint only copies the memory, see [`MatrixRecurrentEvaluator::memory_size`],
+/// which is far cheaper than fabricating the net again to get back to a known state.
+#[derive(Debug, Clone)]
+pub struct RecurrentCheckpoint {
+    internal: DMatrix<f64>,
+}
+
+impl RecurrentCheckpoint {
+    pub(crate) fn new(internal: &DMatrix<f64>) -> Self {
+        Self {
+            internal: internal.clone(),
+        }
+    }
+
+    pub(crate) fn restore_into(&self, internal: &mut DMatrix<f64>) {
+        assert_eq!(
+            self.internal.shape(),
+            internal.shape(),
+            "checkpoint taken from a different net"
+        );
+        internal.copy_from(&self.internal);
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct MatrixRecurrentEvaluator {
     pub internal: DMatrix<f64>,
     pub evaluator: MatrixFeedforwardEvaluator,
     pub outputs: usize,
+    /// Columns of the unrolled output kept in `internal` for the next evaluation, all of them if `None`.
+    pub carried: Option<Vec<usize>>,
+    /// The net [`MatrixRecurrentEvaluator::evaluator`] was fabricated from, if retained.
+    pub unrolled_net: Option<Net>,
+    /// Inputs followed by `internal`, overwritten by every evaluation instead of allocating a new matrix.
+    pub concat_buf: DMatrix<f64>,
+    /// Intermediate results of every evaluation, see [`EvalScratch`].
+    pub scratch: EvalScratch,
+}
+
+impl MatrixRecurrentEvaluator {
+    /// Returns the number of values carried from one evaluation to the next.
+    ///
+    /// There is one slot per output of the net plus one per distinct start node of recurrent edges that is not an output itself,
+    /// independent of how many recurrent edges leave that node.
+    /// Recurrent edges delayed by more than one evaluation add slots, see [`crate::network::net::unroll`].
+    /// With [`super::fabricator::MatrixRecurrentOptions::compact_memory`] outputs without outgoing recurrent edges have no slot.
+    pub fn memory_size(&self) -> usize {
+        self.internal.len()
+    }
+
+    /// Returns the unrolled net computing a single step, with one wrapper input per slot of memory behind the inputs of the net.
+    ///
+    /// Only retained with [`super::fabricator::MatrixRecurrentOptions::retain_unrolled_net`], see [`crate::network::net::unroll`] for its structure.
+    pub fn unrolled_net(&self) -> Option<&Net> {
+        self.unrolled_net.as_ref()
+    }
+
+    /// Snapshots the internal state.
+    pub fn checkpoint(&self) -> RecurrentCheckpoint {
+        RecurrentCheckpoint::new(&self.internal)
+    }
+
+    /// Returns the internal state to a snapshot taken from this evaluator.
+    pub fn restore(&mut self, checkpoint: &RecurrentCheckpoint) {
+        checkpoint.restore_into(&mut self.internal);
+    }
 }
 
 impl StatefulEvaluator for MatrixRecurrentEvaluator {
     fn evaluate<T: NetworkIO>(&mut self, input: T) -> T {
-        let mut input = NetworkIO::input(input);
-        input = DMatrix::from_iterator(
-            1,
-            input.len() + self.internal.len(),
-            input.iter().chain(self.internal.iter()).cloned(),
+        let input = NetworkIO::input(input);
+        let inputs = self.evaluator.input_dim() - self.internal.len();
+        assert_eq!(
+            input.ncols(),
+            inputs,
+            "expected {} inputs, got {}",
+            inputs,
+            input.ncols()
         );
+        self.concat_buf.columns_mut(0, inputs).copy_from(&input);
+        self.concat_buf
+            .columns_mut(inputs, self.internal.len())
+            .copy_from(&self.internal);
 
-        self.internal = self.evaluator.evaluate(input);
+        let unrolled_output = self
+            .evaluator
+            .evaluate_with_scratch(&self.concat_buf, &mut self.scratch);
+        let output = unrolled_output.columns(0, self.outputs).clone_owned();
 
-        NetworkIO::output(DMatrix::from_iterator(
-            1,
-            self.outputs,
-            self.internal
-                .view((0, 0), (1, self.outputs))
-                .iter()
-                .cloned(),
-        ))
+        self.internal = match &self.carried {
+            Some(carried) => unrolled_output.select_columns(carried),
+            None => unrolled_output,
+        };
+
+        NetworkIO::output(output)
     }
 
     fn reset_internal_state(&mut self) {
         self.internal = DMatrix::from_element(1, self.internal.len(), 0.0);
     }
+
+    fn map_internal_state(&mut self, f: &mut dyn FnMut(f64) -> f64) {
+        self.internal.apply(|value| *value = f(*value));
+    }
+}
+
+/// Advances a batch of independent sequences through the same fabricated [`MatrixRecurrentEvaluator`].
+///
+/// Every row of `internal` holds the state of one sequence, all of them are advanced by a single pass through the stages.
+#[derive(Debug, Clone)]
+pub struct MatrixRecurrentBatchEvaluator {
+    pub internal: DMatrix<f64>,
+    pub evaluator: MatrixFeedforwardEvaluator,
+    pub outputs: usize,
+    pub carried: Option<Vec<usize>>,
+}
+
+impl MatrixRecurrentBatchEvaluator {
+    /// Creates a batch evaluator for `batch_size` sequences sharing the weights of `evaluator`, all starting from zero state.
+    pub fn new(evaluator: MatrixRecurrentEvaluator, batch_size: usize) -> Self {
+        Self {
+            internal: DMatrix::from_element(batch_size, evaluator.internal.len(), 0.0),
+            evaluator: evaluator.evaluator,
+            outputs: evaluator.outputs,
+            carried: evaluator.carried,
+        }
+    }
+
+    /// Evaluates one step of every sequence, `inputs` holds one row per sequence and so does the result.
+    pub fn evaluate_batch(&mut self, inputs: DMatrix<f64>) -> DMatrix<f64> {
+        assert_eq!(
+            inputs.nrows(),
+            self.internal.nrows(),
+            "expected one row of inputs per sequence"
+        );
+        let expected_inputs = self.evaluator.input_dim() - self.internal.ncols();
+        assert_eq!(
+            inputs.ncols(),
+            expected_inputs,
+            "expected {} inputs, got {}",
+            expected_inputs,
+            inputs.ncols()
+        );
+
+        let mut input = DMatrix::zeros(inputs.nrows(), inputs.ncols() + self.internal.ncols());
+        input.columns_mut(0, inputs.ncols()).copy_from(&inputs);
+        input
+            .columns_mut(inputs.ncols(), self.internal.ncols())
+            .copy_from(&self.internal);
+
+        let unrolled_output: DMatrix<f64> = self.evaluator.evaluate(input);
+        let output = unrolled_output.columns(0, self.outputs).clone_owned();
+
+        self.internal = match &self.carried {
+            Some(carried) => unrolled_output.select_columns(carried),
+            None => unrolled_output,
+        };
+
+        output
+    }
+
+    pub fn reset_internal_state(&mut self) {
+        self.internal.fill(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{dmatrix, DMatrix};
+
+    use super::MatrixRecurrentBatchEvaluator;
+    use crate::{
+        edges,
+        matrix::recurrent::fabricator::MatrixRecurrentFabricator,
+        network::{net::Net, Evaluator, StatefulEvaluator, StatefulFabricator},
+        nodes,
+    };
+
+    #[test]
+    fn reused_buffers_match_fresh_concatenation() {
+        let some_net = Net::with_recurrent(
+            2,
+            1,
+            nodes!('l', 'l', 't', 's'),
+            edges!(0--0.5->2, 1---0.75->2, 2--1.5->3),
+            edges!(2--0.25->2, 3---0.5->2),
+        );
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        let unrolled = evaluator.evaluator.clone();
+        let mut internal = evaluator.internal.clone();
+
+        for step in 0..1000 {
+            let input = dmatrix![(step as f64 * 0.1).sin(), (step as f64 * 0.07).cos()];
+
+            // concatenate into a new matrix like evaluations did before reusing buffers
+            let concatenated = DMatrix::from_iterator(
+                1,
+                input.len() + internal.len(),
+                input.iter().chain(internal.iter()).cloned(),
+            );
+            internal = unrolled.evaluate(concatenated);
+
+            assert_eq!(
+                evaluator.evaluate(input),
+                internal.columns(0, 1).clone_owned()
+            );
+        }
+    }
+
+    #[test]
+    fn restores_checkpoint() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 's'), edges!(0--1.0->1));
+        some_net.set_recurrent_edges(edges!(1--0.5->1));
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        evaluator.evaluate(dmatrix![1.0]);
+
+        let checkpoint = evaluator.checkpoint();
+        let first = evaluator.evaluate(dmatrix![0.5]);
+        let second = evaluator.evaluate(dmatrix![0.5]);
+        assert_ne!(first, second);
+
+        evaluator.restore(&checkpoint);
+        assert_eq!(evaluator.evaluate(dmatrix![0.5]), first);
+    }
+
+    #[test]
+    fn clone_diverges_independently() {
+        let some_net =
+            Net::with_recurrent(1, 1, nodes!('l', 'l'), edges!(0--1.0->1), edges!(1--0.5->1));
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        evaluator.evaluate(dmatrix![2.0]);
+
+        let mut clone = evaluator.clone();
+        assert_eq!(clone.internal, evaluator.internal);
+
+        assert_eq!(evaluator.evaluate(dmatrix![2.0]), dmatrix![3.0]);
+        assert_eq!(clone.evaluate(dmatrix![0.0]), dmatrix![1.0]);
+        // each copy only sees its own history
+        assert_eq!(evaluator.evaluate(dmatrix![0.0]), dmatrix![1.5]);
+        assert_eq!(clone.evaluate(dmatrix![0.0]), dmatrix![0.5]);
+    }
+
+    #[test]
+    fn batch_matches_independent_evaluators() {
+        let mut some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 't', 'l', 'l'),
+            edges!(
+                0--1.0->1,
+                0--1.0->2,
+                1--1.0->3,
+                2--1.0->3
+            ),
+        );
+        some_net.set_recurrent_edges(edges!(
+            3--0.5->1,
+            3--0.5->2
+        ));
+
+        let mut first = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        let mut second = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        let mut batch = MatrixRecurrentBatchEvaluator::new(
+            MatrixRecurrentFabricator::fabricate(&some_net).unwrap(),
+            2,
+        );
+
+        for (a, b) in &[(1.0, -1.0), (0.5, 2.0), (0.0, 0.0), (-3.0, 1.5)] {
+            let result = batch.evaluate_batch(dmatrix![*a; *b]);
+
+            assert_eq!(result.row(0), first.evaluate(dmatrix![*a]).row(0));
+            assert_eq!(result.row(1), second.evaluate(dmatrix![*b]).row(0));
+        }
+    }
 }
@@ -1,9 +1,10 @@
 use nalgebra::DMatrix;
 
 use crate::{
-    matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+    matrix::feedforward::{fabricator::MatrixFeedforwardFabricator, scratch::EvalScratch},
     network::{
-        net::unroll, EdgeLike, Fabricator, NetworkLike, NodeLike, Recurrent, StatefulFabricator,
+        net::unroll_with, EdgeLike, Fabricator, NetworkLike, NodeLike, Recurrent,
+        StatefulFabricator,
     },
 };
 
@@ -11,6 +12,76 @@ use super::evaluator::MatrixRecurrentEvaluator;
 
 pub struct MatrixRecurrentFabricator;
 
+/// Configures the evaluator built by [`MatrixRecurrentFabricator::fabricate_with`].
+///
+/// [`StatefulFabricator::fabricate`] uses the default options.
+#[derive(Debug, Clone, Default)]
+pub struct MatrixRecurrentOptions {
+    /// Only keep memory for nodes that recurrent edges start at.
+    ///
+    /// By default every output has a slot in `internal`, in output order, followed by the other start nodes of recurrent edges.
+    /// With this option `internal` holds the outputs that recurrent edges start at, in output order, followed by the other start nodes.
+    pub compact_memory: bool,
+    /// Keep the unrolled net in the evaluator, see [`MatrixRecurrentEvaluator::unrolled_net`].
+    pub retain_unrolled_net: bool,
+}
+
+impl MatrixRecurrentFabricator {
+    /// Fabricates like [`StatefulFabricator::fabricate`] but configured by the given options.
+    pub fn fabricate_with<N: NodeLike, E: EdgeLike>(
+        net: &impl Recurrent<N, E>,
+        options: &MatrixRecurrentOptions,
+    ) -> Result<MatrixRecurrentEvaluator, &'static str> {
+        let unrolled = unroll_with(net, !options.compact_memory);
+        let outputs = net.outputs().len();
+
+        let carried = if options.compact_memory {
+            // mirror the order of wrapping inputs chosen by unroll
+            let mut sorted_outputs = net.outputs();
+            sorted_outputs.sort_unstable();
+            let recurrent_edges = net.recurrent_edges();
+
+            let mut carried: Vec<usize> = sorted_outputs
+                .iter()
+                .enumerate()
+                .filter(|(_, output)| {
+                    // leaky integrators have an implicit recurrent self-connection
+                    output.time_constant().is_some()
+                        || recurrent_edges
+                            .iter()
+                            .any(|edge| edge.enabled() && edge.start() == output.id())
+                })
+                .map(|(position, _)| position)
+                .collect();
+            carried.extend(outputs..unrolled.outputs().len());
+            Some(carried)
+        } else {
+            None
+        };
+        let memory = carried
+            .as_ref()
+            .map_or(unrolled.outputs().len(), |carried| carried.len());
+
+        // every slot of memory is fed back through one additional input of the unrolled net
+        if unrolled.inputs().len() != net.inputs().len() + memory {
+            return Err("recurrent unroll produced inconsistent memory layout");
+        }
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&unrolled)?;
+        let scratch = EvalScratch::new(&evaluator, 1);
+
+        Ok(MatrixRecurrentEvaluator {
+            internal: DMatrix::from_element(1, memory, 0.0),
+            evaluator,
+            outputs,
+            carried,
+            concat_buf: DMatrix::zeros(1, unrolled.inputs().len()),
+            unrolled_net: options.retain_unrolled_net.then_some(unrolled),
+            scratch,
+        })
+    }
+}
+
 impl<N, E> StatefulFabricator<N, E> for MatrixRecurrentFabricator
 where
     N: NodeLike,
@@ -19,17 +90,7 @@ where
     type Output = MatrixRecurrentEvaluator;
 
     fn fabricate(net: &impl Recurrent<N, E>) -> Result<Self::Output, &'static str> {
-        let unrolled = unroll(net);
-        let evaluator = MatrixFeedforwardFabricator::fabricate(&unrolled)?;
-        let memory = unrolled.outputs().len();
-
-        assert!(unrolled.inputs().len() - net.inputs().len() == memory);
-
-        Ok(MatrixRecurrentEvaluator {
-            internal: DMatrix::from_element(1, memory, 0.0),
-            evaluator,
-            outputs: net.outputs().len(),
-        })
+        Self::fabricate_with(net, &MatrixRecurrentOptions::default())
     }
 }
 
@@ -39,8 +100,11 @@ mod tests {
 
     use crate::{
         edges,
-        matrix::recurrent::fabricator::MatrixRecurrentFabricator,
-        network::{net::Net, StatefulEvaluator, StatefulFabricator},
+        matrix::recurrent::fabricator::{MatrixRecurrentFabricator, MatrixRecurrentOptions},
+        network::{
+            net::{activations, Net, Node},
+            NetworkLike, StatefulEvaluator, StatefulFabricator,
+        },
         nodes,
     };
 
@@ -62,6 +126,158 @@ mod tests {
         assert_eq!(result, dmatrix![5.0]);
     }
 
+    #[test]
+    fn delays_recurrent_edge_by_two_steps() {
+        let mut delayed = edges!(1--1.0->2);
+        delayed[0].set_delay(2);
+        let some_net = Net::with_recurrent(1, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->1), delayed);
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        // the output, the start node and one more step of delay
+        assert_eq!(evaluator.memory_size(), 3);
+
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![0.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![2.0]), dmatrix![0.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![0.0]), dmatrix![1.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![0.0]), dmatrix![2.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![0.0]), dmatrix![0.0]);
+    }
+
+    #[test]
+    fn reports_inconsistent_memory_layout() {
+        // the output is listed twice but only one of its copies can keep the recurrent edge
+        let some_net = Net::with_recurrent(
+            1,
+            2,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+            ],
+            edges!(0--1.0->1),
+            edges!(1--0.5->1),
+        );
+
+        let result = MatrixRecurrentFabricator::fabricate_with(
+            &some_net,
+            &MatrixRecurrentOptions {
+                compact_memory: true,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            result.unwrap_err(),
+            "recurrent unroll produced inconsistent memory layout"
+        );
+    }
+
+    #[test]
+    fn retains_unrolled_net() {
+        let some_net = Net::with_recurrent(
+            2,
+            1,
+            nodes!('l', 'l', 't', 'l'),
+            edges!(0--1.0->2, 1--0.5->2, 2--1.0->3),
+            edges!(2--0.5->2, 3---1.0->2),
+        );
+
+        let evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        assert!(evaluator.unrolled_net().is_none());
+
+        let evaluator = MatrixRecurrentFabricator::fabricate_with(
+            &some_net,
+            &MatrixRecurrentOptions {
+                retain_unrolled_net: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let unrolled = evaluator.unrolled_net().unwrap();
+
+        assert_eq!(unrolled.inputs().len(), 2 + evaluator.memory_size());
+        assert_eq!(unrolled.outputs().len(), evaluator.memory_size());
+    }
+
+    #[test]
+    fn skips_disabled_recurrent_edges() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+        let mut recurrent_edges = edges!(1--1.0->1);
+        recurrent_edges[0].set_enabled(false);
+        some_net.set_recurrent_edges(recurrent_edges);
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(evaluator.evaluate(dmatrix![5.0]), dmatrix![5.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![5.0]), dmatrix![5.0]);
+    }
+
+    #[test]
+    fn fabricates_net_constructed_with_recurrent_edges() {
+        let some_net =
+            Net::with_recurrent(1, 1, nodes!('l', 'l'), edges!(0--1.0->1), edges!(1--0.5->1));
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(evaluator.evaluate(dmatrix![4.0]), dmatrix![4.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![4.0]), dmatrix![6.0]);
+    }
+
+    #[test]
+    fn compact_memory_only_keeps_recurrent_nodes() {
+        let some_net = Net::with_recurrent(
+            1,
+            3,
+            nodes!('l', 't', 'l', 'l', 'l'),
+            edges!(
+                0--1.0->1,
+                1--1.0->2,
+                1--0.5->3,
+                1---0.5->4
+            ),
+            edges!(1--0.5->1, 4--0.3->1),
+        );
+
+        let mut full = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        let mut compact = MatrixRecurrentFabricator::fabricate_with(
+            &some_net,
+            &MatrixRecurrentOptions {
+                compact_memory: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(full.memory_size(), 4);
+        assert_eq!(compact.memory_size(), 2);
+
+        for input in &[1.0, 0.5, -2.0, 0.0] {
+            assert_eq!(
+                compact.evaluate(dmatrix![*input]),
+                full.evaluate(dmatrix![*input])
+            );
+        }
+    }
+
+    #[test]
+    fn leaky_node_approaches_input_exponentially() {
+        let mut leaky = Node::new(1, activations::LINEAR);
+        leaky.set_time_constant(Some(0.5));
+        let some_net = Net::new(
+            1,
+            1,
+            vec![Node::new(0, activations::LINEAR), leaky],
+            edges!(0--1.0->1),
+        );
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+
+        for step in 1..=6 {
+            let expected = 1.0 - 0.5f64.powi(step);
+            assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![expected]);
+        }
+    }
+
     #[test]
     fn stateful_net_evaluator_0() {
         let mut some_net = Net::new(
@@ -92,4 +308,34 @@ mod tests {
         let result = evaluator.evaluate(dmatrix![0.0, 0.0]);
         assert_eq!(result, dmatrix![0.0, 5.0]);
     }
+
+    #[test]
+    fn stateful_net_evaluator_two_hidden_nodes() {
+        let mut some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(
+                0--1.0->1,
+                0--1.0->2,
+                1--1.0->3,
+                2--1.0->3
+            ),
+        );
+
+        some_net.set_recurrent_edges(edges!(
+            3--1.0->1,
+            3--1.0->2
+        ));
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+
+        // only the output is remembered as it is the start of both recurrent edges
+        assert_eq!(evaluator.memory_size(), 1);
+
+        let result = evaluator.evaluate(dmatrix![1.0]);
+        assert_eq!(result, dmatrix![2.0]);
+
+        let result = evaluator.evaluate(dmatrix![1.0]);
+        assert_eq!(result, dmatrix![6.0]);
+    }
 }
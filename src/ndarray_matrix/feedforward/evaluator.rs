@@ -0,0 +1,203 @@
+use nalgebra::DMatrix;
+use ndarray::{s, Array1, Array2, Axis};
+
+use crate::{
+    matrix::feedforward::{
+        evaluator::{dispatch, MatrixFeedforwardEvaluator, PoolingColumn},
+        vector_activation::VectorActivationKind,
+    },
+    network::{ActivationKind, Evaluator, NetworkIO},
+};
+
+/// Evaluates the same stages as [`MatrixFeedforwardEvaluator`] with `ndarray` arrays and `ndarray`'s `dot` instead of nalgebra.
+///
+/// Every field mirrors the field of the same name of [`MatrixFeedforwardEvaluator`] it is converted from.
+/// [`NetworkIO`] is still built on nalgebra, so [`Evaluator::evaluate`] converts on the way in and out,
+/// [`NdarrayFeedforwardEvaluator::evaluate_array`] avoids that.
+#[derive(Debug, Clone)]
+pub struct NdarrayFeedforwardEvaluator {
+    pub stages: Vec<Array2<f64>>,
+    pub transformations: Vec<crate::Transformations>,
+    pub activation_kinds: Vec<Vec<ActivationKind>>,
+    pub input_nodes: Vec<usize>,
+    pub stage_nodes: Vec<Vec<usize>>,
+    pub dispatch_by_kind: bool,
+    pub stashed_outputs: Vec<(usize, usize, usize)>,
+    pub pooling: Vec<Vec<PoolingColumn>>,
+    pub biases: Vec<Option<Array1<f64>>>,
+    pub outputs: usize,
+    /// Works on nalgebra matrices, so the outputs are converted for it if set.
+    pub output_vector_activation: Option<VectorActivationKind>,
+}
+
+fn to_array(matrix: &DMatrix<f64>) -> Array2<f64> {
+    Array2::from_shape_fn(matrix.shape(), |index| matrix[index])
+}
+
+fn to_matrix(array: &Array2<f64>) -> DMatrix<f64> {
+    DMatrix::from_fn(array.nrows(), array.ncols(), |row, column| {
+        array[(row, column)]
+    })
+}
+
+impl From<MatrixFeedforwardEvaluator> for NdarrayFeedforwardEvaluator {
+    fn from(evaluator: MatrixFeedforwardEvaluator) -> Self {
+        Self {
+            stages: evaluator.stages.iter().map(to_array).collect(),
+            transformations: evaluator.transformations,
+            activation_kinds: evaluator.activation_kinds,
+            input_nodes: evaluator.input_nodes,
+            stage_nodes: evaluator.stage_nodes,
+            dispatch_by_kind: evaluator.dispatch_by_kind,
+            stashed_outputs: evaluator.stashed_outputs,
+            pooling: evaluator.pooling,
+            biases: evaluator
+                .biases
+                .into_iter()
+                .map(|bias| bias.map(|bias| bias.iter().cloned().collect()))
+                .collect(),
+            outputs: evaluator.outputs,
+            output_vector_activation: evaluator.output_vector_activation,
+        }
+    }
+}
+
+impl NdarrayFeedforwardEvaluator {
+    /// Evaluates one sample per row of `input`, like [`Evaluator::evaluate`] does with a `DMatrix`.
+    pub fn evaluate_array(&self, mut state: Array2<f64>) -> Array2<f64> {
+        assert_eq!(
+            state.ncols(),
+            self.input_nodes.len(),
+            "expected {} inputs, got {}",
+            self.input_nodes.len(),
+            state.ncols()
+        );
+
+        let mut stashed = Vec::with_capacity(self.stashed_outputs.len());
+
+        for (stage, ((stage_matrix, transformations), activation_kinds)) in self
+            .stages
+            .iter()
+            .zip(&self.transformations)
+            .zip(&self.activation_kinds)
+            .enumerate()
+        {
+            let mut next = state.dot(stage_matrix);
+            for pooling in &self.pooling[stage] {
+                for row in 0..state.nrows() {
+                    next[(row, pooling.column)] = pooling.pool(&state, row);
+                }
+            }
+            if let Some(bias) = &self.biases[stage] {
+                next += bias;
+            }
+            // every row of the state is an independent sample, so activations apply per column
+            for ((mut column, &activation_kind), &activation) in next
+                .axis_iter_mut(Axis(1))
+                .zip(activation_kinds)
+                .zip(transformations)
+            {
+                if self.dispatch_by_kind {
+                    column.mapv_inplace(|value| dispatch(activation_kind, activation, value));
+                } else {
+                    column.mapv_inplace(activation);
+                }
+            }
+            for &(stashed_stage, column, output) in &self.stashed_outputs {
+                if stashed_stage == stage {
+                    stashed.push((output, next.column(column).to_owned()));
+                }
+            }
+            state = next;
+        }
+        for (output, values) in stashed {
+            state.column_mut(output).assign(&values);
+        }
+        if let Some(kind) = self.output_vector_activation {
+            let mut outputs = to_matrix(&state.slice(s![.., ..self.outputs]).to_owned());
+            (kind.function())(&mut outputs);
+            state
+                .slice_mut(s![.., ..self.outputs])
+                .assign(&to_array(&outputs));
+        }
+        state
+    }
+}
+
+impl Evaluator for NdarrayFeedforwardEvaluator {
+    fn evaluate<T: NetworkIO>(&self, input: T) -> T {
+        let output = self.evaluate_array(to_array(&NetworkIO::input(input)));
+        NetworkIO::output(to_matrix(&output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::NdarrayFeedforwardEvaluator;
+    use crate::{
+        edges,
+        network::{net::Net, Aggregation, Evaluator},
+        nodes, MatrixFeedforwardFabricator, MatrixFeedforwardOptions,
+    };
+
+    fn assert_parity(some_net: &Net, options: &MatrixFeedforwardOptions) {
+        let matrix = MatrixFeedforwardFabricator::fabricate_with(some_net, options).unwrap();
+        let ndarray = NdarrayFeedforwardEvaluator::from(matrix.clone());
+
+        let input = dmatrix![0.5f64, -1.0; 2.0, 0.25; 0.0, 0.0];
+        let expected = matrix.evaluate(input.clone());
+        let result = ndarray.evaluate(input);
+
+        assert_eq!(result.shape(), expected.shape());
+        for (result, expected) in result.iter().zip(expected.iter()) {
+            assert!((result - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn matches_matrix_evaluator() {
+        let mut pooling_nodes = nodes!('l', 'l', 't', 'r', 'l', 's', 'g');
+        pooling_nodes[4].set_aggregation(Aggregation::Max);
+        let fixtures = vec![
+            Net::new(
+                2,
+                2,
+                nodes!('l', 'l', 't', 's', 'g'),
+                edges!(0--0.5->2, 1---1.5->2, 2--2.0->3, 0--0.75->4),
+            ),
+            Net::new(
+                2,
+                2,
+                pooling_nodes,
+                edges!(
+                    0--0.5->2,
+                    1--1.5->3,
+                    2--1.0->4,
+                    3---2.0->4,
+                    4--0.5->5,
+                    1--0.25->6
+                ),
+            ),
+        ];
+
+        for some_net in &fixtures {
+            assert_parity(some_net, &MatrixFeedforwardOptions::default());
+            assert_parity(
+                some_net,
+                &MatrixFeedforwardOptions {
+                    dispatch_by_kind: true,
+                    ..Default::default()
+                },
+            );
+            assert_parity(
+                some_net,
+                &MatrixFeedforwardOptions {
+                    linear_outputs: true,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
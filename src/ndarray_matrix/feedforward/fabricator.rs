@@ -0,0 +1,31 @@
+use crate::{
+    matrix::feedforward::fabricator::{MatrixFeedforwardFabricator, MatrixFeedforwardOptions},
+    network::{EdgeLike, Fabricator, NetworkLike, NodeLike},
+};
+
+use super::evaluator::NdarrayFeedforwardEvaluator;
+
+/// Compiles a net like [`MatrixFeedforwardFabricator`] and converts the stages into `ndarray` arrays.
+pub struct NdarrayFeedforwardFabricator;
+
+impl NdarrayFeedforwardFabricator {
+    /// Fabricates like [`Fabricator::fabricate`] but configured by the options of [`MatrixFeedforwardFabricator::fabricate_with`].
+    pub fn fabricate_with<N: NodeLike, E: EdgeLike>(
+        net: &impl NetworkLike<N, E>,
+        options: &MatrixFeedforwardOptions,
+    ) -> Result<NdarrayFeedforwardEvaluator, &'static str> {
+        MatrixFeedforwardFabricator::fabricate_with(net, options).map(Into::into)
+    }
+}
+
+impl<N, E> Fabricator<N, E> for NdarrayFeedforwardFabricator
+where
+    N: NodeLike,
+    E: EdgeLike,
+{
+    type Output = NdarrayFeedforwardEvaluator;
+
+    fn fabricate(net: &impl NetworkLike<N, E>) -> Result<Self::Output, &'static str> {
+        Self::fabricate_with(net, &MatrixFeedforwardOptions::default())
+    }
+}
@@ -0,0 +1,2 @@
+pub mod evaluator;
+pub mod fabricator;
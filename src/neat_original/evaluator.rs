@@ -1,15 +1,38 @@
+use std::fmt;
+
 use nalgebra::DMatrix;
 
 use crate::network::{NetworkIO, StatefulEvaluator};
 
-#[derive(Debug)]
+/// Reported by [`NeatOriginalEvaluator::evaluate_checked`] when not every output became active within the allowed passes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsettledError {
+    /// Number of passes made before giving up.
+    pub iterations: usize,
+    /// Positions of the outputs that were still inactive.
+    pub inactive_outputs: Vec<usize>,
+}
+
+impl fmt::Display for UnsettledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "outputs {:?} still inactive after {} iterations",
+            self.inactive_outputs, self.iterations
+        )
+    }
+}
+
+impl std::error::Error for UnsettledError {}
+
+#[derive(Debug, Clone)]
 pub struct DependentNode {
     pub activation_function: fn(f64) -> f64,
     pub inputs: Vec<(usize, f64, bool)>,
     pub is_active: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct NeatOriginalEvaluator {
     pub input_ids: Vec<usize>,
     pub output_ids: Vec<usize>,
@@ -17,31 +40,76 @@ pub struct NeatOriginalEvaluator {
     pub node_input_sum: Vec<f64>,
     // [0] is current output, [1] it output before that
     pub node_active_output: Vec<[f64; 2]>,
+    /// Number of passes over all nodes the last evaluation took until every output was active.
+    pub last_iteration_count: usize,
 }
 
 impl NeatOriginalEvaluator {
-    fn outputs_off(&self) -> bool {
-        for &id in self.output_ids.iter() {
-            if !self.nodes[id].is_active {
-                return true;
-            }
+    /// Evaluates the same input `passes` times and returns the last output, like repeated activations in original NEAT.
+    ///
+    /// A single [`StatefulEvaluator::evaluate`] stops as soon as every output is active,
+    /// which can be before values along longer paths reached them.
+    /// Priming with several passes lets the outputs settle, `activate_n(input, 1)` equals a single `evaluate`.
+    /// Use [`StatefulEvaluator::reset_internal_state`] to flush the state between unrelated inputs.
+    pub fn activate_n<T: NetworkIO>(&mut self, input: T, passes: usize) -> T {
+        let input: DMatrix<f64> = NetworkIO::input(input);
+        let mut output = DMatrix::zeros(1, self.output_ids.len());
+
+        for _ in 0..passes {
+            output = self.evaluate(input.clone());
         }
-        false
+
+        NetworkIO::output(output)
     }
-}
 
-impl StatefulEvaluator for NeatOriginalEvaluator {
-    fn evaluate<T: NetworkIO>(&mut self, input: T) -> T {
-        let input = NetworkIO::input(input);
+    /// Returns how many passes the last evaluation took, a proxy for the depth of the net.
+    pub fn last_iteration_count(&self) -> usize {
+        self.last_iteration_count
+    }
 
+    /// Evaluates like [`StatefulEvaluator::evaluate`] but gives up after `max_iterations` passes.
+    ///
+    /// Outputs that are not reachable from any input through non-recurrent edges never become active,
+    /// where [`StatefulEvaluator::evaluate`] would loop forever this reports them instead.
+    /// The internal state is left as it was when giving up.
+    pub fn evaluate_checked<T: NetworkIO>(
+        &mut self,
+        input: T,
+        max_iterations: usize,
+    ) -> Result<T, UnsettledError> {
+        if self.settle(NetworkIO::input(input), Some(max_iterations)) {
+            Ok(NetworkIO::output(self.output()))
+        } else {
+            Err(UnsettledError {
+                iterations: self.last_iteration_count,
+                inactive_outputs: self
+                    .output_ids
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &id)| !self.nodes[id].is_active)
+                    .map(|(position, _)| position)
+                    .collect(),
+            })
+        }
+    }
+
+    // passes over all nodes until every output is active, tells if that happened within `max_iterations`
+    fn settle(&mut self, input: DMatrix<f64>, max_iterations: Option<usize>) -> bool {
         for (&id, &value) in self.input_ids.iter().zip(input.iter()) {
             self.node_active_output[id][0] = value;
             self.nodes[id].is_active = true;
         }
 
         let mut onetime = false;
+        self.last_iteration_count = 0;
 
         while self.outputs_off() || !onetime {
+            if max_iterations
+                .is_some_and(|max_iterations| self.last_iteration_count >= max_iterations)
+            {
+                return false;
+            }
+
             for id in 0..self.nodes.len() {
                 if !self.input_ids.contains(&id) {
                     self.node_input_sum[id] = 0.0;
@@ -72,15 +140,37 @@ impl StatefulEvaluator for NeatOriginalEvaluator {
             }
 
             onetime = true;
+            self.last_iteration_count += 1;
         }
 
-        NetworkIO::output(DMatrix::from_iterator(
+        true
+    }
+
+    fn output(&self) -> DMatrix<f64> {
+        DMatrix::from_iterator(
             1,
             self.output_ids.len(),
             self.output_ids
                 .iter()
-                .map(|&id| self.node_active_output[id][0]), // .collect::<Vec<_>>(),
-        ))
+                .map(|&id| self.node_active_output[id][0]),
+        )
+    }
+
+    fn outputs_off(&self) -> bool {
+        for &id in self.output_ids.iter() {
+            if !self.nodes[id].is_active {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl StatefulEvaluator for NeatOriginalEvaluator {
+    fn evaluate<T: NetworkIO>(&mut self, input: T) -> T {
+        self.settle(NetworkIO::input(input), None);
+
+        NetworkIO::output(self.output())
     }
 
     fn reset_internal_state(&mut self) {
@@ -91,4 +181,126 @@ impl StatefulEvaluator for NeatOriginalEvaluator {
             *value = [0.0; 2];
         }
     }
+
+    fn map_internal_state(&mut self, f: &mut dyn FnMut(f64) -> f64) {
+        for value in self.node_active_output.iter_mut().flatten() {
+            *value = f(*value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::UnsettledError;
+    use crate::{
+        edges,
+        neat_original::fabricator::NeatOriginalFabricator,
+        network::{net::Net, Evaluator, Fabricator, StatefulEvaluator, StatefulFabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn settles_after_enough_activations() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 't', 't', 'l'),
+            edges!(
+                0--0.5->1,
+                1--0.5->2,
+                2--0.5->3,
+                0--0.5->3
+            ),
+        );
+
+        let expected = MatrixFeedforwardFabricator::fabricate(&some_net)
+            .unwrap()
+            .evaluate(dmatrix![1.0]);
+
+        let mut evaluator = NeatOriginalFabricator::fabricate(&some_net).unwrap();
+
+        // the skip connection activates the output before the deeper path delivered its value
+        assert_ne!(evaluator.activate_n(dmatrix![1.0], 1), expected);
+
+        evaluator.reset_internal_state();
+        assert_eq!(evaluator.activate_n(dmatrix![1.0], 3), expected);
+        assert_eq!(evaluator.activate_n(dmatrix![1.0], 1), expected);
+    }
+
+    #[test]
+    fn accepts_any_network_io() {
+        let mut some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 's'),
+            edges!(
+                0--0.5->2,
+                1---0.5->2
+            ),
+        );
+        some_net.set_recurrent_edges(edges!(2--0.7->2));
+
+        let mut vec_evaluator = NeatOriginalFabricator::fabricate(&some_net).unwrap();
+        let mut matrix_evaluator = NeatOriginalFabricator::fabricate(&some_net).unwrap();
+
+        for input in &[[1.0, 0.0], [0.5, 2.0], [-1.0, 1.0]] {
+            let output: Vec<f64> = vec_evaluator.evaluate(input.to_vec());
+            let expected = matrix_evaluator.evaluate(dmatrix![input[0], input[1]]);
+
+            assert_eq!(output.len(), 1);
+            assert_eq!(output[0], expected[0]);
+        }
+    }
+
+    #[test]
+    fn reports_outputs_that_never_activate() {
+        let mut some_net = Net::new(
+            1,
+            2,
+            nodes!('l', 'l', 'l'),
+            edges!(
+                0--1.0->1
+            ),
+        );
+        // the second output is only fed by a recurrent edge and never becomes active
+        some_net.set_recurrent_edges(edges!(1--1.0->2));
+
+        let mut evaluator = NeatOriginalFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(
+            evaluator.evaluate_checked(vec![1.0], 10),
+            Err(UnsettledError {
+                iterations: 10,
+                inactive_outputs: vec![1],
+            })
+        );
+    }
+
+    #[test]
+    fn counts_iterations_along_chain() {
+        // the chain mostly runs against the order nodes are visited in
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l', 'l'),
+            edges!(
+                0--1.0->3,
+                3--1.0->2,
+                2--1.0->1,
+                1--1.0->4
+            ),
+        );
+
+        let mut evaluator = NeatOriginalFabricator::fabricate(&some_net).unwrap();
+
+        // activity reaches node 3, then node 2, then nodes 1 and 4 which come in visiting order
+        evaluator.evaluate(vec![2.0]);
+        assert_eq!(evaluator.last_iteration_count(), 3);
+
+        // nodes stay active, the chain is not traced again
+        evaluator.evaluate(vec![2.0]);
+        assert_eq!(evaluator.last_iteration_count(), 1);
+    }
 }
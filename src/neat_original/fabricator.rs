@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::network::{EdgeLike, NodeLike, StatefulFabricator};
+use crate::network::{ordered_enabled_edges, Aggregation, EdgeLike, NodeLike, StatefulFabricator};
 
 use super::evaluator::{DependentNode, NeatOriginalEvaluator};
 
@@ -15,6 +15,17 @@ where
     type Output = super::evaluator::NeatOriginalEvaluator;
 
     fn fabricate(net: &impl crate::network::Recurrent<N, E>) -> Result<Self::Output, &'static str> {
+        if net.nodes().iter().any(|node| {
+            !matches!(
+                node.aggregation(),
+                Aggregation::WeightedSum | Aggregation::Sum
+            )
+        }) {
+            return Err(
+                "aggregations other than sums are not supported by this fabricator, net invalid",
+            );
+        }
+
         let mut nodes: Vec<DependentNode> = Vec::new();
 
         let node_input_sum: Vec<f64> = vec![0.0; net.nodes().len()];
@@ -33,7 +44,7 @@ where
             });
         }
 
-        for edge in net.edges() {
+        for edge in ordered_enabled_edges(net) {
             nodes[*id_map.get(&edge.end()).unwrap()].inputs.push((
                 *id_map.get(&edge.start()).unwrap(),
                 edge.weight(),
@@ -41,7 +52,11 @@ where
             ))
         }
 
-        for edge in net.recurrent_edges() {
+        for edge in net
+            .recurrent_edges()
+            .into_iter()
+            .filter(|edge| edge.enabled())
+        {
             nodes[*id_map.get(&edge.end()).unwrap()].inputs.push((
                 *id_map.get(&edge.start()).unwrap(),
                 edge.weight(),
@@ -63,6 +78,7 @@ where
             nodes,
             node_input_sum,
             node_active_output,
+            last_iteration_count: 0,
         })
     }
 }
@@ -0,0 +1,177 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::net::activations::{
+    derivatives::{d_gaussian, d_inverse, d_linear, d_relu, d_sigmoid, d_squared, d_tanh},
+    CUSTOM_CODE, GAUSSIAN, INVERSE, LINEAR, RELU, SIGMOID, SQUARED, TANH,
+};
+
+/// Names the built-in activations of [`super::net::activations`].
+///
+/// Comparing `fn` pointers is fragile as identical functions are not guaranteed to share an address.
+/// Features that need to know which activation a node uses query this enum instead.
+/// Any activation that is not built-in is [`ActivationKind::Custom`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ActivationKind {
+    Linear,
+    Sigmoid,
+    Tanh,
+    Gaussian,
+    Inverse,
+    Relu,
+    Squared,
+    Custom,
+}
+
+impl ActivationKind {
+    /// All kinds that correspond to a built-in activation.
+    pub const BUILT_IN: [ActivationKind; 7] = [
+        ActivationKind::Linear,
+        ActivationKind::Sigmoid,
+        ActivationKind::Tanh,
+        ActivationKind::Gaussian,
+        ActivationKind::Inverse,
+        ActivationKind::Relu,
+        ActivationKind::Squared,
+    ];
+
+    /// Returns the built-in activation function, [`ActivationKind::Custom`] has none.
+    pub fn function(self) -> Option<fn(f64) -> f64> {
+        match self {
+            ActivationKind::Linear => Some(LINEAR),
+            ActivationKind::Sigmoid => Some(SIGMOID),
+            ActivationKind::Tanh => Some(TANH),
+            ActivationKind::Gaussian => Some(GAUSSIAN),
+            ActivationKind::Inverse => Some(INVERSE),
+            ActivationKind::Relu => Some(RELU),
+            ActivationKind::Squared => Some(SQUARED),
+            ActivationKind::Custom => None,
+        }
+    }
+
+    /// Returns the derivative of the built-in activation, [`ActivationKind::Custom`] has none.
+    pub fn derivative(self) -> Option<fn(f64) -> f64> {
+        match self {
+            ActivationKind::Linear => Some(d_linear),
+            ActivationKind::Sigmoid => Some(d_sigmoid),
+            ActivationKind::Tanh => Some(d_tanh),
+            ActivationKind::Gaussian => Some(d_gaussian),
+            ActivationKind::Inverse => Some(d_inverse),
+            ActivationKind::Relu => Some(d_relu),
+            ActivationKind::Squared => Some(d_squared),
+            ActivationKind::Custom => None,
+        }
+    }
+
+    /// Returns the name of the built-in activation, [`ActivationKind::Custom`] has none.
+    pub fn name(self) -> Option<&'static str> {
+        match self {
+            ActivationKind::Linear => Some("linear"),
+            ActivationKind::Sigmoid => Some("sigmoid"),
+            ActivationKind::Tanh => Some("tanh"),
+            ActivationKind::Gaussian => Some("gaussian"),
+            ActivationKind::Inverse => Some("inverse"),
+            ActivationKind::Relu => Some("relu"),
+            ActivationKind::Squared => Some("squared"),
+            ActivationKind::Custom => None,
+        }
+    }
+
+    /// Looks up the built-in kind with the given [`ActivationKind::name`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::BUILT_IN
+            .iter()
+            .find(|kind| kind.name() == Some(name))
+            .cloned()
+    }
+
+    /// Returns the compact numeric code of the kind, see [`super::net::activations::to_code`].
+    pub fn code(self) -> u8 {
+        match self {
+            ActivationKind::Custom => CUSTOM_CODE,
+            kind => kind as u8,
+        }
+    }
+
+    /// Recovers the kind of a function by comparing it against the built-in activations.
+    ///
+    /// This is the pointer comparison the enum is meant to avoid, it serves as fallback for [`super::NodeLike::activation_kind`].
+    pub fn from_function(activation: fn(f64) -> f64) -> Self {
+        Self::BUILT_IN
+            .iter()
+            .find(|kind| {
+                kind.function()
+                    .is_some_and(|built_in| built_in as usize == activation as usize)
+            })
+            .cloned()
+            .unwrap_or(ActivationKind::Custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ActivationKind;
+    use crate::network::net::activations::{
+        derivatives, GAUSSIAN, INVERSE, LINEAR, RELU, SIGMOID, SQUARED, TANH,
+    };
+
+    #[test]
+    fn maps_kinds_to_functions() {
+        let expected = [LINEAR, SIGMOID, TANH, GAUSSIAN, INVERSE, RELU, SQUARED];
+
+        for (kind, function) in ActivationKind::BUILT_IN.iter().zip(expected.iter()) {
+            let kind_function = kind.function().unwrap();
+            for &value in &[-2.0, -0.5, 0.0, 0.5, 2.0] {
+                assert_eq!(kind_function(value), function(value));
+            }
+            assert_eq!(ActivationKind::from_function(*function), *kind);
+        }
+
+        assert!(ActivationKind::Custom.function().is_none());
+        assert_eq!(
+            ActivationKind::from_function(|val| val * 3.0),
+            ActivationKind::Custom
+        );
+    }
+
+    #[test]
+    fn relu_derivative() {
+        assert_eq!(derivatives::d_relu(2.0), 1.0);
+        assert_eq!(derivatives::d_relu(-1.0), 0.0);
+    }
+
+    #[test]
+    fn derivatives_match_finite_differences() {
+        let step = 1e-6;
+
+        for kind in ActivationKind::BUILT_IN.iter() {
+            let function = kind.function().unwrap();
+            let derivative = kind.derivative().unwrap();
+            for &value in &[-1.5, -0.3, 0.4, 1.2] {
+                let numerical = (function(value + step) - function(value - step)) / (2.0 * step);
+                assert!(
+                    (derivative(value) - numerical).abs() < 1e-6,
+                    "{:?} at {}",
+                    kind,
+                    value
+                );
+            }
+        }
+
+        assert!(ActivationKind::Custom.derivative().is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn round_trips_through_serde() {
+        for &kind in ActivationKind::BUILT_IN
+            .iter()
+            .chain(&[ActivationKind::Custom])
+        {
+            let serialized = serde_json::to_string(&kind).unwrap();
+            let deserialized: ActivationKind = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(deserialized, kind);
+        }
+    }
+}
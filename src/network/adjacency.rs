@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use nalgebra::DMatrix;
+
+use super::{EdgeLike, NetworkLike, NodeLike, Recurrent};
+
+/// Builds the weighted adjacency matrix of the enabled edges of a net.
+///
+/// Returns the node ids in ascending order and a square matrix whose entry at row `i` and column `j`
+/// is the weight of the edge from the `i`-th to the `j`-th of these nodes. Zero entries mean no edge, parallel edges are summed.
+/// Recurrent edges are left out, see [`recurrent_adjacency_matrix`].
+pub fn adjacency_matrix<N: NodeLike, E: EdgeLike>(
+    net: &impl NetworkLike<N, E>,
+) -> (Vec<usize>, DMatrix<f64>) {
+    let ids = sorted_ids(net);
+    let matrix = weights(&ids, net.edges());
+    (ids, matrix)
+}
+
+/// Builds the weighted adjacency matrix of the enabled recurrent edges of a net, in the same node order as [`adjacency_matrix`].
+pub fn recurrent_adjacency_matrix<N: NodeLike, E: EdgeLike>(
+    net: &impl Recurrent<N, E>,
+) -> (Vec<usize>, DMatrix<f64>) {
+    let ids = sorted_ids(net);
+    let matrix = weights(&ids, net.recurrent_edges());
+    (ids, matrix)
+}
+
+fn sorted_ids<N: NodeLike, E: EdgeLike>(net: &impl NetworkLike<N, E>) -> Vec<usize> {
+    let mut ids: Vec<usize> = net.nodes().iter().map(|node| node.id()).collect();
+    ids.sort_unstable();
+    ids
+}
+
+fn weights<E: EdgeLike>(ids: &[usize], edges: Vec<&E>) -> DMatrix<f64> {
+    let positions: HashMap<usize, usize> = ids
+        .iter()
+        .enumerate()
+        .map(|(position, &id)| (id, position))
+        .collect();
+
+    let mut matrix = DMatrix::zeros(ids.len(), ids.len());
+    for edge in edges.into_iter().filter(|edge| edge.enabled()) {
+        matrix[(positions[&edge.start()], positions[&edge.end()])] += edge.weight();
+    }
+    matrix
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{adjacency_matrix, recurrent_adjacency_matrix};
+    use crate::{edges, network::net::Net, nodes};
+
+    #[test]
+    fn places_weights_at_start_row_and_end_column() {
+        let some_net = Net::with_recurrent(
+            1,
+            1,
+            nodes!('l', 's', 'l'),
+            edges!(0--0.5->1, 1---2.0->2, 0--0.25->2),
+            edges!(2--0.75->1),
+        );
+
+        let (ids, matrix) = adjacency_matrix(&some_net);
+        assert_eq!(ids, vec![0, 1, 2]);
+        assert_eq!(
+            matrix,
+            dmatrix![
+                0.0, 0.5, 0.25;
+                0.0, 0.0, -2.0;
+                0.0, 0.0, 0.0
+            ]
+        );
+
+        let (_, recurrent) = recurrent_adjacency_matrix(&some_net);
+        assert_eq!(recurrent[(2, 1)], 0.75);
+        assert_eq!(recurrent.sum(), 0.75);
+    }
+}
@@ -0,0 +1,72 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How a node combines its incoming values before applying its activation.
+///
+/// All aggregations work on the incoming values multiplied by their edge weights,
+/// so pure pooling is expressed with weights of `1.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Aggregation {
+    /// The default aggregation every node has unless stated otherwise.
+    #[default]
+    WeightedSum,
+    /// Same value as [`Aggregation::WeightedSum`] and compiled the same way, available so genomes can name it explicitly.
+    Sum,
+    Mean,
+    Max,
+    Min,
+    /// Multiplies the weighted inputs, which lets gates of LSTM or GRU like cells scale one signal by another.
+    Product,
+}
+
+impl Aggregation {
+    /// Tells if the aggregation can be expressed as a weighted sum and therefore as a matrix multiplication.
+    pub fn is_linear(self) -> bool {
+        matches!(
+            self,
+            Aggregation::WeightedSum | Aggregation::Sum | Aggregation::Mean
+        )
+    }
+
+    /// Combines already weighted values, yields `0.0` for no values.
+    pub fn aggregate(self, weighted_values: impl Iterator<Item = f64>) -> f64 {
+        let (result, count) = weighted_values.fold((None, 0usize), |(result, count), value| {
+            let result = match (self, result) {
+                (_, None) => value,
+                (Aggregation::Max, Some(max)) => value.max(max),
+                (Aggregation::Min, Some(min)) => value.min(min),
+                (Aggregation::Product, Some(product)) => value * product,
+                (_, Some(sum)) => sum + value,
+            };
+            (Some(result), count + 1)
+        });
+
+        match (self, result) {
+            (_, None) => 0.0,
+            (Aggregation::Mean, Some(sum)) => sum / count as f64,
+            (_, Some(result)) => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Aggregation;
+
+    #[test]
+    fn aggregates_weighted_values() {
+        let values = [1.0, -3.0, 2.0];
+
+        assert_eq!(
+            Aggregation::WeightedSum.aggregate(values.iter().cloned()),
+            0.0
+        );
+        assert_eq!(Aggregation::Sum.aggregate(values.iter().cloned()), 0.0);
+        assert_eq!(Aggregation::Mean.aggregate(values.iter().cloned()), 0.0);
+        assert_eq!(Aggregation::Max.aggregate(values.iter().cloned()), 2.0);
+        assert_eq!(Aggregation::Min.aggregate(values.iter().cloned()), -3.0);
+        assert_eq!(Aggregation::Product.aggregate(values.iter().cloned()), -6.0);
+        assert_eq!(Aggregation::Max.aggregate(std::iter::empty()), 0.0);
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use petgraph::{graph::NodeIndex, Graph};
+
+use super::{EdgeLike, NetworkLike, NodeLike, Recurrent};
+
+/// Converts a [`NetworkLike`] structure into a [`Graph`] where node weights are node ids and edge weights are edge weights.
+pub fn to_petgraph<N: NodeLike, E: EdgeLike>(net: &impl NetworkLike<N, E>) -> Graph<usize, f64> {
+    build_graph(net, net.edges())
+}
+
+/// Converts a [`Recurrent`] [`NetworkLike`] structure into a [`Graph`] like [`to_petgraph`] but includes the recurrent edges.
+pub fn to_petgraph_recurrent<N: NodeLike, E: EdgeLike>(
+    net: &impl Recurrent<N, E>,
+) -> Graph<usize, f64> {
+    build_graph(
+        net,
+        net.edges()
+            .into_iter()
+            .chain(net.recurrent_edges())
+            .collect(),
+    )
+}
+
+fn build_graph<N: NodeLike, E: EdgeLike>(
+    net: &impl NetworkLike<N, E>,
+    edges: Vec<&E>,
+) -> Graph<usize, f64> {
+    let mut graph = Graph::new();
+
+    let indices: HashMap<usize, NodeIndex> = net
+        .nodes()
+        .iter()
+        .map(|node| (node.id(), graph.add_node(node.id())))
+        .collect();
+
+    for edge in edges.into_iter().filter(|edge| edge.enabled()) {
+        graph.add_edge(indices[&edge.start()], indices[&edge.end()], edge.weight());
+    }
+
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_petgraph, to_petgraph_recurrent};
+    use crate::{
+        edges,
+        network::{net::Net, NetworkLike},
+        nodes,
+    };
+
+    #[test]
+    fn converts_nodes_and_edges() {
+        let mut net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l'),
+            edges!(
+                0--0.5->1,
+                1--0.5->2,
+                0--0.5->2
+            ),
+        );
+        net.set_recurrent_edges(edges!(2--1.0->1));
+
+        let graph = to_petgraph(&net);
+
+        assert_eq!(graph.node_count(), net.nodes().len());
+        assert_eq!(graph.edge_count(), net.edges().len());
+
+        let graph = to_petgraph_recurrent(&net);
+
+        assert_eq!(graph.node_count(), net.nodes().len());
+        assert_eq!(graph.edge_count(), net.edges().len() + 1);
+    }
+}
@@ -0,0 +1,196 @@
+use super::{
+    net::{
+        activations::{LINEAR, SIGMOID, TANH},
+        Edge, Node,
+    },
+    Aggregation,
+};
+
+// slope of the built-in sigmoid, see `SIGMOID`
+const STEEPNESS: f64 = 4.9;
+
+/// Weights of a GRU cell, see [`super::net::gru_cell`].
+///
+/// Every `input_*` matrix has one row per hidden unit and one column per input,
+/// every `hidden_*` matrix has one row per hidden unit and one column per hidden unit,
+/// i.e. entry `[j][i]` weights the contribution of input or previous hidden value `i` to hidden unit `j`.
+#[derive(Debug, Clone)]
+pub struct GruWeights {
+    pub input_reset: Vec<Vec<f64>>,
+    pub input_update: Vec<Vec<f64>>,
+    pub input_candidate: Vec<Vec<f64>>,
+    pub hidden_reset: Vec<Vec<f64>>,
+    pub hidden_update: Vec<Vec<f64>>,
+    pub hidden_candidate: Vec<Vec<f64>>,
+}
+
+/// Builds the nodes, feedforward edges and recurrent edges of a GRU cell without biases.
+///
+/// With `x` the input and `h` the previous output the cell computes
+///
+/// ```text
+/// r  = sigmoid(input_reset * x + hidden_reset * h)
+/// z  = sigmoid(input_update * x + hidden_update * h)
+/// n  = tanh(input_candidate * x + r ⊙ (hidden_candidate * h))
+/// h' = (1 - z) ⊙ n + z ⊙ h
+/// ```
+///
+/// The elementwise products are nodes with [`Aggregation::Product`].
+/// As the built-in [`SIGMOID`] and [`TANH`] are steepened, weights into gates and the candidate are scaled to get the standard functions.
+/// Nodes come as `input_dim` inputs with ids `0..input_dim`, followed by `7 * hidden_dim` hidden nodes
+/// and `hidden_dim` outputs holding `h'`, ids increase in that order.
+/// Offset all ids to splice the cell into a larger net, a constant input can stand in for biases.
+///
+/// Panics if the weight matrices do not match the given dimensions.
+pub fn gru_cell(
+    input_dim: usize,
+    hidden_dim: usize,
+    weights: &GruWeights,
+) -> (Vec<Node>, Vec<Edge>, Vec<Edge>) {
+    for (matrix, columns) in &[
+        (&weights.input_reset, input_dim),
+        (&weights.input_update, input_dim),
+        (&weights.input_candidate, input_dim),
+        (&weights.hidden_reset, hidden_dim),
+        (&weights.hidden_update, hidden_dim),
+        (&weights.hidden_candidate, hidden_dim),
+    ] {
+        assert!(
+            matrix.len() == hidden_dim && matrix.iter().all(|row| row.len() == *columns),
+            "GRU weights do not match dimensions"
+        );
+    }
+
+    // first id of every group of hidden_dim nodes
+    let reset = input_dim;
+    let update = reset + hidden_dim;
+    let hidden_candidate = update + hidden_dim;
+    let gated_candidate = hidden_candidate + hidden_dim;
+    let candidate = gated_candidate + hidden_dim;
+    let update_candidate = candidate + hidden_dim;
+    let update_previous = update_candidate + hidden_dim;
+    let output = update_previous + hidden_dim;
+
+    let mut nodes: Vec<Node> = (0..input_dim).map(|id| Node::new(id, LINEAR)).collect();
+    for (start, activation, aggregation) in &[
+        (reset, SIGMOID, Aggregation::WeightedSum),
+        (update, SIGMOID, Aggregation::WeightedSum),
+        (hidden_candidate, LINEAR, Aggregation::WeightedSum),
+        (gated_candidate, LINEAR, Aggregation::Product),
+        (candidate, TANH, Aggregation::WeightedSum),
+        (update_candidate, LINEAR, Aggregation::Product),
+        (update_previous, LINEAR, Aggregation::Product),
+        (output, LINEAR, Aggregation::WeightedSum),
+    ] {
+        for unit in 0..hidden_dim {
+            let mut node = Node::new(start + unit, *activation);
+            node.set_aggregation(*aggregation);
+            nodes.push(node);
+        }
+    }
+
+    // SIGMOID(x / STEEPNESS) is the standard logistic function, TANH(x / STEEPNESS) the standard tanh
+    let scale = 1.0 / STEEPNESS;
+
+    let mut edges = Vec::new();
+    let mut recurrent_edges = Vec::new();
+    for unit in 0..hidden_dim {
+        for input in 0..input_dim {
+            edges.push(Edge::new(
+                input,
+                reset + unit,
+                weights.input_reset[unit][input] * scale,
+            ));
+            edges.push(Edge::new(
+                input,
+                update + unit,
+                weights.input_update[unit][input] * scale,
+            ));
+            edges.push(Edge::new(
+                input,
+                candidate + unit,
+                weights.input_candidate[unit][input] * scale,
+            ));
+        }
+        for previous in 0..hidden_dim {
+            recurrent_edges.push(Edge::new(
+                output + previous,
+                reset + unit,
+                weights.hidden_reset[unit][previous] * scale,
+            ));
+            recurrent_edges.push(Edge::new(
+                output + previous,
+                update + unit,
+                weights.hidden_update[unit][previous] * scale,
+            ));
+            recurrent_edges.push(Edge::new(
+                output + previous,
+                hidden_candidate + unit,
+                weights.hidden_candidate[unit][previous],
+            ));
+        }
+
+        edges.push(Edge::new(reset + unit, gated_candidate + unit, 1.0));
+        edges.push(Edge::new(
+            hidden_candidate + unit,
+            gated_candidate + unit,
+            1.0,
+        ));
+        edges.push(Edge::new(gated_candidate + unit, candidate + unit, scale));
+
+        edges.push(Edge::new(update + unit, update_candidate + unit, 1.0));
+        edges.push(Edge::new(candidate + unit, update_candidate + unit, 1.0));
+        edges.push(Edge::new(update + unit, update_previous + unit, 1.0));
+        recurrent_edges.push(Edge::new(output + unit, update_previous + unit, 1.0));
+
+        // (1 - z) * n + z * h = n - z * n + z * h
+        edges.push(Edge::new(candidate + unit, output + unit, 1.0));
+        edges.push(Edge::new(update_candidate + unit, output + unit, -1.0));
+        edges.push(Edge::new(update_previous + unit, output + unit, 1.0));
+    }
+
+    (nodes, edges, recurrent_edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{gru_cell, GruWeights};
+    use crate::{
+        network::{net::Net, StatefulEvaluator, StatefulFabricator},
+        MatrixRecurrentFabricator,
+    };
+
+    fn sigmoid(value: f64) -> f64 {
+        1.0 / (1.0 + (-value).exp())
+    }
+
+    #[test]
+    fn steps_like_reference_gru() {
+        let weights = GruWeights {
+            input_reset: vec![vec![0.5]],
+            input_update: vec![vec![-1.5]],
+            input_candidate: vec![vec![2.0]],
+            hidden_reset: vec![vec![1.5]],
+            hidden_update: vec![vec![0.25]],
+            hidden_candidate: vec![vec![-0.75]],
+        };
+        let (nodes, edges, recurrent_edges) = gru_cell(1, 1, &weights);
+        let net = Net::with_recurrent(1, 1, nodes, edges, recurrent_edges);
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&net).unwrap();
+
+        let mut previous = 0.0;
+        for &input in &[1.0, -0.5, 0.25] {
+            let reset = sigmoid(0.5 * input + 1.5 * previous);
+            let update = sigmoid(-1.5 * input + 0.25 * previous);
+            let candidate = (2.0 * input + reset * (-0.75 * previous)).tanh();
+            let expected = (1.0 - update) * candidate + update * previous;
+
+            let result = evaluator.evaluate(dmatrix![input]);
+            assert!((result[0] - expected).abs() < 1e-9);
+            previous = expected;
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use super::{EdgeLike, NetworkLike, NodeLike};
+
+/// Weights are rounded to multiples of this before hashing.
+const WEIGHT_RESOLUTION: f64 = 1e-9;
+
+/// Hashes the topology and weights of a net into a value that is stable across runs, platforms and versions of Rust.
+///
+/// Node ids are replaced by their rank among all node ids of the net,
+/// so nets that only differ by an order preserving relabeling of their nodes hash equal.
+/// Every node contributes its rank, [`super::ActivationKind`], [`super::Aggregation`] and time constant,
+/// every enabled edge its start, end and weight. Custom activations are indistinguishable from each other.
+///
+/// Weights and time constants are quantized to multiples of `1e-9` to make the hash robust against float noise,
+/// e.g. from serialization round-trips. Values closer than that may still land on different sides of a rounding boundary,
+/// so equal hashes only hint at equal nets and a single weight change can leave the hash unchanged if it is tiny.
+pub fn structural_hash<N: NodeLike, E: EdgeLike>(net: &impl NetworkLike<N, E>) -> u64 {
+    let mut hasher = Fnv1a::default();
+
+    let mut nodes = net.nodes();
+    nodes.sort_unstable_by_key(|node| node.id());
+    let ranks: HashMap<usize, u64> = nodes
+        .iter()
+        .enumerate()
+        .map(|(rank, node)| (node.id(), rank as u64))
+        .collect();
+
+    hasher.write(net.inputs().len() as u64);
+    hasher.write(net.outputs().len() as u64);
+    for node in net.inputs().iter().chain(net.outputs().iter()) {
+        hasher.write(ranks[&node.id()]);
+    }
+
+    for node in &nodes {
+        hasher.write(node.activation_kind().code() as u64);
+        hasher.write(node.aggregation() as u64);
+        match node.time_constant() {
+            Some(tau) => hasher.write(quantize(tau)),
+            None => hasher.write(u64::MAX),
+        }
+    }
+
+    let mut edges: Vec<(u64, u64, u64)> = net
+        .edges()
+        .into_iter()
+        .filter(|edge| edge.enabled())
+        .map(|edge| {
+            (
+                ranks[&edge.start()],
+                ranks[&edge.end()],
+                quantize(edge.weight()),
+            )
+        })
+        .collect();
+    edges.sort_unstable();
+
+    hasher.write(edges.len() as u64);
+    for (start, end, weight) in edges {
+        hasher.write(start);
+        hasher.write(end);
+        hasher.write(weight);
+    }
+
+    hasher.0
+}
+
+fn quantize(value: f64) -> u64 {
+    ((value / WEIGHT_RESOLUTION).round() as i64) as u64
+}
+
+// the standard library hashers are not guaranteed to be stable, FNV-1a is simple enough to spell out
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, value: u64) {
+        for byte in value.to_le_bytes() {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::structural_hash;
+    use crate::{edges, network::net::Net, nodes};
+
+    #[test]
+    fn identical_nets_hash_equal() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 't', 's'),
+            edges!(0--0.5->2, 1--0.7->2, 2--1.5->3),
+        );
+        let same_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 't', 's'),
+            edges!(2--1.5->3, 1--0.7->2, 0--0.5->2),
+        );
+
+        assert_eq!(structural_hash(&some_net), structural_hash(&same_net));
+    }
+
+    #[test]
+    fn weight_change_alters_hash() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 't', 's'),
+            edges!(0--0.5->2, 1--0.7->2, 2--1.5->3),
+        );
+        let other_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 't', 's'),
+            edges!(0--0.5->2, 1--0.7->2, 2--1.6->3),
+        );
+
+        assert_ne!(structural_hash(&some_net), structural_hash(&other_net));
+    }
+}
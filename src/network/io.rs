@@ -1,6 +1,9 @@
 use nalgebra::{DMatrix, DVector};
 
 /// Data structures implementing this trait can be used as input and output of networks.
+///
+/// Evaluators work on a `DMatrix` with one row per sample and one column per input or output, so a single sample is a row vector.
+/// Vectors like `DVector`, despite being columns in nalgebra, are read as and filled from a single row.
 pub trait NetworkIO {
     fn input(input: Self) -> DMatrix<f64>;
     fn output(output: DMatrix<f64>) -> Self;
@@ -26,22 +29,169 @@ impl NetworkIO for DVector<f64> {
 
 impl NetworkIO for Vec<f64> {
     fn input(input: Self) -> DMatrix<f64> {
-        DMatrix::from_iterator(1, input.len(), input.into_iter())
+        DMatrix::from_iterator(1, input.len(), input)
     }
     fn output(output: DMatrix<f64>) -> Self {
         output.into_iter().cloned().collect::<Vec<f64>>()
     }
 }
 
+/// Values are widened to `f64` for evaluation and narrowed back to `f32` afterwards.
+///
+/// Widening is exact, narrowing rounds each output to the nearest `f32`,
+/// so outputs carry about seven significant digits and values beyond the `f32` range become infinite.
+///
+/// With two float implementations, unsuffixed literals no longer infer a type, e.g. `evaluator.evaluate(vec![1.0])[0].abs()`
+/// fails to compile with E0689. Suffix one literal, as in `vec![1.0f64]`, or annotate the type to pick an implementation.
+impl NetworkIO for Vec<f32> {
+    fn input(input: Self) -> DMatrix<f64> {
+        DMatrix::from_iterator(1, input.len(), input.into_iter().map(f64::from))
+    }
+    fn output(output: DMatrix<f64>) -> Self {
+        output.into_iter().map(|&value| value as f32).collect()
+    }
+}
+
+/// Casts like the implementation for `Vec<f32>`, keeping the shape of the matrix.
+///
+/// Both casts copy every value, so evaluating `f64` matrices directly avoids that overhead where memory allows it.
+///
+/// As for `Vec<f32>`, unsuffixed literals in `dmatrix!` become ambiguous, write e.g. `dmatrix![1.0f64, 1.0]` for `f64` input.
+impl NetworkIO for DMatrix<f32> {
+    fn input(input: Self) -> DMatrix<f64> {
+        input.map(f64::from)
+    }
+    fn output(output: DMatrix<f64>) -> Self {
+        output.map(|value| value as f32)
+    }
+}
+
+/// Casts like the implementation for `Vec<f32>`, see `DMatrix<f32>` for the cost of the casts.
+impl NetworkIO for DVector<f32> {
+    fn input(input: Self) -> DMatrix<f64> {
+        DMatrix::from_iterator(
+            1,
+            input.len(),
+            input.into_iter().map(|&value| f64::from(value)),
+        )
+    }
+    fn output(output: DMatrix<f64>) -> Self {
+        DVector::from_iterator(output.len(), output.into_iter().map(|&value| value as f32))
+    }
+}
+
 #[cfg(feature = "ndarray")]
 use ndarray::Array1;
 
 #[cfg(feature = "ndarray")]
 impl NetworkIO for Array1<f64> {
     fn input(input: Self) -> DMatrix<f64> {
-        DMatrix::from_iterator(1, input.len(), input.into_iter())
+        DMatrix::from_iterator(1, input.len(), input)
     }
     fn output(output: DMatrix<f64>) -> Self {
         Array1::from_iter(output.into_iter().cloned())
     }
 }
+
+/// Casts like the implementation for `Vec<f32>`.
+#[cfg(feature = "ndarray")]
+impl NetworkIO for Array1<f32> {
+    fn input(input: Self) -> DMatrix<f64> {
+        DMatrix::from_iterator(1, input.len(), input.into_iter().map(f64::from))
+    }
+    fn output(output: DMatrix<f64>) -> Self {
+        Array1::from_iter(output.into_iter().map(|&value| value as f32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{dmatrix, DVector};
+
+    use crate::{
+        edges,
+        network::{net::Net, Evaluator, Fabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn f32_matches_f64_within_tolerance() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 's'),
+            edges!(
+                0--0.3->2,
+                1---0.7->2
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let narrow = evaluator.evaluate(vec![1.5f32, 2.5]);
+        let wide = evaluator.evaluate(vec![1.5f64, 2.5]);
+
+        assert_eq!(narrow.len(), 1);
+        assert!((f64::from(narrow[0]) - wide[0]).abs() < 1e-6);
+
+        let narrow = evaluator.evaluate(DVector::from_vec(vec![1.5f32, 2.5]));
+        assert!((f64::from(narrow[0]) - wide[0]).abs() < 1e-6);
+
+        let narrow = evaluator.evaluate(dmatrix![1.5f32, 2.5; -1.0, 0.5]);
+        let wide = evaluator.evaluate(dmatrix![1.5f64, 2.5; -1.0, 0.5]);
+        assert_eq!(narrow.shape(), (2, 1));
+        for (narrow, wide) in narrow.iter().zip(wide.iter()) {
+            assert!((f64::from(*narrow) - wide).abs() < 1e-6);
+        }
+
+        #[cfg(feature = "ndarray")]
+        {
+            let wide = evaluator.evaluate(vec![1.5f64, 2.5]);
+            let narrow = evaluator.evaluate(ndarray::array![1.5f32, 2.5]);
+            assert!((f64::from(narrow[0]) - wide[0]).abs() < 1e-6);
+        }
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn array_round_trips_like_vec() {
+        let some_net = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 's', 't'),
+            edges!(0--0.3->2, 1---0.7->2, 0--1.5->3),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let array = evaluator.evaluate(ndarray::array![1.5f64, -2.5]);
+        let vec = evaluator.evaluate(vec![1.5f64, -2.5]);
+
+        assert_eq!(array.to_vec(), vec);
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn rejects_array_of_wrong_length() {
+        let some_net = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--1.0->2));
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(
+            evaluator.try_evaluate(ndarray::array![1.0f64]),
+            Err("input length does not match number of inputs of the net")
+        );
+        assert_eq!(
+            evaluator.try_evaluate(ndarray::array![1.0f64, 2.0]),
+            Ok(ndarray::array![3.0])
+        );
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    #[should_panic(expected = "expected 2 inputs, got 3")]
+    fn sparse_evaluator_rejects_array_of_wrong_length() {
+        let some_net = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--1.0->2));
+        let evaluator = crate::SparseMatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        evaluator.evaluate(ndarray::array![1.0f64, 2.0, 3.0]);
+    }
+}
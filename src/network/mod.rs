@@ -1,8 +1,30 @@
 //! Defines vocabulary and interfaces for this crate.
 
+pub use self::activation::ActivationKind;
+pub use self::adjacency::{adjacency_matrix, recurrent_adjacency_matrix};
+pub use self::aggregation::Aggregation;
+#[cfg(feature = "petgraph")]
+pub use self::graph::{to_petgraph, to_petgraph_recurrent};
+pub use self::gru::GruWeights;
+pub use self::hash::structural_hash;
 pub use self::io::NetworkIO;
+pub use self::prune::{prune_dead_nodes, prune_weak_edges, PruneReport};
+pub use self::select::{argmax, top_k};
+pub use self::sequence::SequenceEvaluator;
 
+mod activation;
+mod adjacency;
+mod aggregation;
+#[cfg(feature = "petgraph")]
+mod graph;
+mod gru;
+mod hash;
 mod io;
+mod prune;
+mod select;
+mod sequence;
+
+use std::collections::HashMap;
 
 /// Declares a structure to have [`NodeLike`] properties.
 ///
@@ -11,6 +33,36 @@ mod io;
 pub trait NodeLike: Ord {
     fn id(&self) -> usize;
     fn activation(&self) -> fn(f64) -> f64;
+
+    /// Names the activation returned by [`NodeLike::activation`].
+    ///
+    /// The default implementation compares function pointers against the built-in activations.
+    /// Implementors that know their activation kind should return it directly.
+    fn activation_kind(&self) -> ActivationKind {
+        ActivationKind::from_function(self.activation())
+    }
+
+    /// Returns the derivative of [`NodeLike::activation`] with respect to its input.
+    ///
+    /// Only built-in activations have a known derivative, see [`net::activations::derivatives`].
+    fn activation_derivative(&self) -> Option<fn(f64) -> f64> {
+        self.activation_kind().derivative()
+    }
+
+    /// Tells how the node combines its incoming values, see [`Aggregation`].
+    fn aggregation(&self) -> Aggregation {
+        Aggregation::WeightedSum
+    }
+
+    /// Makes the node a leaky integrator with the given time constant `tau`, by default it has none.
+    ///
+    /// Recurrent fabricators then update the node as `(1 - tau) * previous + tau * input`, where `input` is what the node would receive otherwise,
+    /// including contributions of recurrent edges ending at it, and `previous` is the output of the node in the previous evaluation.
+    /// This amounts to scaling all incoming weights by `tau` and adding a recurrent self-connection with weight `1 - tau`, see [`net::unroll`],
+    /// so it is only meaningful for summing aggregations. Feedforward fabricators and input nodes ignore the time constant.
+    fn time_constant(&self) -> Option<f64> {
+        None
+    }
 }
 
 /// Declares a structure to have [`EdgeLike`] properties.
@@ -20,6 +72,53 @@ pub trait EdgeLike {
     fn start(&self) -> usize;
     fn end(&self) -> usize;
     fn weight(&self) -> f64;
+
+    /// Disabled edges are ignored by all fabricators, like disabled connection genes in NEAT.
+    fn enabled(&self) -> bool {
+        true
+    }
+
+    /// The innovation number of the connection gene this edge stems from in NEAT.
+    ///
+    /// Fabricators process edges with an innovation number first and in ascending order, followed by all others in their given order.
+    /// This only fixes the internal order of processing, the fabricated evaluators compute the same values
+    /// as long as no two enabled edges connect the same nodes.
+    fn innovation(&self) -> Option<u64> {
+        None
+    }
+
+    /// The number of evaluations a recurrent edge delays the value it passes on, by default one, i.e. the previous evaluation.
+    ///
+    /// Only recurrent edges of a [`Recurrent`] net are delayed, a delay of zero is treated like one.
+    /// Every start node keeps one slot of memory per step of its longest delay, see [`net::unroll`].
+    fn delay(&self) -> usize {
+        1
+    }
+
+    /// Ties the weight of this edge to all other edges of the same group, by default it is not tied.
+    ///
+    /// Fabrication still uses the weight of every edge, tied edges are expected to carry the same one.
+    /// The group lets [`crate::MatrixFeedforwardEvaluator::set_group_weight`] update all of them at once after fabrication.
+    fn weight_group(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Collects the enabled edges of a net in the order fabricators process them, see [`EdgeLike::innovation`].
+pub(crate) fn ordered_enabled_edges<N: NodeLike, E: EdgeLike>(
+    net: &impl NetworkLike<N, E>,
+) -> Vec<&E> {
+    let mut edges: Vec<&E> = net
+        .edges()
+        .into_iter()
+        .filter(|edge| edge.enabled())
+        .collect();
+    // stable, so edges without innovation number keep their order
+    edges.sort_by_key(|edge| match edge.innovation() {
+        Some(innovation) => (false, innovation),
+        None => (true, 0),
+    });
+    edges
 }
 
 /// Declares a structure to have network-like properties.
@@ -37,10 +136,85 @@ pub trait NetworkLike<N: NodeLike, E: EdgeLike> {
     fn nodes(&self) -> Vec<&N> {
         self.inputs()
             .into_iter()
-            .chain(self.hidden().into_iter())
-            .chain(self.outputs().into_iter())
+            .chain(self.hidden())
+            .chain(self.outputs())
             .collect()
     }
+
+    /// Returns all edges ending at the given node, in the order of [`NetworkLike::edges`].
+    ///
+    /// Disabled edges are included, recurrent edges of a [`Recurrent`] net are not.
+    /// The default implementation scans all edges and therefore takes O(edges), implementors that index their edges can override it.
+    fn incoming_edges(&self, node_id: usize) -> Vec<&E> {
+        self.edges()
+            .into_iter()
+            .filter(|edge| edge.end() == node_id)
+            .collect()
+    }
+
+    /// Returns all edges starting at the given node, see [`NetworkLike::incoming_edges`].
+    fn outgoing_edges(&self, node_id: usize) -> Vec<&E> {
+        self.edges()
+            .into_iter()
+            .filter(|edge| edge.start() == node_id)
+            .collect()
+    }
+
+    /// Returns the sorted and distinct codes of all activations present, see [`net::activations::to_code`].
+    fn activation_codes(&self) -> Vec<u8> {
+        let mut codes = self
+            .nodes()
+            .iter()
+            .map(|node| node.activation_kind().code())
+            .collect::<Vec<_>>();
+        codes.sort_unstable();
+        codes.dedup();
+        codes
+    }
+
+    /// Tells if the enabled edges form no cycle, i.e. the net can be evaluated by a feedforward fabricator.
+    ///
+    /// Only [`NetworkLike::edges`] are checked, recurrent edges of a [`Recurrent`] net are excluded as they never form a cycle within one evaluation.
+    fn is_acyclic(&self) -> bool {
+        let mut successors: HashMap<usize, Vec<usize>> = HashMap::new();
+        for edge in self.edges().into_iter().filter(|edge| edge.enabled()) {
+            successors.entry(edge.start()).or_default().push(edge.end());
+        }
+
+        // nodes on the current path are `false`, fully explored ones `true`
+        let mut visited: HashMap<usize, bool> = HashMap::new();
+
+        for &root in successors.keys() {
+            if visited.contains_key(&root) {
+                continue;
+            }
+            visited.insert(root, false);
+            let mut path = vec![(root, 0)];
+
+            while let Some((node, next)) = path.last_mut() {
+                let node = *node;
+                match successors.get(&node).and_then(|ends| ends.get(*next)) {
+                    Some(&end) => {
+                        *next += 1;
+                        match visited.get(&end) {
+                            Some(false) => return false,
+                            Some(true) => {}
+                            None => {
+                                visited.insert(end, false);
+                                path.push((end, 0));
+                            }
+                        }
+                    }
+                    None => {
+                        visited.insert(node, true);
+                        path.pop();
+                    }
+                }
+            }
+        }
+
+        true
+    }
 }
 
 /// Declares a [`NetworkLike`] structure to have recurrent edges.
@@ -62,6 +236,19 @@ pub trait Evaluator {
 pub trait StatefulEvaluator {
     fn evaluate<T: NetworkIO>(&mut self, input: T) -> T;
     fn reset_internal_state(&mut self);
+    /// Replaces every value of the internal state with the result of `f` applied to it.
+    ///
+    /// Does nothing by default, evaluators override it to expose their state to wrappers like [`crate::SaturatingRecurrentEvaluator`].
+    fn map_internal_state(&mut self, _f: &mut dyn FnMut(f64) -> f64) {}
+    /// Lazily evaluates `inputs` in order, advancing the internal state with every yielded output.
+    fn evaluate_sequence<T, I>(&mut self, inputs: I) -> SequenceEvaluator<'_, Self, I::IntoIter>
+    where
+        Self: Sized,
+        T: NetworkIO,
+        I: IntoIterator<Item = T>,
+    {
+        SequenceEvaluator::new(self, inputs.into_iter())
+    }
 }
 
 /// A facade behind which the fabrication of a [`NetworkLike`] structure is implemented.
@@ -84,19 +271,58 @@ pub trait StatefulFabricator<N: NodeLike, E: EdgeLike> {
 
 /// Contains an example of a [`Recurrent`] [`NetworkLike`] structure.
 pub mod net {
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
 
-    use super::{EdgeLike, NetworkLike, NodeLike, Recurrent};
+    use super::{ActivationKind, Aggregation, EdgeLike, NetworkLike, NodeLike, Recurrent};
 
-    #[derive(Debug)]
+    pub use super::gru::gru_cell;
+
+    #[derive(Debug, Clone)]
     pub struct Node {
         id: usize,
         activation: fn(f64) -> f64,
+        activation_kind: ActivationKind,
+        aggregation: Aggregation,
+        time_constant: Option<f64>,
     }
 
     impl Node {
+        /// Creates a node with any activation, its [`ActivationKind`] is recovered once by [`ActivationKind::from_function`].
         pub fn new(id: usize, activation: fn(f64) -> f64) -> Self {
-            Self { id, activation }
+            Self {
+                id,
+                activation,
+                activation_kind: ActivationKind::from_function(activation),
+                aggregation: Aggregation::WeightedSum,
+                time_constant: None,
+            }
+        }
+        /// Creates a node with a built-in activation, keeping its kind without comparing function pointers.
+        ///
+        /// Panics for [`ActivationKind::Custom`], which has no function, use [`Node::new`] instead.
+        pub fn with_activation_kind(id: usize, activation_kind: ActivationKind) -> Self {
+            Self {
+                id,
+                activation: activation_kind
+                    .function()
+                    .expect("custom activations need a function, use Node::new"),
+                activation_kind,
+                aggregation: Aggregation::WeightedSum,
+                time_constant: None,
+            }
+        }
+        pub(crate) fn copy_of(node: &impl NodeLike) -> Self {
+            let mut copy = Node::new(node.id(), node.activation());
+            copy.activation_kind = node.activation_kind();
+            copy.set_aggregation(node.aggregation());
+            copy.set_time_constant(node.time_constant());
+            copy
+        }
+        pub fn set_aggregation(&mut self, aggregation: Aggregation) {
+            self.aggregation = aggregation
+        }
+        pub fn set_time_constant(&mut self, time_constant: Option<f64>) {
+            self.time_constant = time_constant
         }
     }
 
@@ -107,6 +333,15 @@ pub mod net {
         fn activation(&self) -> fn(f64) -> f64 {
             self.activation
         }
+        fn activation_kind(&self) -> ActivationKind {
+            self.activation_kind
+        }
+        fn aggregation(&self) -> Aggregation {
+            self.aggregation
+        }
+        fn time_constant(&self) -> Option<f64> {
+            self.time_constant
+        }
     }
 
     impl PartialEq for Node {
@@ -129,16 +364,44 @@ pub mod net {
         }
     }
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone)]
     pub struct Edge {
         start: usize,
         end: usize,
         weight: f64,
+        enabled: bool,
+        delay: usize,
+        weight_group: Option<usize>,
     }
 
     impl Edge {
         pub fn new(start: usize, end: usize, weight: f64) -> Self {
-            Self { start, end, weight }
+            Self {
+                start,
+                end,
+                weight,
+                enabled: true,
+                delay: 1,
+                weight_group: None,
+            }
+        }
+        pub fn set_enabled(&mut self, enabled: bool) {
+            self.enabled = enabled
+        }
+        /// Sets the delay of a recurrent edge, see [`EdgeLike::delay`].
+        pub fn set_delay(&mut self, delay: usize) {
+            self.delay = delay
+        }
+        /// Ties the weight of the edge to a group, see [`EdgeLike::weight_group`].
+        pub fn set_weight_group(&mut self, weight_group: Option<usize>) {
+            self.weight_group = weight_group
+        }
+        pub(crate) fn copy_of(edge: &impl EdgeLike) -> Self {
+            let mut copy = Edge::new(edge.start(), edge.end(), edge.weight());
+            copy.set_enabled(edge.enabled());
+            copy.set_delay(edge.delay());
+            copy.set_weight_group(edge.weight_group());
+            copy
         }
     }
 
@@ -152,10 +415,19 @@ pub mod net {
         fn weight(&self) -> f64 {
             self.weight
         }
+        fn enabled(&self) -> bool {
+            self.enabled
+        }
+        fn delay(&self) -> usize {
+            self.delay
+        }
+        fn weight_group(&self) -> Option<usize> {
+            self.weight_group
+        }
     }
 
     /// [`Net`] is an example of a [`Recurrent`] [`NetworkLike`] structure and also used as an intermediate representation to perform the [`unroll`] operation on [`Recurrent`] [`NetworkLike`] structures.
-    #[derive(Debug)]
+    #[derive(Debug, Clone)]
     pub struct Net {
         inputs: usize,
         outputs: usize,
@@ -207,6 +479,43 @@ pub mod net {
                 recurrent_edges: Vec::new(),
             }
         }
+        /// Creates a net with recurrent edges in one go, instead of calling [`Net::set_recurrent_edges`] afterwards.
+        pub fn with_recurrent(
+            inputs: usize,
+            outputs: usize,
+            nodes: Vec<Node>,
+            edges: Vec<Edge>,
+            recurrent_edges: Vec<Edge>,
+        ) -> Self {
+            Net {
+                inputs,
+                outputs,
+                nodes,
+                edges,
+                recurrent_edges,
+            }
+        }
+        /// Copies any [`NetworkLike`] into a [`Net`], so helpers working on [`Net`] can be used with it.
+        ///
+        /// Inputs, hidden and output nodes keep their order, edges keep their enabled state.
+        pub fn from_network_like<N: NodeLike, E: EdgeLike>(net: &impl NetworkLike<N, E>) -> Net {
+            Net::new(
+                net.inputs().len(),
+                net.outputs().len(),
+                net.nodes().into_iter().map(Node::copy_of).collect(),
+                net.edges().into_iter().map(Edge::copy_of).collect(),
+            )
+        }
+        /// Copies any [`Recurrent`] [`NetworkLike`] into a [`Net`] including its recurrent edges, see [`Net::from_network_like`].
+        pub fn from_recurrent<N: NodeLike, E: EdgeLike>(net: &impl Recurrent<N, E>) -> Net {
+            let mut copy = Net::from_network_like(net);
+            copy.recurrent_edges = net
+                .recurrent_edges()
+                .into_iter()
+                .map(Edge::copy_of)
+                .collect();
+            copy
+        }
         pub fn set_recurrent_edges(&mut self, edges: Vec<Edge>) {
             self.recurrent_edges = edges
         }
@@ -216,36 +525,69 @@ pub mod net {
     ///
     /// It restructures the edges and nodes to be evaluatable in a feedforward manner.
     /// The evaluation further depends on the implementations in [`crate::matrix::recurrent::evaluator`] and [`crate::sparse_matrix::recurrent::evaluator`] which handle the internal state.
+    ///
+    /// A recurrent edge with a [`EdgeLike::delay`] of `d` greater than one reads from a chain of `d - 1` additional wrapping inputs and outputs,
+    /// shifting the value of its start node by one slot every evaluation. Chains are shared by all edges leaving the same node,
+    /// so the memory grows by one slot per start node and step beyond one of its longest delay.
     pub fn unroll<R: Recurrent<N, E>, N: NodeLike, E: EdgeLike>(recurrent: &R) -> Net {
+        unroll_with(recurrent, true)
+    }
+
+    /// Like [`unroll`] but only wraps outputs as inputs of the next evaluation if `wrap_all_outputs` is set or a recurrent edge starts at them.
+    ///
+    /// Wrapping inputs of outputs come first in output order, followed by those of other nodes in order of their first recurrent edge.
+    pub fn unroll_with<R: Recurrent<N, E>, N: NodeLike, E: EdgeLike>(
+        recurrent: &R,
+        wrap_all_outputs: bool,
+    ) -> Net {
         // remember known ids as they can not be reused as otherwise
         // during rewriting edge inputs/outputs stuff would be confused
         let known_ids = recurrent
             .nodes()
             .iter()
             .map(|node| node.id())
-            .collect::<Vec<_>>();
+            .collect::<HashSet<_>>();
+
+        // leaky integrators take only part of their input, see NodeLike::time_constant
+        let time_constants = recurrent
+            .hidden()
+            .into_iter()
+            .chain(recurrent.outputs())
+            .filter_map(|node| node.time_constant().map(|tau| (node.id(), tau)))
+            .collect::<HashMap<_, _>>();
+        let input_share = |end: usize| time_constants.get(&end).cloned().unwrap_or(1.0);
 
         let mut known_edges = recurrent
             .edges()
             .iter()
-            .map(|e| Edge {
-                start: e.start(),
-                end: e.end(),
-                weight: e.weight(),
-            })
+            .filter(|e| e.enabled())
+            .map(|e| Edge::new(e.start(), e.end(), e.weight() * input_share(e.end())))
             .collect::<Vec<_>>();
 
         let mut known_recurrent_edges = recurrent
             .recurrent_edges()
             .iter()
-            .map(|e| Edge {
-                start: e.start(),
-                end: e.end(),
-                weight: e.weight(),
+            .filter(|e| e.enabled())
+            .map(|e| {
+                let mut edge = Edge::new(e.start(), e.end(), e.weight() * input_share(e.end()));
+                edge.set_delay(e.delay().max(1));
+                edge
             })
             .collect::<Vec<_>>();
 
-        let mut new_low_ids = (usize::MIN..usize::MAX).filter(|tmp_id| !known_ids.contains(tmp_id));
+        // and keep the rest of their previous output
+        let mut leaky_nodes = time_constants.into_iter().collect::<Vec<_>>();
+        leaky_nodes.sort_unstable_by_key(|&(id, _)| id);
+        known_recurrent_edges.extend(
+            leaky_nodes
+                .into_iter()
+                .map(|(id, tau)| Edge::new(id, id, 1.0 - tau)),
+        );
+
+        // fresh ids are the lowest unused ones, never `max + 1`, as inputs and outputs need to sort before hidden nodes
+        // and the ids of the net may reach up to usize::MAX
+        let mut new_low_ids =
+            (usize::MIN..=usize::MAX).filter(|tmp_id| !known_ids.contains(tmp_id));
 
         // give static input nodes the lowest possible ids to not fuck up output order by sorting in feedforward fabricator
         let mut known_inputs = recurrent.inputs();
@@ -278,6 +620,9 @@ pub mod net {
                 Node {
                     id: new_id,
                     activation: n.activation(),
+                    activation_kind: n.activation_kind(),
+                    aggregation: n.aggregation(),
+                    time_constant: None,
                 }
             })
             .collect::<Vec<_>>();
@@ -313,20 +658,36 @@ pub mod net {
                 Node {
                     id: new_id,
                     activation: n.activation(),
+                    activation_kind: n.activation_kind(),
+                    aggregation: n.aggregation(),
+                    time_constant: None,
                 }
             })
             .collect::<Vec<_>>();
 
         let mut unroll_map: HashMap<usize, usize> = HashMap::new();
+        // wrapping inputs holding the value of a node from the given number of evaluations ago, beyond the previous one
+        let mut delay_map: HashMap<(usize, usize), usize> = HashMap::new();
 
         // create wrapping input for all original outputs, regardless of if they are used
         // this is to simplify the state transfer inside the stateful matrix evaluator
         for output in &known_outputs {
+            if !wrap_all_outputs
+                && !known_recurrent_edges
+                    .iter()
+                    .any(|edge| edge.start() == output.id())
+            {
+                continue;
+            }
+
             let wrapper_input_id = new_low_ids.next().unwrap();
 
             let wrapper_input_node = Node {
                 id: wrapper_input_id,
-                activation: |val| val,
+                activation: activations::LINEAR,
+                activation_kind: ActivationKind::Linear,
+                aggregation: Aggregation::WeightedSum,
+                time_constant: None,
             };
 
             known_inputs.push(wrapper_input_node);
@@ -341,19 +702,22 @@ pub mod net {
 
                 let wrapper_input_node = Node {
                     id: wrapper_input_id,
-                    activation: |val| val,
+                    activation: activations::LINEAR,
+                    activation_kind: ActivationKind::Linear,
+                    aggregation: Aggregation::WeightedSum,
+                    time_constant: None,
                 };
                 let wrapper_output_node = Node {
                     id: new_low_ids.next().unwrap(),
-                    activation: |val| val,
+                    activation: activations::LINEAR,
+                    activation_kind: ActivationKind::Linear,
+                    aggregation: Aggregation::WeightedSum,
+                    time_constant: None,
                 };
 
                 // used to carry value into next evaluation
-                let outward_wrapping_edge = Edge {
-                    start: recurrent_edge.start(),
-                    weight: 1.0,
-                    end: wrapper_output_node.id(),
-                };
+                let outward_wrapping_edge =
+                    Edge::new(recurrent_edge.start(), wrapper_output_node.id(), 1.0);
 
                 // add nodes for wrapping
                 known_inputs.push(wrapper_input_node);
@@ -365,11 +729,43 @@ pub mod net {
                 wrapper_input_id
             });
 
-            let inward_wrapping_connection = Edge {
-                start: *recurrent_input,
-                end: recurrent_edge.end(),
-                weight: recurrent_edge.weight(),
-            };
+            // every further step of delay shifts the value through one more pair of wrapping nodes
+            let mut recurrent_input = *recurrent_input;
+            for step in 2..=recurrent_edge.delay() {
+                let previous_input = recurrent_input;
+                recurrent_input = *delay_map
+                    .entry((recurrent_edge.start(), step))
+                    .or_insert_with(|| {
+                        let wrapper_input_id = new_low_ids.next().unwrap();
+
+                        let wrapper_input_node = Node {
+                            id: wrapper_input_id,
+                            activation: activations::LINEAR,
+                            activation_kind: ActivationKind::Linear,
+                            aggregation: Aggregation::WeightedSum,
+                            time_constant: None,
+                        };
+                        let wrapper_output_node = Node {
+                            id: new_low_ids.next().unwrap(),
+                            activation: activations::LINEAR,
+                            activation_kind: ActivationKind::Linear,
+                            aggregation: Aggregation::WeightedSum,
+                            time_constant: None,
+                        };
+
+                        known_edges.push(Edge::new(previous_input, wrapper_output_node.id(), 1.0));
+                        known_inputs.push(wrapper_input_node);
+                        known_outputs.push(wrapper_output_node);
+
+                        wrapper_input_id
+                    });
+            }
+
+            let inward_wrapping_connection = Edge::new(
+                recurrent_input,
+                recurrent_edge.end(),
+                recurrent_edge.weight(),
+            );
 
             known_edges.push(inward_wrapping_connection);
         }
@@ -381,28 +777,131 @@ pub mod net {
             .chain(recurrent.hidden().iter().map(|n| Node {
                 id: n.id(),
                 activation: n.activation(),
+                activation_kind: n.activation_kind(),
+                aggregation: n.aggregation(),
+                time_constant: None,
             }))
-            .chain(known_outputs.into_iter())
+            .chain(known_outputs)
             .collect::<Vec<_>>();
         let edges = known_edges;
 
         Net::new(inputs_count, outputs_count, nodes, edges)
     }
 
+    /// The built-in activations.
+    ///
+    /// Each constant points to a named function of this crate, so other crates see the same address for it
+    /// and [`crate::network::ActivationKind::from_function`] recognizes it. A `const` closure would be instantiated per crate.
     pub mod activations {
-        pub const LINEAR: fn(f64) -> f64 = |val| val;
+        pub const LINEAR: fn(f64) -> f64 = linear;
         // pub const SIGMOID: fn(f64) -> f64 = |val| 1.0 / (1.0 + (-1.0 * val).exp());
-        pub const SIGMOID: fn(f64) -> f64 = |val| 1.0 / (1.0 + (-4.9 * val).exp());
-        pub const TANH: fn(f64) -> f64 = |val| 2.0 * SIGMOID(2.0 * val) - 1.0;
+        pub const SIGMOID: fn(f64) -> f64 = sigmoid;
+        pub const TANH: fn(f64) -> f64 = tanh;
         // a = 1, b = 0, c = 1
-        pub const GAUSSIAN: fn(f64) -> f64 = |val| (val * val / -2.0).exp();
+        pub const GAUSSIAN: fn(f64) -> f64 = gaussian;
         // pub const STEP: fn(f64) -> f64 = |val| if val > 0.0 { 1.0 } else { 0.0 };
         // pub const SINE: fn(f64) -> f64 = |val| (val * std::f64::consts::PI).sin();
         // pub const COSINE: fn(f64) -> f64 = |val| (val * std::f64::consts::PI).cos();
-        pub const INVERSE: fn(f64) -> f64 = |val| -val;
+        pub const INVERSE: fn(f64) -> f64 = inverse;
         // pub const ABSOLUTE: fn(f64) -> f64 = |val| val.abs();
-        pub const RELU: fn(f64) -> f64 = |val| 0f64.max(val);
-        pub const SQUARED: fn(f64) -> f64 = |val| val * val;
+        pub const RELU: fn(f64) -> f64 = relu;
+        pub const SQUARED: fn(f64) -> f64 = squared;
+
+        // never inlined, small functions could otherwise be copied into other crates under a different address
+        #[inline(never)]
+        fn linear(val: f64) -> f64 {
+            val
+        }
+
+        #[inline(never)]
+        fn sigmoid(val: f64) -> f64 {
+            1.0 / (1.0 + (-4.9 * val).exp())
+        }
+
+        #[inline(never)]
+        fn tanh(val: f64) -> f64 {
+            2.0 * sigmoid(2.0 * val) - 1.0
+        }
+
+        #[inline(never)]
+        fn gaussian(val: f64) -> f64 {
+            (val * val / -2.0).exp()
+        }
+
+        #[inline(never)]
+        fn inverse(val: f64) -> f64 {
+            -val
+        }
+
+        #[inline(never)]
+        fn relu(val: f64) -> f64 {
+            0f64.max(val)
+        }
+
+        #[inline(never)]
+        fn squared(val: f64) -> f64 {
+            val * val
+        }
+
+        /// Code reported by [`to_code`] for any activation that is not one of the above.
+        pub const CUSTOM_CODE: u8 = u8::MAX;
+
+        /// Maps the built-in activations to a compact numeric code.
+        ///
+        /// Any other function is mapped to [`CUSTOM_CODE`].
+        pub fn to_code(activation: fn(f64) -> f64) -> u8 {
+            crate::network::ActivationKind::from_function(activation).code()
+        }
+
+        /// Looks up a built-in activation by its name, see [`crate::network::ActivationKind::name`].
+        pub fn by_name(name: &str) -> Option<fn(f64) -> f64> {
+            crate::network::ActivationKind::from_name(name).and_then(|kind| kind.function())
+        }
+
+        /// Returns the name of a built-in activation by comparing function pointers, any other function has none.
+        pub fn name_of(activation: fn(f64) -> f64) -> Option<&'static str> {
+            crate::network::ActivationKind::from_function(activation).name()
+        }
+
+        /// Derivatives of the built-in activations, taking the same pre-activation value as the activation itself.
+        pub mod derivatives {
+            use super::{SIGMOID, TANH};
+
+            pub fn d_linear(_val: f64) -> f64 {
+                1.0
+            }
+
+            pub fn d_sigmoid(val: f64) -> f64 {
+                let sigmoid = SIGMOID(val);
+                4.9 * sigmoid * (1.0 - sigmoid)
+            }
+
+            pub fn d_tanh(val: f64) -> f64 {
+                let tanh = TANH(val);
+                // TANH is built from SIGMOID and equals tanh(4.9 * val)
+                4.9 * (1.0 - tanh * tanh)
+            }
+
+            pub fn d_gaussian(val: f64) -> f64 {
+                -val * (val * val / -2.0).exp()
+            }
+
+            pub fn d_inverse(_val: f64) -> f64 {
+                -1.0
+            }
+
+            pub fn d_relu(val: f64) -> f64 {
+                if val > 0.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+
+            pub fn d_squared(val: f64) -> f64 {
+                2.0 * val
+            }
+        }
     }
 
     #[macro_export]
@@ -411,7 +910,7 @@ pub mod net {
             {
                 vec![
                     $(
-                        crate::network::net::Edge::new($start, $end, $weight),
+                        $crate::network::net::Edge::new($start, $end, $weight),
                     )*
                 ]
             }
@@ -422,25 +921,284 @@ pub mod net {
     macro_rules! nodes {
         ( $( $activation:literal ),* ) => {
             {
-            let mut nodes = Vec::new();
-
-            $(
-                nodes.push(
-                    crate::network::net::Node::new(nodes.len(), match $activation {
-                        'l' => crate::network::net::activations::LINEAR,
-                        's' => crate::network::net::activations::SIGMOID,
-                        't' => crate::network::net::activations::TANH,
-                        'g' => crate::network::net::activations::GAUSSIAN,
-                        'r' => crate::network::net::activations::RELU,
-                        'q' => crate::network::net::activations::SQUARED,
-                        'i' => crate::network::net::activations::INVERSE,
-                        _ => crate::network::net::activations::SIGMOID }
+            [$( $activation ),*]
+                .iter()
+                .enumerate()
+                .map(|(id, activation)| {
+                    $crate::network::net::Node::with_activation_kind(id, match activation {
+                        'l' => $crate::network::ActivationKind::Linear,
+                        's' => $crate::network::ActivationKind::Sigmoid,
+                        't' => $crate::network::ActivationKind::Tanh,
+                        'g' => $crate::network::ActivationKind::Gaussian,
+                        'r' => $crate::network::ActivationKind::Relu,
+                        'q' => $crate::network::ActivationKind::Squared,
+                        'i' => $crate::network::ActivationKind::Inverse,
+                        _ => $crate::network::ActivationKind::Sigmoid }
                     )
-                );
-            )*
-
-            nodes
+                })
+                .collect::<Vec<_>>()
             }
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{
+        net::{activations, unroll, Edge, Net, Node},
+        ActivationKind, EdgeLike, Evaluator, Fabricator, NetworkLike, NodeLike, StatefulEvaluator,
+        StatefulFabricator,
+    };
+    use crate::{edges, nodes, MatrixFeedforwardFabricator, MatrixRecurrentFabricator};
+
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct CustomNode(usize);
+
+    impl NodeLike for CustomNode {
+        fn id(&self) -> usize {
+            self.0
+        }
+        fn activation(&self) -> fn(f64) -> f64 {
+            activations::TANH
+        }
+    }
+
+    struct CustomEdge(usize, usize, f64, Option<u64>);
+
+    impl EdgeLike for CustomEdge {
+        fn start(&self) -> usize {
+            self.0
+        }
+        fn end(&self) -> usize {
+            self.1
+        }
+        fn weight(&self) -> f64 {
+            self.2
+        }
+        fn innovation(&self) -> Option<u64> {
+            self.3
+        }
+    }
+
+    struct CustomNet {
+        nodes: Vec<CustomNode>,
+        edges: Vec<CustomEdge>,
+    }
+
+    impl NetworkLike<CustomNode, CustomEdge> for CustomNet {
+        fn edges(&self) -> Vec<&CustomEdge> {
+            self.edges.iter().collect()
+        }
+        fn inputs(&self) -> Vec<&CustomNode> {
+            self.nodes.iter().take(2).collect()
+        }
+        fn hidden(&self) -> Vec<&CustomNode> {
+            self.nodes.iter().skip(2).take(1).collect()
+        }
+        fn outputs(&self) -> Vec<&CustomNode> {
+            self.nodes.iter().skip(3).collect()
+        }
+    }
+
+    #[test]
+    fn detects_cycles_but_ignores_recurrent_edges() {
+        let chain = Net::with_recurrent(
+            1,
+            1,
+            nodes!('l', 'l', 'l'),
+            edges!(0--1.0->1, 1--1.0->2),
+            edges!(2--1.0->1),
+        );
+        let cycle = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--1.0->1, 1--1.0->2, 2--1.0->1, 2--1.0->3),
+        );
+
+        assert!(chain.is_acyclic());
+        assert!(!cycle.is_acyclic());
+    }
+
+    #[test]
+    fn finds_incoming_and_outgoing_edges() {
+        let branching = Net::with_recurrent(
+            1,
+            2,
+            nodes!('l', 'l', 'l', 'l', 'l'),
+            edges!(0--1.0->1, 0--2.0->2, 1--3.0->3, 2--4.0->3, 1--5.0->4),
+            edges!(3--6.0->1),
+        );
+        let weights =
+            |edges: Vec<&Edge>| edges.iter().map(|edge| edge.weight()).collect::<Vec<_>>();
+
+        assert_eq!(weights(branching.outgoing_edges(0)), vec![1.0, 2.0]);
+        assert_eq!(weights(branching.outgoing_edges(1)), vec![3.0, 5.0]);
+        assert_eq!(weights(branching.incoming_edges(3)), vec![3.0, 4.0]);
+        // recurrent edges are not part of the neighbors
+        assert_eq!(weights(branching.incoming_edges(1)), vec![1.0]);
+        assert!(branching.outgoing_edges(4).is_empty());
+        assert!(branching.incoming_edges(0).is_empty());
+    }
+
+    #[test]
+    fn keeps_activation_kind_of_nodes() {
+        let node = Node::with_activation_kind(0, ActivationKind::Tanh);
+
+        assert_eq!(node.activation_kind(), ActivationKind::Tanh);
+        assert_eq!((node.activation())(0.5), activations::TANH(0.5));
+        assert_eq!(Node::copy_of(&node).activation_kind(), ActivationKind::Tanh);
+        assert_eq!(nodes!('r')[0].activation_kind(), ActivationKind::Relu);
+        assert_eq!(
+            Node::new(1, |val| val * 3.0).activation_kind(),
+            ActivationKind::Custom
+        );
+    }
+
+    #[test]
+    fn copies_network_like_into_net() {
+        let custom = CustomNet {
+            nodes: (0..4).map(CustomNode).collect(),
+            edges: vec![
+                CustomEdge(0, 2, 0.5, None),
+                CustomEdge(1, 2, -0.7, None),
+                CustomEdge(2, 3, 1.5, None),
+                CustomEdge(0, 3, 0.3, None),
+            ],
+        };
+        let copy = Net::from_network_like(&custom);
+
+        let expected = MatrixFeedforwardFabricator::fabricate(&custom).unwrap();
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&copy).unwrap();
+
+        for input in &[[0.0, 0.0], [0.7, -1.2], [-2.0, 3.5]] {
+            assert_eq!(
+                evaluator.evaluate(input.to_vec()),
+                expected.evaluate(input.to_vec())
+            );
+        }
+    }
+
+    #[test]
+    fn fabricates_innovation_ordered_edges_identically() {
+        let innovation_net = |edges: Vec<CustomEdge>| CustomNet {
+            nodes: (0..4).map(CustomNode).collect(),
+            edges,
+        };
+        let first = innovation_net(vec![
+            CustomEdge(2, 3, 1.5, Some(7)),
+            CustomEdge(0, 2, 0.5, Some(1)),
+            CustomEdge(0, 3, 0.3, None),
+            CustomEdge(1, 2, -0.7, Some(2)),
+        ]);
+        let second = innovation_net(vec![
+            CustomEdge(1, 2, -0.7, Some(2)),
+            CustomEdge(0, 3, 0.3, None),
+            CustomEdge(0, 2, 0.5, Some(1)),
+            CustomEdge(2, 3, 1.5, Some(7)),
+        ]);
+
+        let innovations = |net: &CustomNet| {
+            super::ordered_enabled_edges(net)
+                .iter()
+                .map(|edge| edge.innovation())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(innovations(&first), vec![Some(1), Some(2), Some(7), None]);
+
+        let first = MatrixFeedforwardFabricator::fabricate(&first).unwrap();
+        let again = MatrixFeedforwardFabricator::fabricate(&innovation_net(vec![
+            CustomEdge(2, 3, 1.5, Some(7)),
+            CustomEdge(0, 2, 0.5, Some(1)),
+            CustomEdge(0, 3, 0.3, None),
+            CustomEdge(1, 2, -0.7, Some(2)),
+        ]))
+        .unwrap();
+        let second = MatrixFeedforwardFabricator::fabricate(&second).unwrap();
+
+        for other in &[again, second] {
+            assert_eq!(first.stages, other.stages);
+            assert_eq!(first.stage_nodes, other.stage_nodes);
+            assert_eq!(first.activation_kinds, other.activation_kinds);
+        }
+    }
+
+    #[test]
+    fn unrolls_nets_with_ids_near_usize_max() {
+        let high = usize::MAX - 2;
+        let high_net = Net::with_recurrent(
+            1,
+            1,
+            vec![
+                Node::new(high, activations::LINEAR),
+                Node::new(high + 1, activations::TANH),
+                Node::new(high + 2, activations::LINEAR),
+            ],
+            vec![
+                Edge::new(high, high + 1, 0.5),
+                Edge::new(high + 1, high + 2, 1.5),
+            ],
+            vec![Edge::new(high + 2, high + 1, 0.3)],
+        );
+        let low_net = Net::with_recurrent(
+            1,
+            1,
+            nodes!('l', 't', 'l'),
+            edges!(0--0.5->1, 1--1.5->2),
+            edges!(2--0.3->1),
+        );
+
+        let unrolled = unroll(&high_net);
+        let ids: HashSet<usize> = unrolled.nodes().iter().map(|node| node.id()).collect();
+        assert_eq!(ids.len(), unrolled.nodes().len());
+
+        let mut high_evaluator = MatrixRecurrentFabricator::fabricate(&high_net).unwrap();
+        let mut low_evaluator = MatrixRecurrentFabricator::fabricate(&low_net).unwrap();
+        for input in &[1.0, 0.5, -2.0] {
+            assert_eq!(
+                high_evaluator.evaluate(vec![*input]),
+                low_evaluator.evaluate(vec![*input])
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_activation_names() {
+        for name in &[
+            "linear", "sigmoid", "tanh", "gaussian", "inverse", "relu", "squared",
+        ] {
+            assert_eq!(
+                activations::name_of(activations::by_name(name).unwrap()),
+                Some(*name)
+            );
+        }
+
+        assert!(activations::by_name("softplus").is_none());
+        assert!(activations::name_of(|val| val * 3.0).is_none());
+    }
+
+    #[test]
+    fn reports_distinct_activation_codes() {
+        let net = Net::new(1, 2, nodes!('s', 'l', 's'), edges!(0--1.0->1, 0--1.0->2));
+
+        assert_eq!(
+            net.activation_codes(),
+            vec![
+                activations::to_code(activations::LINEAR),
+                activations::to_code(activations::SIGMOID)
+            ]
+        );
+        assert_eq!(net.activation_codes(), vec![0, 1]);
+    }
+
+    #[test]
+    fn maps_custom_activation_to_sentinel_code() {
+        fn custom(val: f64) -> f64 {
+            val * 3.0
+        }
+
+        assert_eq!(activations::to_code(custom), activations::CUSTOM_CODE);
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{
+    net::{Edge, Net, Node},
+    Aggregation, EdgeLike, NetworkLike, NodeLike,
+};
+
+/// Removes hidden nodes that are not on any path from an input to an output, together with their edges.
+///
+/// Inputs and outputs are always preserved, even when they are not connected.
+/// The returned [`Net`] computes the same function as the original one.
+pub fn prune_dead_nodes<N: NodeLike, E: EdgeLike>(net: &impl NetworkLike<N, E>) -> Net {
+    let edges = net
+        .edges()
+        .into_iter()
+        .filter(|edge| edge.enabled())
+        .collect::<Vec<_>>();
+
+    let reachable_from_inputs = reachable(
+        net.inputs().iter().map(|node| node.id()).collect(),
+        &edges,
+        |edge| (edge.start(), edge.end()),
+    );
+    let reaching_outputs = reachable(
+        net.outputs().iter().map(|node| node.id()).collect(),
+        &edges,
+        |edge| (edge.end(), edge.start()),
+    );
+
+    let mut alive: HashSet<usize> = net
+        .inputs()
+        .iter()
+        .chain(net.outputs().iter())
+        .map(|node| node.id())
+        .collect();
+
+    let hidden = net
+        .hidden()
+        .into_iter()
+        .filter(|node| {
+            reachable_from_inputs.contains(&node.id()) && reaching_outputs.contains(&node.id())
+        })
+        .collect::<Vec<_>>();
+
+    alive.extend(hidden.iter().map(|node| node.id()));
+
+    let nodes = net
+        .inputs()
+        .into_iter()
+        .chain(hidden)
+        .chain(net.outputs())
+        .map(Node::copy_of)
+        .collect();
+
+    let edges = edges
+        .iter()
+        .filter(|edge| alive.contains(&edge.start()) && alive.contains(&edge.end()))
+        .map(|&edge| Edge::copy_of(edge))
+        .collect();
+
+    Net::new(net.inputs().len(), net.outputs().len(), nodes, edges)
+}
+
+/// Tells what [`prune_weak_edges`] removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Number of enabled edges dropped for their weight.
+    pub removed: usize,
+    /// Outputs that were reachable from the inputs before pruning but are not anymore.
+    ///
+    /// Fabricators reject such nets as the outputs can not be computed, so a non-empty list asks for a lower threshold.
+    pub disconnected_outputs: Vec<usize>,
+}
+
+/// Drops enabled edges with an absolute weight below `threshold`, disabled edges are dropped as well.
+///
+/// Only edges into nodes with [`Aggregation::WeightedSum`] are candidates. For any other aggregation even a zero input counts,
+/// it shifts a mean, zeroes a product and can win a maximum or minimum, so those edges are always kept.
+/// Pruned edges with a weight of exactly `0.0` never change the output, others change it by an amount that depends on the threshold.
+/// All nodes are kept, see [`prune_dead_nodes`] to drop hidden nodes left without connections afterwards.
+pub fn prune_weak_edges<N: NodeLike, E: EdgeLike>(
+    net: &impl NetworkLike<N, E>,
+    threshold: f64,
+) -> (Net, PruneReport) {
+    let edges = net
+        .edges()
+        .into_iter()
+        .filter(|edge| edge.enabled())
+        .collect::<Vec<_>>();
+    let aggregations: HashMap<usize, Aggregation> = net
+        .nodes()
+        .iter()
+        .map(|node| (node.id(), node.aggregation()))
+        .collect();
+    let (strong, weak): (Vec<&E>, Vec<&E>) = edges.iter().partition(|edge| {
+        edge.weight().abs() >= threshold
+            || aggregations.get(&edge.end()) != Some(&Aggregation::WeightedSum)
+    });
+
+    let inputs: Vec<usize> = net.inputs().iter().map(|node| node.id()).collect();
+    let forward = |edge: &E| (edge.start(), edge.end());
+    let reachable_before = reachable(inputs.clone(), &edges, forward);
+    let reachable_after = reachable(inputs, &strong, forward);
+
+    let disconnected_outputs = net
+        .outputs()
+        .iter()
+        .map(|node| node.id())
+        .filter(|id| reachable_before.contains(id) && !reachable_after.contains(id))
+        .collect();
+
+    let pruned = Net::new(
+        net.inputs().len(),
+        net.outputs().len(),
+        net.nodes().into_iter().map(Node::copy_of).collect(),
+        strong.into_iter().map(Edge::copy_of).collect(),
+    );
+
+    (
+        pruned,
+        PruneReport {
+            removed: weak.len(),
+            disconnected_outputs,
+        },
+    )
+}
+
+// collects all ids reachable from the given ones by following edges in the direction given by `direction`
+fn reachable<E: EdgeLike>(
+    mut pending: Vec<usize>,
+    edges: &[&E],
+    direction: impl Fn(&E) -> (usize, usize),
+) -> HashSet<usize> {
+    let mut visited: HashSet<usize> = pending.iter().cloned().collect();
+
+    while let Some(id) = pending.pop() {
+        for &edge in edges {
+            let (from, to) = direction(edge);
+            if from == id && visited.insert(to) {
+                pending.push(to);
+            }
+        }
+    }
+
+    visited
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{prune_dead_nodes, prune_weak_edges};
+    use crate::{
+        edges,
+        network::{net::Net, Aggregation, EdgeLike, Evaluator, Fabricator, NetworkLike, NodeLike},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn drops_dead_end_hidden_node() {
+        let net = Net::new(
+            1,
+            1,
+            nodes!('l', 't', 's', 'l'),
+            edges!(
+                0--0.5->1,
+                0--0.5->2,
+                2--0.5->3
+            ),
+        );
+
+        let pruned = prune_dead_nodes(&net);
+
+        assert_eq!(pruned.hidden().len(), 1);
+        assert_eq!(pruned.hidden()[0].id(), 2);
+        assert_eq!(pruned.edges().len(), 2);
+
+        let original = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+        let pruned = MatrixFeedforwardFabricator::fabricate(&pruned).unwrap();
+
+        assert_eq!(
+            original.evaluate(dmatrix![5.0]),
+            pruned.evaluate(dmatrix![5.0])
+        );
+    }
+
+    #[test]
+    fn prunes_zero_weight_edge_without_changing_output() {
+        let net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l'),
+            edges!(
+                0--0.5->2,
+                1--0.0->2
+            ),
+        );
+
+        let (pruned, report) = prune_weak_edges(&net, 1e-6);
+
+        assert_eq!(report.removed, 1);
+        assert!(report.disconnected_outputs.is_empty());
+        assert_eq!(pruned.edges().len(), 1);
+
+        let original = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+        let pruned = MatrixFeedforwardFabricator::fabricate(&pruned).unwrap();
+
+        assert_eq!(
+            original.evaluate(dmatrix![5.0, 5.0]),
+            pruned.evaluate(dmatrix![5.0, 5.0])
+        );
+    }
+
+    #[test]
+    fn keeps_weak_edges_into_products() {
+        let mut nodes = nodes!('l', 'l', 'l');
+        nodes[2].set_aggregation(Aggregation::Product);
+        let net = Net::new(
+            2,
+            1,
+            nodes,
+            edges!(
+                0--0.5->2,
+                1--0.0->2
+            ),
+        );
+
+        let (pruned, report) = prune_weak_edges(&net, 1e-6);
+
+        assert_eq!(report.removed, 0);
+        assert_eq!(pruned.edges().len(), 2);
+
+        let pruned = MatrixFeedforwardFabricator::fabricate(&pruned).unwrap();
+        // the zero weighted input still zeroes the product
+        assert_eq!(pruned.evaluate(dmatrix![5.0, 5.0]), dmatrix![0.0]);
+    }
+
+    #[test]
+    fn reports_disconnected_outputs() {
+        let net = Net::new(1, 2, nodes!('l', 'l', 'l'), edges!(0--0.5->1, 0--0.01->2));
+
+        let (_, report) = prune_weak_edges(&net, 0.1);
+
+        assert_eq!(report.removed, 1);
+        assert_eq!(report.disconnected_outputs, vec![2]);
+    }
+
+    #[test]
+    fn keeps_edge_attributes_of_alive_edges() {
+        let mut edges = edges!(0--0.5->1, 1--0.5->2);
+        edges[0].set_delay(2);
+        edges[1].set_weight_group(Some(3));
+        let net = Net::new(1, 1, nodes!('l', 'l', 'l'), edges);
+
+        let pruned = prune_dead_nodes(&net);
+
+        assert_eq!(pruned.edges()[0].delay(), 2);
+        assert_eq!(pruned.edges()[1].weight_group(), Some(3));
+    }
+
+    #[test]
+    fn preserves_unconnected_inputs_and_outputs() {
+        let net = Net::new(2, 2, nodes!('l', 'l', 'l', 'l', 'l'), edges!(0--1.0->3));
+
+        let pruned = prune_dead_nodes(&net);
+
+        assert_eq!(pruned.inputs().len(), 2);
+        assert_eq!(pruned.hidden().len(), 0);
+        assert_eq!(pruned.outputs().len(), 2);
+        assert_eq!(pruned.edges().len(), 1);
+    }
+}
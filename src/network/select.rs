@@ -0,0 +1,47 @@
+use nalgebra::DMatrix;
+
+/// Returns the position of the largest value in the first row of `output`, like the selected action of a policy net.
+///
+/// Ties are won by the lowest position and `NaN` is never selected unless every value is `NaN`.
+/// Panics if `output` is empty.
+pub fn argmax(output: &DMatrix<f64>) -> usize {
+    top_k(output, 1)
+        .first()
+        .map(|&(position, _)| position)
+        .expect("output is empty")
+}
+
+/// Returns the `k` largest values in the first row of `output` with their positions, largest first.
+///
+/// Ties are ordered by position, `NaN` values come last. Fewer than `k` values are returned if the row is shorter.
+pub fn top_k(output: &DMatrix<f64>, k: usize) -> Vec<(usize, f64)> {
+    if output.nrows() == 0 {
+        return Vec::new();
+    }
+
+    let mut ranked: Vec<(usize, f64)> = output.row(0).iter().cloned().enumerate().collect();
+    // stable sort keeps positions of equal values in order
+    ranked.sort_by(|a, b| match (a.1.is_nan(), b.1.is_nan()) {
+        (false, false) => b.1.partial_cmp(&a.1).unwrap(),
+        (nan_a, nan_b) => nan_a.cmp(&nan_b),
+    });
+    ranked.truncate(k);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{argmax, top_k};
+
+    #[test]
+    fn selects_largest_with_lowest_position_on_ties() {
+        let output = dmatrix![0.1, 0.7, f64::NAN, 0.7, 0.2];
+
+        assert_eq!(argmax(&output), 1);
+        assert_eq!(top_k(&output, 3), vec![(1, 0.7), (3, 0.7), (4, 0.2)]);
+        assert_eq!(top_k(&output, 10).len(), 5);
+        assert_eq!(argmax(&dmatrix![f64::NAN, f64::NAN]), 0);
+    }
+}
@@ -0,0 +1,64 @@
+use super::{NetworkIO, StatefulEvaluator};
+
+/// Iterator returned by [`StatefulEvaluator::evaluate_sequence`].
+///
+/// Every call to `next` evaluates the next input, so dropping the iterator early leaves the remaining inputs unevaluated.
+pub struct SequenceEvaluator<'a, E, I> {
+    evaluator: &'a mut E,
+    inputs: I,
+}
+
+impl<'a, E, I> SequenceEvaluator<'a, E, I> {
+    pub fn new(evaluator: &'a mut E, inputs: I) -> Self {
+        Self { evaluator, inputs }
+    }
+}
+
+impl<'a, E, I, T> Iterator for SequenceEvaluator<'a, E, I>
+where
+    E: StatefulEvaluator,
+    I: Iterator<Item = T>,
+    T: NetworkIO,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inputs
+            .next()
+            .map(|input| self.evaluator.evaluate(input))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inputs.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        edges,
+        network::{net::Net, StatefulEvaluator, StatefulFabricator},
+        nodes, MatrixRecurrentFabricator,
+    };
+
+    #[test]
+    fn matches_manual_evaluation() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+        some_net.set_recurrent_edges(edges!(1--0.5->1));
+
+        let inputs = vec![vec![1.0], vec![2.0], vec![3.0]];
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        let manual: Vec<Vec<f64>> = inputs
+            .iter()
+            .cloned()
+            .map(|input| evaluator.evaluate(input))
+            .collect();
+
+        evaluator.reset_internal_state();
+        let sequence: Vec<Vec<f64>> = evaluator.evaluate_sequence(inputs).collect();
+
+        assert_eq!(sequence, manual);
+        assert_eq!(sequence, vec![vec![1.0], vec![2.5], vec![4.25]]);
+    }
+}
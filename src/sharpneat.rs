@@ -0,0 +1,234 @@
+//! Reads genomes in the XML format of SharpNEAT, enabled by the `sharpneat` feature.
+
+use std::{collections::HashMap, io::Read};
+
+use crate::network::{
+    net::{activations, Edge, Net, Node},
+    NodeLike,
+};
+
+/// Parses the first `<Network>` of a SharpNEAT XML genome file into a [`Net`].
+///
+/// Nodes of type `bias` and `in` become inputs, so the bias node is the first input and expects `1.0` on every evaluation.
+/// Activations of hidden and output nodes are looked up by the name given in `<ActivationFunctions>`. Nodes without a `fnId` get
+/// [`activations::SIGMOID`], which matches the steepened logistic function SharpNEAT uses by default.
+/// Names without an exact built-in counterpart, e.g. `LeakyReLU` or the plain `LogisticFunction`, fail with `"unsupported activation function"`,
+/// as do references to functions that are not declared.
+///
+/// SharpNEAT does not mark recurrent connections, so every connection that closes a cycle in a depth first search from the inputs
+/// is set as a recurrent edge of the returned net, see [`crate::network::Recurrent`].
+pub fn parse(mut reader: impl Read) -> Result<Net, &'static str> {
+    let mut text = String::new();
+    reader
+        .read_to_string(&mut text)
+        .map_err(|_| "genome is not readable as text")?;
+    let document = roxmltree::Document::parse(&text).map_err(|_| "genome is not valid XML")?;
+
+    let function_names: HashMap<&str, &str> = document
+        .descendants()
+        .filter(|element| element.has_tag_name("Fn"))
+        .filter_map(|function| Some((function.attribute("id")?, function.attribute("name")?)))
+        .collect();
+
+    let network = document
+        .descendants()
+        .find(|element| element.has_tag_name("Network"))
+        .ok_or("genome contains no network")?;
+
+    let mut inputs = Vec::new();
+    let mut hidden = Vec::new();
+    let mut outputs = Vec::new();
+    for node in network
+        .descendants()
+        .filter(|element| element.has_tag_name("Node"))
+    {
+        let id = node
+            .attribute("id")
+            .and_then(|id| id.parse::<usize>().ok())
+            .ok_or("node without valid id")?;
+        let activation = || match node.attribute("fnId") {
+            Some(function) => function_names
+                .get(function)
+                .ok_or("unsupported activation function")
+                .and_then(|name| activation_by_name(name)),
+            None => Ok(activations::SIGMOID),
+        };
+
+        match node.attribute("type") {
+            Some("bias") | Some("in") => inputs.push(Node::new(id, activations::LINEAR)),
+            Some("hid") => hidden.push(Node::new(id, activation()?)),
+            Some("out") => outputs.push(Node::new(id, activation()?)),
+            _ => return Err("node with unknown type"),
+        }
+    }
+
+    let mut connections = Vec::new();
+    for connection in network
+        .descendants()
+        .filter(|element| element.has_tag_name("Con"))
+    {
+        let attribute = |name| {
+            connection
+                .attribute(name)
+                .ok_or("connection attribute missing")
+        };
+        let start = attribute("src")?
+            .parse::<usize>()
+            .map_err(|_| "connection source is not an id")?;
+        let end = attribute("tgt")?
+            .parse::<usize>()
+            .map_err(|_| "connection target is not an id")?;
+        let weight = attribute("wght")?
+            .parse::<f64>()
+            .map_err(|_| "connection weight is not a number")?;
+        connections.push((start, end, weight));
+    }
+
+    let mut roots: Vec<usize> = inputs.iter().map(|node| node.id()).collect();
+    roots.extend(hidden.iter().chain(outputs.iter()).map(|node| node.id()));
+    let closing = cycle_closing_connections(&roots, &connections);
+
+    let (recurrent, feedforward): (Vec<_>, Vec<_>) = connections
+        .into_iter()
+        .enumerate()
+        .partition(|(index, _)| closing[*index]);
+    let into_edges = |connections: Vec<(usize, (usize, usize, f64))>| {
+        connections
+            .into_iter()
+            .map(|(_, (start, end, weight))| Edge::new(start, end, weight))
+            .collect()
+    };
+
+    let (input_count, output_count) = (inputs.len(), outputs.len());
+    let nodes = inputs.into_iter().chain(hidden).chain(outputs).collect();
+
+    Ok(Net::with_recurrent(
+        input_count,
+        output_count,
+        nodes,
+        into_edges(feedforward),
+        into_edges(recurrent),
+    ))
+}
+
+// only names computing exactly what the built-in activation does are accepted
+fn activation_by_name(name: &str) -> Result<fn(f64) -> f64, &'static str> {
+    match name {
+        "Linear" => Ok(activations::LINEAR),
+        "SteepenedSigmoid" => Ok(activations::SIGMOID),
+        "TanH" => Ok(activations::TANH),
+        "ReLU" | "RectifiedLinear" => Ok(activations::RELU),
+        "Gaussian" => Ok(activations::GAUSSIAN),
+        _ => Err("unsupported activation function"),
+    }
+}
+
+// marks every connection that leads back to a node on the current path of a depth first search started at the roots in order
+fn cycle_closing_connections(roots: &[usize], connections: &[(usize, usize, f64)]) -> Vec<bool> {
+    let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (index, &(start, _, _)) in connections.iter().enumerate() {
+        outgoing.entry(start).or_default().push(index);
+    }
+
+    let mut closing = vec![false; connections.len()];
+    // nodes on the current path are `false`, fully explored ones `true`
+    let mut visited: HashMap<usize, bool> = HashMap::new();
+
+    for &root in roots {
+        if visited.contains_key(&root) {
+            continue;
+        }
+        visited.insert(root, false);
+        let mut path = vec![(root, 0)];
+
+        while let Some((node, next)) = path.last_mut() {
+            let node = *node;
+            match outgoing.get(&node).and_then(|indices| indices.get(*next)) {
+                Some(&index) => {
+                    *next += 1;
+                    let end = connections[index].1;
+                    match visited.get(&end) {
+                        Some(false) => closing[index] = true,
+                        Some(true) => {}
+                        None => {
+                            visited.insert(end, false);
+                            path.push((end, 0));
+                        }
+                    }
+                }
+                None => {
+                    visited.insert(node, true);
+                    path.pop();
+                }
+            }
+        }
+    }
+
+    closing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use crate::{
+        network::{NetworkLike, Recurrent, StatefulEvaluator, StatefulFabricator},
+        MatrixRecurrentFabricator,
+    };
+
+    const GENOME: &str = r#"<Root>
+  <ActivationFunctions>
+    <Fn id="0" name="Linear" prob="1" />
+  </ActivationFunctions>
+  <Networks>
+    <Network id="7" birthGen="3" fitness="0.5">
+      <Nodes>
+        <Node type="bias" id="0" fnId="0" />
+        <Node type="in" id="1" fnId="0" />
+        <Node type="out" id="2" fnId="0" />
+        <Node type="hid" id="3" fnId="0" />
+      </Nodes>
+      <Connections>
+        <Con id="4" src="0" tgt="3" wght="0.5" />
+        <Con id="5" src="1" tgt="3" wght="2" />
+        <Con id="6" src="3" tgt="2" wght="1" />
+        <Con id="8" src="2" tgt="3" wght="0.25" />
+      </Connections>
+    </Network>
+  </Networks>
+</Root>"#;
+
+    #[test]
+    fn parses_and_evaluates_genome() {
+        let net = parse(GENOME.as_bytes()).unwrap();
+
+        assert_eq!(net.inputs().len(), 2);
+        assert_eq!(net.outputs().len(), 1);
+        assert_eq!(net.edges().len(), 3);
+        // the connection from the output back to the hidden node closes a cycle
+        assert_eq!(net.recurrent_edges().len(), 1);
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&net).unwrap();
+        assert_eq!(evaluator.evaluate(vec![1.0, 1.0]), vec![2.5]);
+        assert_eq!(evaluator.evaluate(vec![1.0, 1.0]), vec![2.5 + 0.25 * 2.5]);
+    }
+
+    #[test]
+    fn rejects_unsupported_activation_functions() {
+        for name in ["LeakyReLU", "LogisticFunction", "RbfGaussian"].iter() {
+            let genome = GENOME.replace(r#"name="Linear""#, &format!(r#"name="{}""#, name));
+
+            assert_eq!(
+                parse(genome.as_bytes()).err(),
+                Some("unsupported activation function")
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_xml() {
+        assert_eq!(
+            parse("<Root>".as_bytes()).err(),
+            Some("genome is not valid XML")
+        );
+    }
+}
@@ -1,19 +1,84 @@
 use nalgebra::DMatrix;
 use nalgebra_sparse::{CscMatrix, SparseEntry, SparseEntryMut};
 
-use crate::network::{Evaluator, NetworkIO};
+use crate::{
+    matrix::feedforward::equivalence::{approx_eq_values, same_transformations},
+    network::{ActivationKind, Evaluator, NetworkIO},
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SparseMatrixFeedforwardEvaluator {
     pub stages: Vec<CscMatrix<f64>>,
     pub transformations: Vec<crate::Transformations>,
+    pub activation_kinds: Vec<Vec<ActivationKind>>,
+}
+
+impl SparseMatrixFeedforwardEvaluator {
+    /// Returns the number of stored entries over all stages, the weights of edges plus one per carried value.
+    pub fn nnz(&self) -> usize {
+        self.stages.iter().map(CscMatrix::nnz).sum()
+    }
+
+    /// Returns the number of stored entries of each stage.
+    pub fn stage_nnz(&self) -> Vec<usize> {
+        self.stages.iter().map(CscMatrix::nnz).collect()
+    }
+
+    /// Returns the share of stored entries among all entries of all stages.
+    pub fn density(&self) -> f64 {
+        let entries: usize = self
+            .stages
+            .iter()
+            .map(|stage| stage.nrows() * stage.ncols())
+            .sum();
+        if entries == 0 {
+            return 0.0;
+        }
+        self.nnz() as f64 / entries as f64
+    }
+
+    /// Tells if both evaluators store entries at the same positions with values differing by at most `eps` and apply the same activations.
+    pub fn approx_eq(&self, other: &Self, eps: f64) -> bool {
+        self.stages.len() == other.stages.len()
+            && self.stages.iter().zip(&other.stages).all(|(a, b)| {
+                a.nrows() == b.nrows()
+                    && a.ncols() == b.ncols()
+                    && a.col_offsets() == b.col_offsets()
+                    && a.row_indices() == b.row_indices()
+                    && approx_eq_values(a.values(), b.values(), eps)
+            })
+            && same_transformations(&self.transformations, &other.transformations)
+            && self.activation_kinds == other.activation_kinds
+    }
+}
+
+/// Exact equality, see [`SparseMatrixFeedforwardEvaluator::approx_eq`] to tolerate rounding differences.
+impl PartialEq for SparseMatrixFeedforwardEvaluator {
+    fn eq(&self, other: &Self) -> bool {
+        self.approx_eq(other, 0.0)
+    }
 }
 
 impl Evaluator for SparseMatrixFeedforwardEvaluator {
     fn evaluate<T: NetworkIO>(&self, state: T) -> T {
-        let state = NetworkIO::input(state);
+        NetworkIO::output(self.evaluate_matrix(&NetworkIO::input(state)))
+    }
+}
+
+impl SparseMatrixFeedforwardEvaluator {
+    // evaluates without taking ownership of the input, which only gets converted to a sparse matrix
+    pub(crate) fn evaluate_matrix(&self, state: &DMatrix<f64>) -> DMatrix<f64> {
+        if let Some(first_stage) = self.stages.first() {
+            assert_eq!(
+                state.ncols(),
+                first_stage.nrows(),
+                "expected {} inputs, got {}",
+                first_stage.nrows(),
+                state.ncols()
+            );
+        }
         let mut len = 0;
-        let mut state: CscMatrix<f64> = (&state).into();
+        let mut state: CscMatrix<f64> = state.into();
         // performs evaluation by sequentially matrix multiplying and transforming the state with every stage
         for (stage_matrix, transformations) in self.stages.iter().zip(&self.transformations) {
             len = transformations.len();
@@ -24,7 +89,7 @@ impl Evaluator for SparseMatrixFeedforwardEvaluator {
                 }
             }
         }
-        NetworkIO::output(DMatrix::from_iterator(
+        DMatrix::from_iterator(
             1,
             len,
             (0..len).map(|index| {
@@ -34,6 +99,35 @@ impl Evaluator for SparseMatrixFeedforwardEvaluator {
                     0.0
                 }
             }),
-        ))
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        edges,
+        network::{net::Net, Fabricator},
+        nodes, SparseMatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn counts_edges_and_carries() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 't', 's'),
+            edges!(
+                0--0.5->2,
+                2--0.5->3,
+                1--0.5->3
+            ),
+        );
+        let evaluator = SparseMatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        // input 1 is carried through the first stage
+        assert_eq!(evaluator.stage_nnz(), vec![2, 2]);
+        assert_eq!(evaluator.nnz(), 3 + 1);
+        assert_eq!(evaluator.density(), 4.0 / 6.0);
     }
 }
@@ -1,33 +1,59 @@
-use crate::network::{EdgeLike, Fabricator, NetworkLike, NodeLike};
+use crate::network::{
+    ordered_enabled_edges, ActivationKind, Aggregation, EdgeLike, Fabricator, NetworkLike, NodeLike,
+};
 use nalgebra_sparse::{CooMatrix, CscMatrix};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::evaluator::SparseMatrixFeedforwardEvaluator;
+
+/// Column indices, row indices, values, row count and column count of a stage in triplet form.
+type SparseStage = (Vec<usize>, Vec<usize>, Vec<f64>, usize, usize);
 
 pub struct SparseMatrixFeedforwardFabricator;
 
-impl SparseMatrixFeedforwardFabricator {
-    fn get_sparse(
-        (col_inds, row_inds, data, rows): (Vec<usize>, Vec<usize>, Vec<f64>, usize),
-    ) -> CscMatrix<f64> {
-        let colums = col_inds.iter().max().unwrap() + 1;
+/// Configures the evaluator built by [`SparseMatrixFeedforwardFabricator::fabricate_with`].
+///
+/// [`Fabricator::fabricate`] uses the default options.
+#[derive(Debug, Clone, Default)]
+pub struct SparseMatrixFeedforwardOptions {
+    /// Skip checking edge weights for `NaN` and infinity, see [`crate::MatrixFeedforwardOptions::allow_non_finite_weights`].
+    pub allow_non_finite_weights: bool,
+}
 
+impl SparseMatrixFeedforwardFabricator {
+    // the shape is tracked during fabrication as it can not be derived from the indices,
+    // trailing columns or rows may have no entries and the triplets may be empty altogether
+    fn get_sparse((col_inds, row_inds, data, rows, columns): SparseStage) -> CscMatrix<f64> {
         CscMatrix::from(
-            &CooMatrix::try_from_triplets(rows, colums, row_inds, col_inds, data).unwrap(),
+            &CooMatrix::try_from_triplets(rows, columns, row_inds, col_inds, data)
+                .expect("triplet indices within tracked shape"),
         )
     }
-}
 
-impl<N, E> Fabricator<N, E> for SparseMatrixFeedforwardFabricator
-where
-    N: NodeLike,
-    E: EdgeLike,
-{
-    type Output = super::evaluator::SparseMatrixFeedforwardEvaluator;
+    /// Fabricates like [`Fabricator::fabricate`] but configured by the given options.
+    pub fn fabricate_with<N: NodeLike, E: EdgeLike>(
+        net: &impl NetworkLike<N, E>,
+        options: &SparseMatrixFeedforwardOptions,
+    ) -> Result<SparseMatrixFeedforwardEvaluator, &'static str> {
+        if net.nodes().iter().any(|node| {
+            !matches!(
+                node.aggregation(),
+                Aggregation::WeightedSum | Aggregation::Sum
+            )
+        }) {
+            return Err(
+                "aggregations other than sums are not supported by this fabricator, net invalid",
+            );
+        }
 
-    fn fabricate(net: &impl NetworkLike<N, E>) -> Result<Self::Output, &'static str> {
         // build dependency graph by collecting incoming edges per node
-        let mut dependency_graph: HashMap<usize, Vec<&E>> = HashMap::new();
+        // ordered by node id so fabrication is reproducible
+        let mut dependency_graph: BTreeMap<usize, Vec<&E>> = BTreeMap::new();
 
-        for edge in net.edges() {
+        for edge in ordered_enabled_edges(net) {
+            if !options.allow_non_finite_weights && !edge.weight().is_finite() {
+                return Err("non-finite edge weight present, net invalid");
+            }
             dependency_graph
                 .entry(edge.end())
                 .and_modify(|dependencies| dependencies.push(edge))
@@ -41,12 +67,21 @@ where
         // keep track of dependencies present
         let mut dependency_count = dependency_graph.len();
 
+        // look up nodes by id once instead of scanning all nodes for every computed node
+        let nodes_by_id: HashMap<usize, &N> = net
+            .nodes()
+            .into_iter()
+            .map(|node| (node.id(), node))
+            .collect();
+
         // println!("initial dependency_graph {:#?}", dependency_graph);
 
         // contains list of matrices (stages) that form the computable net
-        let mut compute_stages: Vec<(Vec<usize>, Vec<usize>, Vec<f64>, usize)> = Vec::new();
+        let mut compute_stages: Vec<SparseStage> = Vec::new();
         // contains activation functions corresponding to each stage
         let mut stage_transformations: Vec<crate::Transformations> = Vec::new();
+        // contains the kinds of the activation functions corresponding to each stage
+        let mut stage_activation_kinds: Vec<Vec<ActivationKind>> = Vec::new();
         // set available nodes a.k.a net input
         let mut available_nodes = net.inputs();
         // sort via Ord implementation of provided nodes to guarantee each input will be processed by the same node every time
@@ -62,6 +97,14 @@ where
         wanted_nodes.sort_unstable();
         // reduce nodes to ids
         let wanted_nodes: Vec<usize> = wanted_nodes.iter().map(|n| n.id()).collect();
+        // an output listed twice would claim two columns in the reordering of the last stage
+        let mut distinct_wanted_nodes = HashSet::new();
+        if !wanted_nodes
+            .iter()
+            .all(|id| distinct_wanted_nodes.insert(*id))
+        {
+            return Err("duplicate output ids present, net invalid");
+        }
 
         // println!("wanted_nodes {:?}", wanted_nodes);
 
@@ -69,6 +112,8 @@ where
         while !dependency_graph.is_empty() {
             // setup new transformations
             let mut transformations: crate::Transformations = Vec::new();
+            // setup new activation kinds
+            let mut activation_kinds: Vec<ActivationKind> = Vec::new();
             // list of nodes becoming available by compute stage
             let mut next_available_nodes: Vec<usize> = Vec::new();
 
@@ -77,6 +122,13 @@ where
             let mut stage_row_indices = Vec::new();
             let mut stage_data = Vec::new();
 
+            // position of every available node in the state entering this stage
+            let available_indices: HashMap<usize, usize> = available_nodes
+                .iter()
+                .enumerate()
+                .map(|(index, &id)| (id, index))
+                .collect();
+
             for (&dependent_node, dependencies) in dependency_graph.iter() {
                 let mut node_column_indices = Vec::new();
                 let mut node_row_indices = Vec::new();
@@ -85,33 +137,25 @@ where
                 let mut computable = true;
                 // check every dependency
                 for &dependency in dependencies {
-                    let mut found = false;
-                    for (row_index, &id) in available_nodes.iter().enumerate() {
-                        // index here is row index
-                        if dependency.start() == id {
+                    // index here is row index
+                    match available_indices.get(&dependency.start()) {
+                        Some(&row_index) => {
                             node_column_indices.push(column_index);
                             node_row_indices.push(row_index);
                             node_data.push(dependency.weight());
-                            found = true;
                         }
-                    }
-                    // if any dependency is not found the node is not computable yet
-                    if !found {
-                        computable = false;
+                        // if any dependency is not found the node is not computable yet
+                        None => computable = false,
                     }
                 }
                 if computable {
                     stage_column_indices = [stage_column_indices, node_column_indices].concat();
                     stage_row_indices = [stage_row_indices, node_row_indices].concat();
                     stage_data = [stage_data, node_data].concat();
+                    let node = nodes_by_id[&dependent_node];
                     // add activation function to stage transformations
-                    transformations.push(
-                        net.nodes()
-                            .iter()
-                            .find(|&node| node.id() == dependent_node)
-                            .unwrap()
-                            .activation(),
-                    );
+                    transformations.push(node.activation());
+                    activation_kinds.push(node.activation_kind());
                     column_index += 1;
                     // mark node as available in next iteration
                     next_available_nodes.push(dependent_node);
@@ -126,6 +170,7 @@ where
                             column_index += 1;
                             carry_data.push(1.0);
                             transformations.push(|val| val);
+                            activation_kinds.push(ActivationKind::Linear);
                             next_available_nodes.push(available_nodes[row_index]);
                         }
                     }
@@ -148,6 +193,7 @@ where
 
                             // add identity function for carried vector
                             transformations.push(|val| val);
+                            activation_kinds.push(ActivationKind::Linear);
                             // add node as available
                             next_available_nodes.push(*available_node);
                         }
@@ -173,30 +219,17 @@ where
             if dependency_graph.is_empty() {
                 // println!("stage_matrix {:?}", stage_matrix);
 
-                // let mut reordered_matrix = stage_matrix.clone();
-                let mut reordered_stage_column_indices =
-                    vec![usize::MAX; stage_column_indices.len()];
-                let mut reordered_transformations = transformations.clone();
-
+                // new column of every old column, outputs come first in output order followed by any remaining columns
+                let mut new_column_indices = vec![usize::MAX; next_available_nodes.len()];
                 let mut matched_wanted_count = 0;
 
                 for (old_column_index, available_node) in next_available_nodes.iter().enumerate() {
-                    for (new_column_index, wanted_node) in wanted_nodes.iter().enumerate() {
-                        if available_node == wanted_node {
-                            for (reordered_index, &old_index) in reordered_stage_column_indices
-                                .iter_mut()
-                                .zip(stage_column_indices.iter())
-                            {
-                                if old_index == old_column_index {
-                                    *reordered_index = new_column_index;
-                                }
-                            }
-
-                            reordered_transformations[new_column_index] =
-                                transformations[old_column_index];
-                            matched_wanted_count += 1;
-                            break;
-                        }
+                    if let Some(new_column_index) = wanted_nodes
+                        .iter()
+                        .position(|wanted_node| wanted_node == available_node)
+                    {
+                        new_column_indices[old_column_index] = new_column_index;
+                        matched_wanted_count += 1;
                     }
                 }
 
@@ -206,10 +239,32 @@ where
                     );
                 }
 
+                // keep any remaining columns behind the outputs
+                let mut remaining_column_index = wanted_nodes.len();
+                for new_column_index in new_column_indices.iter_mut() {
+                    if *new_column_index == usize::MAX {
+                        *new_column_index = remaining_column_index;
+                        remaining_column_index += 1;
+                    }
+                }
+
+                let mut reordered_transformations = transformations.clone();
+                let mut reordered_activation_kinds = activation_kinds.clone();
+                for (old_column_index, &new_column_index) in new_column_indices.iter().enumerate() {
+                    reordered_transformations[new_column_index] = transformations[old_column_index];
+                    reordered_activation_kinds[new_column_index] =
+                        activation_kinds[old_column_index];
+                }
+                let reordered_stage_column_indices = stage_column_indices
+                    .iter()
+                    .map(|&old_column_index| new_column_indices[old_column_index])
+                    .collect();
+
                 // println!("reordered_matrix {:?}", reordered_matrix);
 
                 stage_column_indices = reordered_stage_column_indices;
                 transformations = reordered_transformations;
+                activation_kinds = reordered_activation_kinds;
             }
 
             // add resolved dependencies and transformations to compute stages
@@ -218,31 +273,49 @@ where
                 stage_row_indices,
                 stage_data,
                 available_nodes.len(),
+                transformations.len(),
             ));
             stage_transformations.push(transformations);
+            stage_activation_kinds.push(activation_kinds);
 
             // set available nodes for next iteration
             available_nodes = next_available_nodes;
         }
 
-        Ok(super::evaluator::SparseMatrixFeedforwardEvaluator {
+        Ok(SparseMatrixFeedforwardEvaluator {
             stages: compute_stages
                 .into_iter()
                 .map(SparseMatrixFeedforwardFabricator::get_sparse)
                 .collect(),
             transformations: stage_transformations,
+            activation_kinds: stage_activation_kinds,
         })
     }
 }
 
+impl<N, E> Fabricator<N, E> for SparseMatrixFeedforwardFabricator
+where
+    N: NodeLike,
+    E: EdgeLike,
+{
+    type Output = SparseMatrixFeedforwardEvaluator;
+
+    fn fabricate(net: &impl NetworkLike<N, E>) -> Result<Self::Output, &'static str> {
+        Self::fabricate_with(net, &SparseMatrixFeedforwardOptions::default())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use nalgebra::dmatrix;
 
-    use super::SparseMatrixFeedforwardFabricator;
+    use super::{SparseMatrixFeedforwardFabricator, SparseMatrixFeedforwardOptions};
     use crate::{
         edges,
-        network::{net::Net, Evaluator, Fabricator},
+        network::{
+            net::{activations, Edge, Net, Node},
+            Aggregation, Evaluator, Fabricator,
+        },
         nodes,
     };
 
@@ -384,6 +457,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn reports_error_on_duplicate_output() {
+        let nodes = vec![
+            Node::new(0, activations::LINEAR),
+            Node::new(1, activations::LINEAR),
+            Node::new(1, activations::LINEAR),
+        ];
+        let net = Net::new(1, 2, nodes, edges!(0--1.0->1));
+
+        assert_eq!(
+            SparseMatrixFeedforwardFabricator::fabricate(&net).err(),
+            Some("duplicate output ids present, net invalid")
+        );
+    }
+
     // test uncomputable output
     #[test]
     fn simple_net_evaluator_7() {
@@ -399,6 +487,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn builds_empty_stage_from_empty_triplets() {
+        let stage = SparseMatrixFeedforwardFabricator::get_sparse((
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            2,
+            0,
+        ));
+
+        assert_eq!((stage.nrows(), stage.ncols()), (2, 0));
+    }
+
+    // every column holds an entry, so the closest fabrication gets to an empty stage are rows without any,
+    // the shape has to come from the available nodes and not from the largest index or node id
+    #[test]
+    fn fabricates_stage_with_rows_without_entries() {
+        let nodes = vec![
+            Node::new(0, activations::LINEAR),
+            Node::new(1, activations::LINEAR),
+            Node::new(usize::MAX - 1, activations::LINEAR),
+            Node::new(usize::MAX, activations::LINEAR),
+        ];
+        let edges = vec![
+            Edge::new(0, usize::MAX - 1, 0.5),
+            Edge::new(usize::MAX - 1, usize::MAX, 2.0),
+        ];
+        let some_net = Net::new(2, 1, nodes, edges);
+
+        let evaluator = SparseMatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        // input 1 is not connected and leaves the last row of the first stage empty
+        assert_eq!(
+            (evaluator.stages[0].nrows(), evaluator.stages[0].ncols()),
+            (2, 1)
+        );
+        assert_eq!(evaluator.evaluate(dmatrix![3.0, 7.0]), dmatrix![3.0]);
+    }
+
+    // a dead end computed in the last stage has no output position
+    #[test]
+    fn keeps_unwanted_columns_of_last_stage_behind_outputs() {
+        let some_net = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(0--0.5->1, 0--2.0->2));
+
+        let evaluator = SparseMatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let result = evaluator.evaluate(dmatrix![1.0]);
+
+        assert_eq!(result, dmatrix![2.0, 0.5]);
+    }
+
     // test uncomputable output
     #[test]
     fn simple_net_evaluator_8() {
@@ -411,6 +549,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn rejects_non_finite_weights() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l'),
+            vec![Edge::new(0, 2, 0.5), Edge::new(1, 2, f64::INFINITY)],
+        );
+
+        assert_eq!(
+            SparseMatrixFeedforwardFabricator::fabricate(&some_net).err(),
+            Some("non-finite edge weight present, net invalid")
+        );
+        assert!(SparseMatrixFeedforwardFabricator::fabricate_with(
+            &some_net,
+            &SparseMatrixFeedforwardOptions {
+                allow_non_finite_weights: true,
+            },
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn rejects_pooling_aggregation() {
+        let mut nodes = nodes!('l', 'l', 'l');
+        nodes[2].set_aggregation(Aggregation::Max);
+        let some_net = Net::new(2, 1, nodes, edges!(0--0.5->2, 1--0.5->2));
+
+        assert_eq!(
+            SparseMatrixFeedforwardFabricator::fabricate(&some_net).err(),
+            Some("aggregations other than sums are not supported by this fabricator, net invalid")
+        );
+    }
+
     #[test]
     fn simple_net_evaluator_9() {
         let some_net = Net::new(
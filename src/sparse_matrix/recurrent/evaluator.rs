@@ -1,27 +1,57 @@
 use nalgebra::DMatrix;
 
 use crate::{
-    network::{Evaluator, NetworkIO, StatefulEvaluator},
+    matrix::recurrent::evaluator::RecurrentCheckpoint,
+    network::{NetworkIO, StatefulEvaluator},
     sparse_matrix::feedforward::evaluator::SparseMatrixFeedforwardEvaluator,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SparseMatrixRecurrentEvaluator {
     pub internal: DMatrix<f64>,
     pub evaluator: SparseMatrixFeedforwardEvaluator,
     pub outputs: usize,
+    /// Inputs followed by `internal`, overwritten by every evaluation instead of allocating a new matrix.
+    pub concat_buf: DMatrix<f64>,
+}
+
+impl SparseMatrixRecurrentEvaluator {
+    /// Returns the number of values carried from one evaluation to the next.
+    ///
+    /// There is one slot per output of the net plus one per distinct start node of recurrent edges that is not an output itself,
+    /// independent of how many recurrent edges leave that node.
+    pub fn memory_size(&self) -> usize {
+        self.internal.len()
+    }
+
+    /// Snapshots the internal state, see [`RecurrentCheckpoint`].
+    pub fn checkpoint(&self) -> RecurrentCheckpoint {
+        RecurrentCheckpoint::new(&self.internal)
+    }
+
+    /// Returns the internal state to a snapshot taken from this evaluator.
+    pub fn restore(&mut self, checkpoint: &RecurrentCheckpoint) {
+        checkpoint.restore_into(&mut self.internal);
+    }
 }
 
 impl StatefulEvaluator for SparseMatrixRecurrentEvaluator {
     fn evaluate<T: NetworkIO>(&mut self, input: T) -> T {
-        let mut input = NetworkIO::input(input);
-        input = DMatrix::from_iterator(
-            1,
-            input.len() + self.internal.len(),
-            input.iter().chain(self.internal.iter()).cloned(),
+        let input = NetworkIO::input(input);
+        let inputs = self.concat_buf.ncols() - self.internal.len();
+        assert_eq!(
+            input.ncols(),
+            inputs,
+            "expected {} inputs, got {}",
+            inputs,
+            input.ncols()
         );
+        self.concat_buf.columns_mut(0, inputs).copy_from(&input);
+        self.concat_buf
+            .columns_mut(inputs, self.internal.len())
+            .copy_from(&self.internal);
 
-        self.internal = self.evaluator.evaluate(input);
+        self.internal = self.evaluator.evaluate_matrix(&self.concat_buf);
 
         NetworkIO::output(DMatrix::from_iterator(
             1,
@@ -36,4 +66,66 @@ impl StatefulEvaluator for SparseMatrixRecurrentEvaluator {
     fn reset_internal_state(&mut self) {
         self.internal = DMatrix::from_element(1, self.internal.len(), 0.0);
     }
+
+    fn map_internal_state(&mut self, f: &mut dyn FnMut(f64) -> f64) {
+        self.internal.apply(|value| *value = f(*value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{dmatrix, DMatrix};
+
+    use crate::{
+        edges,
+        network::{net::Net, Evaluator, StatefulEvaluator, StatefulFabricator},
+        nodes, SparseMatrixRecurrentFabricator,
+    };
+
+    #[test]
+    fn reused_buffer_matches_fresh_concatenation() {
+        let some_net = Net::with_recurrent(
+            2,
+            1,
+            nodes!('l', 'l', 't', 's'),
+            edges!(0--0.5->2, 1---0.75->2, 2--1.5->3),
+            edges!(2--0.25->2, 3---0.5->2),
+        );
+        let mut evaluator = SparseMatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        let unrolled = evaluator.evaluator.clone();
+        let mut internal = evaluator.internal.clone();
+
+        for step in 0..1000 {
+            let input = dmatrix![(step as f64 * 0.1).sin(), (step as f64 * 0.07).cos()];
+
+            // concatenate into a new matrix like evaluations did before reusing the buffer
+            let concatenated = DMatrix::from_iterator(
+                1,
+                input.len() + internal.len(),
+                input.iter().chain(internal.iter()).cloned(),
+            );
+            internal = unrolled.evaluate(concatenated);
+
+            assert_eq!(
+                evaluator.evaluate(input),
+                internal.columns(0, 1).clone_owned()
+            );
+        }
+    }
+
+    #[test]
+    fn restores_checkpoint() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 's'), edges!(0--1.0->1));
+        some_net.set_recurrent_edges(edges!(1--0.5->1));
+
+        let mut evaluator = SparseMatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        evaluator.evaluate(dmatrix![1.0]);
+
+        let checkpoint = evaluator.checkpoint();
+        let first = evaluator.evaluate(dmatrix![0.5]);
+        evaluator.evaluate(dmatrix![0.5]);
+
+        evaluator.restore(&checkpoint);
+        assert_eq!(evaluator.evaluate(dmatrix![0.5]), first);
+    }
 }
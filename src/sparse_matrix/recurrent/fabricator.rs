@@ -27,6 +27,7 @@ where
             internal: DMatrix::from_element(1, memory, 0.0),
             evaluator,
             outputs: net.outputs().len(),
+            concat_buf: DMatrix::zeros(1, unrolled.inputs().len()),
         })
     }
 }
@@ -207,6 +208,27 @@ mod tests {
         assert_eq!(result, dmatrix![14.0]);
     }
 
+    #[test]
+    fn reports_memory_size() {
+        let mut self_loop = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->1, 1--1.0->2));
+        self_loop.set_recurrent_edges(edges!(1--1.0->1));
+        let mut shared_start = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--1.0->1, 0--1.0->2, 1--1.0->3, 2--1.0->3),
+        );
+        shared_start.set_recurrent_edges(edges!(3--1.0->1, 3--1.0->2));
+
+        // the output and the hidden node are remembered
+        let evaluator = SparseMatrixRecurrentFabricator::fabricate(&self_loop).unwrap();
+        assert_eq!(evaluator.memory_size(), 2);
+
+        // only the output is remembered as it is the start of both recurrent edges
+        let evaluator = SparseMatrixRecurrentFabricator::fabricate(&shared_start).unwrap();
+        assert_eq!(evaluator.memory_size(), 1);
+    }
+
     #[test]
     fn stateful_net_evaluator_self_recurrence() {
         let mut some_net = Net::new(
@@ -0,0 +1,273 @@
+//! Helpers to test code that depends on fabrication, enabled by the `testing` feature.
+
+use crate::{
+    matrix::feedforward::evaluator::MatrixFeedforwardEvaluator,
+    network::{
+        net::{activations, Edge, Net, Node},
+        Fabricator,
+    },
+    MatrixFeedforwardFabricator,
+};
+
+/// Fabricates `net` `repeats` times and panics at the first difference between the resulting evaluators.
+///
+/// Compares stage matrices element-wise as well as the activations, pooling and node ids of every stage.
+pub fn assert_fabrication_deterministic(net: &Net, repeats: usize) {
+    let reference = MatrixFeedforwardFabricator::fabricate(net).expect("net fabricates");
+
+    for repeat in 1..repeats {
+        let evaluator = MatrixFeedforwardFabricator::fabricate(net).expect("net fabricates");
+
+        if let Some(mismatch) = first_mismatch(&reference, &evaluator) {
+            panic!("fabrication {} differs from first: {}", repeat, mismatch);
+        }
+    }
+}
+
+/// Builds a random feedforward net that is the same for the same arguments.
+///
+/// Ids are assigned to inputs first, then hidden nodes, then outputs, and edges only lead from lower to higher ids,
+/// every possible edge between them is present with probability `edge_prob`.
+/// Every hidden node and output gets at least one incoming edge, so all outputs are computable and the net always fabricates.
+pub fn random_net(inputs: usize, outputs: usize, hidden: usize, edge_prob: f64, seed: u64) -> Net {
+    let mut rng = SplitMix64(seed);
+    let nodes_count = inputs + hidden + outputs;
+
+    let nodes = (0..nodes_count)
+        .map(|id| Node::new(id, RANDOM_ACTIVATIONS[rng.below(RANDOM_ACTIVATIONS.len())]))
+        .collect();
+
+    let mut edges = Vec::new();
+    for end in inputs..nodes_count {
+        // outputs do not feed other outputs
+        let starts = end.min(inputs + hidden);
+        let before = edges.len();
+        for start in 0..starts {
+            if rng.unit() < edge_prob {
+                edges.push(Edge::new(start, end, rng.weight()));
+            }
+        }
+        if edges.len() == before {
+            edges.push(Edge::new(rng.below(starts), end, rng.weight()));
+        }
+    }
+
+    Net::new(inputs, outputs, nodes, edges)
+}
+
+/// Builds a [`random_net`] and adds recurrent edges between any two nodes that are not inputs with probability `recurrent_prob`.
+pub fn random_recurrent_net(
+    inputs: usize,
+    outputs: usize,
+    hidden: usize,
+    edge_prob: f64,
+    recurrent_prob: f64,
+    seed: u64,
+) -> Net {
+    let mut net = random_net(inputs, outputs, hidden, edge_prob, seed);
+    // continue from a different stream than the feedforward part
+    let mut rng = SplitMix64(!seed);
+    let nodes_count = inputs + hidden + outputs;
+
+    let mut recurrent_edges = Vec::new();
+    for start in inputs..nodes_count {
+        for end in inputs..nodes_count {
+            if rng.unit() < recurrent_prob {
+                recurrent_edges.push(Edge::new(start, end, rng.weight()));
+            }
+        }
+    }
+
+    net.set_recurrent_edges(recurrent_edges);
+    net
+}
+
+const RANDOM_ACTIVATIONS: [fn(f64) -> f64; 5] = [
+    activations::LINEAR,
+    activations::SIGMOID,
+    activations::TANH,
+    activations::GAUSSIAN,
+    activations::RELU,
+];
+
+// small self-contained generator so nets are reproducible without depending on a rand crate
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform in [0, 1)
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    fn weight(&mut self) -> f64 {
+        self.unit() * 2.0 - 1.0
+    }
+}
+
+fn first_mismatch(
+    reference: &MatrixFeedforwardEvaluator,
+    evaluator: &MatrixFeedforwardEvaluator,
+) -> Option<String> {
+    if reference.stages.len() != evaluator.stages.len() {
+        return Some(format!(
+            "stage count {} != {}",
+            reference.stages.len(),
+            evaluator.stages.len()
+        ));
+    }
+
+    if reference.input_nodes != evaluator.input_nodes {
+        return Some(format!(
+            "input nodes {:?} != {:?}",
+            reference.input_nodes, evaluator.input_nodes
+        ));
+    }
+
+    for stage in 0..reference.stages.len() {
+        let (a, b) = (&reference.stages[stage], &evaluator.stages[stage]);
+        if a.shape() != b.shape() {
+            return Some(format!(
+                "stage {} shape {:?} != {:?}",
+                stage,
+                a.shape(),
+                b.shape()
+            ));
+        }
+        for (index, (x, y)) in a.iter().zip(b.iter()).enumerate() {
+            if x.to_bits() != y.to_bits() {
+                return Some(format!(
+                    "stage {} entry {:?} {} != {}",
+                    stage,
+                    (index % a.nrows(), index / a.nrows()),
+                    x,
+                    y
+                ));
+            }
+        }
+        if reference.stage_nodes[stage] != evaluator.stage_nodes[stage] {
+            return Some(format!(
+                "stage {} nodes {:?} != {:?}",
+                stage, reference.stage_nodes[stage], evaluator.stage_nodes[stage]
+            ));
+        }
+        if reference.activation_kinds[stage] != evaluator.activation_kinds[stage] {
+            return Some(format!(
+                "stage {} activation kinds {:?} != {:?}",
+                stage, reference.activation_kinds[stage], evaluator.activation_kinds[stage]
+            ));
+        }
+        if reference.pooling[stage] != evaluator.pooling[stage] {
+            return Some(format!(
+                "stage {} pooling {:?} != {:?}",
+                stage, reference.pooling[stage], evaluator.pooling[stage]
+            ));
+        }
+        let transformations_equal = reference.transformations[stage]
+            .iter()
+            .zip(&evaluator.transformations[stage])
+            .all(|(&f, &g)| f as usize == g as usize);
+        if !transformations_equal {
+            return Some(format!("stage {} transformations differ", stage));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        assert_fabrication_deterministic, first_mismatch, random_net, random_recurrent_net,
+    };
+    use crate::{
+        edges,
+        network::{net::Net, Fabricator, NetworkLike, Recurrent, StatefulFabricator},
+        nodes, MatrixFeedforwardFabricator, MatrixRecurrentFabricator,
+    };
+
+    #[test]
+    fn passes_on_single_dependency() {
+        let net = Net::new(2, 1, nodes!('l', 'l', 's'), edges!(0--0.5->2, 1--0.5->2));
+
+        assert_fabrication_deterministic(&net, 10);
+    }
+
+    #[test]
+    fn passes_on_branching_net() {
+        let net = Net::new(
+            1,
+            2,
+            nodes!('l', 't', 's', 'l', 'l', 'g'),
+            edges!(
+                0--0.5->1,
+                0--0.5->2,
+                0--0.5->3,
+                1--0.5->4,
+                2--0.5->4,
+                3--0.5->5,
+                0--0.5->4
+            ),
+        );
+
+        assert_fabrication_deterministic(&net, 10);
+    }
+
+    #[test]
+    fn reports_first_mismatch() {
+        let net = Net::new(2, 1, nodes!('l', 'l', 's'), edges!(0--0.5->2, 1--0.5->2));
+        let reference = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+        evaluator.stages[0][(1, 0)] = 0.25;
+
+        assert_eq!(
+            first_mismatch(&reference, &evaluator),
+            Some("stage 0 entry (1, 0) 0.5 != 0.25".to_owned())
+        );
+    }
+
+    #[test]
+    fn fabricates_seeded_random_nets() {
+        for seed in 0..100 {
+            let net = random_net(3, 2, 6, 0.3, seed);
+
+            assert!(
+                MatrixFeedforwardFabricator::fabricate(&net).is_ok(),
+                "seed {} failed to fabricate",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn random_nets_are_reproducible() {
+        let first = random_recurrent_net(2, 2, 4, 0.5, 0.2, 7);
+        let second = random_recurrent_net(2, 2, 4, 0.5, 0.2, 7);
+
+        let describe = |net: &Net| {
+            (
+                net.edges()
+                    .iter()
+                    .map(|edge| format!("{:?}", edge))
+                    .collect::<Vec<_>>(),
+                net.recurrent_edges()
+                    .iter()
+                    .map(|edge| format!("{:?}", edge))
+                    .collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(describe(&first), describe(&second));
+        assert!(MatrixRecurrentFabricator::fabricate(&first).is_ok());
+    }
+}
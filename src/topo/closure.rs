@@ -0,0 +1,155 @@
+use nalgebra::DMatrix;
+
+use crate::network::{
+    Aggregation, EdgeLike, Evaluator, Fabricator, NetworkIO, NetworkLike, NodeLike,
+};
+
+use super::fabricator::TopoFeedforwardFabricator;
+
+/// Evaluates a single sample given as slice of inputs, see [`ClosureFeedforwardFabricator`].
+///
+/// Unlike the other evaluators it can not be cloned, fabricate it again or share it behind an `Arc` instead.
+pub type ClosureFeedforwardEvaluator = Box<dyn Fn(&[f64]) -> Vec<f64> + Send + Sync>;
+
+/// Allows the closure to be used like any other evaluator, every row of the input is evaluated as an independent sample.
+impl Evaluator for ClosureFeedforwardEvaluator {
+    fn evaluate<T: NetworkIO>(&self, input: T) -> T {
+        let input = NetworkIO::input(input);
+
+        let rows = input
+            .row_iter()
+            .map(|row| self(&row.iter().cloned().collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        let columns = rows.first().map_or(0, Vec::len);
+
+        NetworkIO::output(DMatrix::from_row_iterator(
+            rows.len(),
+            columns,
+            rows.into_iter().flatten(),
+        ))
+    }
+}
+
+/// Fabricates a closure that runs a flat instruction list over a scratch register file.
+///
+/// Reuses the topological ordering of [`TopoFeedforwardFabricator`] and flattens all connections into one contiguous list,
+/// which avoids the per-stage overhead of matrix multiplication and any conversion through [`crate::network::NetworkIO`].
+/// This suits latency-critical single-sample inference on small nets.
+/// Only nodes with an [`Aggregation`] other than the weighted sum take a branch.
+pub struct ClosureFeedforwardFabricator;
+
+impl<N, E> Fabricator<N, E> for ClosureFeedforwardFabricator
+where
+    N: NodeLike,
+    E: EdgeLike,
+{
+    type Output = ClosureFeedforwardEvaluator;
+
+    fn fabricate(net: &impl NetworkLike<N, E>) -> Result<Self::Output, &'static str> {
+        let topo = TopoFeedforwardFabricator::fabricate(net)?;
+
+        let inputs = topo.inputs;
+        let registers = topo.inputs + topo.nodes.len();
+        let outputs = topo.outputs;
+
+        // all connections back to back, each node knows where its connections end
+        let mut connections: Vec<(usize, f64)> = Vec::new();
+        let mut ends: Vec<usize> = Vec::with_capacity(topo.nodes.len());
+        let mut activations: crate::Transformations = Vec::with_capacity(topo.nodes.len());
+        let mut aggregations: Vec<Aggregation> = Vec::with_capacity(topo.nodes.len());
+
+        for node in topo.nodes {
+            connections.extend(node.inputs);
+            ends.push(connections.len());
+            activations.push(node.activation);
+            aggregations.push(node.aggregation);
+        }
+
+        Ok(Box::new(move |input: &[f64]| {
+            assert_eq!(
+                input.len(),
+                inputs,
+                "input length does not match net inputs"
+            );
+
+            let mut values = vec![0.0; registers];
+            values[..inputs].copy_from_slice(input);
+
+            let mut start = 0;
+            for ((register, &end), (activation, &aggregation)) in (inputs..)
+                .zip(&ends)
+                .zip(activations.iter().zip(&aggregations))
+            {
+                let weighted = connections[start..end]
+                    .iter()
+                    .map(|&(position, weight)| values[position] * weight);
+                let aggregated = if aggregation == Aggregation::WeightedSum {
+                    weighted.sum()
+                } else {
+                    aggregation.aggregate(weighted)
+                };
+                values[register] = activation(aggregated);
+                start = end;
+            }
+
+            outputs.iter().map(|&position| values[position]).collect()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::ClosureFeedforwardFabricator;
+    use crate::{
+        edges,
+        network::{net::Net, Evaluator, Fabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn matches_matrix_evaluator() {
+        let some_net = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 't', 's', 'r', 'l'),
+            edges!(
+                0--0.5->2,
+                1---0.3->2,
+                0--0.8->3,
+                2--1.2->3,
+                2---0.7->4,
+                1--0.4->5,
+                3--0.9->5
+            ),
+        );
+
+        let closure = ClosureFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let matrix = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        for input in &[[0.0, 0.0], [1.0, -1.0], [0.3, 2.5], [-4.0, 0.7]] {
+            let expected = matrix.evaluate(input.to_vec());
+            let result = closure(input);
+
+            assert_eq!(result.len(), expected.len());
+            for (result, expected) in result.iter().zip(&expected) {
+                assert!((result - expected).abs() < 1e-12);
+            }
+        }
+
+        let batch = dmatrix![1.0, -1.0; 0.3, 2.5];
+        let expected = matrix.evaluate(batch.clone());
+        assert!((closure.evaluate(batch) - expected).abs().max() < 1e-12);
+    }
+
+    #[test]
+    fn reports_errors_of_topological_analysis() {
+        let some_net = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(1--0.5->2));
+
+        assert_eq!(
+            ClosureFeedforwardFabricator::fabricate(&some_net).err(),
+            Some("can't resolve dependencies, net invalid")
+        );
+    }
+}
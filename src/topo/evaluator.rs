@@ -0,0 +1,52 @@
+use nalgebra::DMatrix;
+
+use crate::network::{ActivationKind, Aggregation, Evaluator, NetworkIO};
+
+/// A node of the [`TopoFeedforwardEvaluator`] with its incoming connections as `(position, weight)` pairs.
+///
+/// Positions index into the value buffer which holds all inputs followed by all nodes in topological order.
+#[derive(Debug, Clone)]
+pub struct TopoNode {
+    pub activation: fn(f64) -> f64,
+    pub activation_kind: ActivationKind,
+    pub aggregation: Aggregation,
+    pub inputs: Vec<(usize, f64)>,
+}
+
+/// Evaluates nodes one after another in topological order, no matrices involved.
+///
+/// Avoids the carry rows matrix stages need which makes it a good fit for sparse and irregular nets.
+#[derive(Debug, Clone)]
+pub struct TopoFeedforwardEvaluator {
+    pub inputs: usize,
+    pub nodes: Vec<TopoNode>,
+    pub outputs: Vec<usize>,
+}
+
+impl Evaluator for TopoFeedforwardEvaluator {
+    fn evaluate<T: NetworkIO>(&self, input: T) -> T {
+        let input = NetworkIO::input(input);
+        let mut values = vec![0.0; self.inputs + self.nodes.len()];
+        let mut output = DMatrix::zeros(input.nrows(), self.outputs.len());
+
+        // every row is treated as an independent sample
+        for (row, mut output_row) in input.row_iter().zip(output.row_iter_mut()) {
+            for (value, &input) in values.iter_mut().zip(row.iter()) {
+                *value = input;
+            }
+            for (index, node) in self.nodes.iter().enumerate() {
+                let aggregated = node.aggregation.aggregate(
+                    node.inputs
+                        .iter()
+                        .map(|&(position, weight)| values[position] * weight),
+                );
+                values[self.inputs + index] = (node.activation)(aggregated);
+            }
+            for (value, &position) in output_row.iter_mut().zip(&self.outputs) {
+                *value = values[position];
+            }
+        }
+
+        NetworkIO::output(output)
+    }
+}
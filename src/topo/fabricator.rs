@@ -0,0 +1,210 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use crate::network::{ordered_enabled_edges, EdgeLike, Fabricator, NetworkLike, NodeLike};
+
+use super::evaluator::{TopoFeedforwardEvaluator, TopoNode};
+
+pub struct TopoFeedforwardFabricator;
+
+impl<N, E> Fabricator<N, E> for TopoFeedforwardFabricator
+where
+    N: NodeLike,
+    E: EdgeLike,
+{
+    type Output = TopoFeedforwardEvaluator;
+
+    fn fabricate(net: &impl NetworkLike<N, E>) -> Result<Self::Output, &'static str> {
+        // build dependency graph by collecting incoming edges per node
+        let mut dependency_graph: BTreeMap<usize, Vec<&E>> = BTreeMap::new();
+        // collect outgoing edges per node to find dependents once a node becomes available
+        let mut dependents: HashMap<usize, Vec<usize>> = HashMap::new();
+
+        for edge in ordered_enabled_edges(net) {
+            dependency_graph.entry(edge.end()).or_default().push(edge);
+            dependents.entry(edge.start()).or_default().push(edge.end());
+        }
+
+        if dependency_graph.is_empty() {
+            return Err("no edges present, net invalid");
+        }
+
+        let nodes: HashMap<usize, &N> = net.nodes().into_iter().map(|n| (n.id(), n)).collect();
+
+        // count unresolved dependencies per node
+        let mut unresolved: HashMap<usize, usize> = dependency_graph
+            .iter()
+            .map(|(&id, dependencies)| (id, dependencies.len()))
+            .collect();
+
+        // sort via Ord implementation of provided nodes to guarantee each input will be processed by the same node every time
+        let mut inputs = net.inputs();
+        inputs.sort_unstable();
+
+        // position of every available node in the value buffer
+        let mut positions: HashMap<usize, usize> = inputs
+            .iter()
+            .enumerate()
+            .map(|(position, node)| (node.id(), position))
+            .collect();
+
+        let mut available: VecDeque<usize> = inputs.iter().map(|n| n.id()).collect();
+        let mut ordered_nodes = Vec::new();
+
+        // resolve nodes in topological order, Kahn's algorithm
+        while let Some(id) = available.pop_front() {
+            if let Some(dependents) = dependents.get(&id) {
+                for dependent in dependents {
+                    let count = unresolved.get_mut(dependent).unwrap();
+                    *count -= 1;
+                    if *count == 0 {
+                        positions.insert(*dependent, inputs.len() + ordered_nodes.len());
+                        ordered_nodes.push(*dependent);
+                        available.push_back(*dependent);
+                    }
+                }
+            }
+        }
+
+        if ordered_nodes.len() < dependency_graph.len() {
+            return Err("can't resolve dependencies, net invalid");
+        }
+
+        // sort via Ord implementation of provided nodes to guarantee each output will appear in the same order every time
+        let mut outputs = net.outputs();
+        outputs.sort_unstable();
+
+        let outputs = outputs
+            .iter()
+            .map(|node| positions.get(&node.id()).cloned())
+            .collect::<Option<Vec<_>>>()
+            .ok_or("dependencies resolved but not all outputs computable, net invalid")?;
+
+        Ok(TopoFeedforwardEvaluator {
+            inputs: inputs.len(),
+            nodes: ordered_nodes
+                .iter()
+                .map(|id| TopoNode {
+                    activation: nodes[id].activation(),
+                    activation_kind: nodes[id].activation_kind(),
+                    aggregation: nodes[id].aggregation(),
+                    inputs: dependency_graph[id]
+                        .iter()
+                        .map(|edge| (positions[&edge.start()], edge.weight()))
+                        .collect(),
+                })
+                .collect(),
+            outputs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::TopoFeedforwardFabricator;
+    use crate::{
+        edges,
+        network::{net::Net, Aggregation, Evaluator, Fabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn reports_same_errors_as_matrix_fabricator() {
+        let nets = vec![
+            Net::new(1, 1, nodes!('l', 'l'), Vec::new()),
+            Net::new(1, 2, nodes!('l', 'l', 'l'), edges!(0--1.0->1)),
+            Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(1--0.5->2)),
+        ];
+
+        for net in nets {
+            assert_eq!(
+                TopoFeedforwardFabricator::fabricate(&net).err(),
+                MatrixFeedforwardFabricator::fabricate(&net).err()
+            );
+        }
+    }
+
+    #[test]
+    fn matches_matrix_evaluator_on_fixtures() {
+        let nets = vec![
+            Net::new(1, 1, nodes!('l', 'l'), edges!(0--0.5->1)),
+            Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--0.5->2, 1--0.5->2)),
+            Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(0--0.5->1, 1--0.5->2)),
+            Net::new(
+                1,
+                1,
+                nodes!('l', 't', 's'),
+                edges!(0--0.5->1, 1--0.5->2, 0--0.5->2),
+            ),
+            Net::new(
+                1,
+                2,
+                nodes!('l', 'r', 'g', 'q'),
+                edges!(0--0.5->1, 1--0.5->2, 0--0.5->3, 0--0.5->2),
+            ),
+            Net::new(
+                1,
+                2,
+                nodes!('l', 'i', 's', 't'),
+                edges!(0--0.5->1, 1--0.5->3, 0--0.5->2),
+            ),
+            Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--0.5->2, 1--0.0->2)),
+        ];
+
+        for net in nets {
+            let topo = TopoFeedforwardFabricator::fabricate(&net).unwrap();
+            let matrix = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+
+            for input in &[-1.5, 0.0, 5.0] {
+                let input = vec![*input; topo.inputs];
+                assert_eq!(topo.evaluate(input.clone()), matrix.evaluate(input));
+            }
+        }
+    }
+
+    #[test]
+    fn matches_matrix_evaluator_on_pooling() {
+        let mut nodes = nodes!('l', 'l', 'l', 't', 'l', 's');
+        nodes[2].set_aggregation(Aggregation::Max);
+        nodes[3].set_aggregation(Aggregation::Min);
+        nodes[4].set_aggregation(Aggregation::Mean);
+        let net = Net::new(
+            2,
+            2,
+            nodes,
+            edges!(
+                0--1.0->2,
+                1--0.5->2,
+                0---1.0->3,
+                1--2.0->3,
+                2--1.0->4,
+                3--0.3->4,
+                0--0.7->4,
+                2--1.5->5,
+                1--0.2->5
+            ),
+        );
+
+        let topo = TopoFeedforwardFabricator::fabricate(&net).unwrap();
+        let matrix = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+
+        for input in &[[1.0, 4.0], [-2.0, 0.5], [3.0, -3.0]] {
+            let difference = topo.evaluate(dmatrix![input[0], input[1]])
+                - matrix.evaluate(dmatrix![input[0], input[1]]);
+            assert!(difference.abs().max() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn evaluates_rows_independently() {
+        let net = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--0.5->2, 1--0.5->2));
+
+        let evaluator = TopoFeedforwardFabricator::fabricate(&net).unwrap();
+
+        assert_eq!(
+            evaluator.evaluate(dmatrix![5.0, 5.0; 1.0, 3.0]),
+            dmatrix![5.0; 2.0]
+        );
+    }
+}
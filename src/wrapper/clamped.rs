@@ -0,0 +1,82 @@
+use nalgebra::DMatrix;
+
+use crate::network::{Evaluator, NetworkIO};
+
+/// Clamps every output of the wrapped [`Evaluator`] into its own range.
+///
+/// `bounds` holds the lower and upper bound of each output in output order, every row of the output is clamped alike.
+#[derive(Debug, Clone)]
+pub struct ClampedEvaluator<E: Evaluator> {
+    pub inner: E,
+    pub bounds: Vec<(f64, f64)>,
+}
+
+impl<E: Evaluator> ClampedEvaluator<E> {
+    /// Wraps `inner` which returns `outputs` values per sample.
+    ///
+    /// As evaluators do not expose their dimensions, `outputs` is given explicitly, e.g. from [`crate::network::NetworkLike::outputs`].
+    pub fn new(inner: E, bounds: Vec<(f64, f64)>, outputs: usize) -> Result<Self, &'static str> {
+        if bounds.len() != outputs {
+            return Err("number of bounds does not match number of outputs");
+        }
+        if bounds
+            .iter()
+            .any(|(lower, upper)| lower.is_nan() || upper.is_nan() || lower > upper)
+        {
+            return Err("lower bound above upper bound or bound is NaN");
+        }
+
+        Ok(Self { inner, bounds })
+    }
+}
+
+impl<E: Evaluator> Evaluator for ClampedEvaluator<E> {
+    fn evaluate<T: NetworkIO>(&self, input: T) -> T {
+        let mut output: DMatrix<f64> = self.inner.evaluate(NetworkIO::input(input));
+
+        for (mut column, &(lower, upper)) in output.column_iter_mut().zip(&self.bounds) {
+            column.apply(|value| *value = value.clamp(lower, upper));
+        }
+
+        NetworkIO::output(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::ClampedEvaluator;
+    use crate::{
+        edges,
+        network::{net::Net, Evaluator, Fabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn saturates_at_bounds() {
+        let some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--2.0->1));
+        let evaluator = ClampedEvaluator::new(
+            MatrixFeedforwardFabricator::fabricate(&some_net).unwrap(),
+            vec![(-1.0, 1.0)],
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.evaluate(dmatrix![0.25; 3.0; -5.0]),
+            dmatrix![0.5; 1.0; -1.0]
+        );
+    }
+
+    #[test]
+    fn rejects_bounds_not_matching_outputs() {
+        let some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--2.0->1));
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(
+            ClampedEvaluator::new(evaluator, vec![(-1.0, 1.0), (0.0, 1.0)], 1).err(),
+            Some("number of bounds does not match number of outputs")
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use nalgebra::DMatrix;
+
+use crate::network::{Evaluator, NetworkIO, StatefulEvaluator};
+
+/// Feeds outputs of the previous evaluation of the wrapped [`Evaluator`] back into it as inputs.
+///
+/// `feedback` maps output positions to input positions of the wrapped evaluator, given as `(output, input)` pairs.
+/// The input passed to [`StatefulEvaluator::evaluate`] fills all other input positions in order,
+/// so it has as many values fewer than the wrapped evaluator expects as there are pairs.
+/// Fed back values start at zero and are set to zero again by [`StatefulEvaluator::reset_internal_state`].
+/// Every row of a batch keeps its own feedback, changing the number of rows starts over from zero.
+#[derive(Debug, Clone)]
+pub struct FeedbackEvaluator<E: Evaluator> {
+    pub inner: E,
+    pub feedback: Vec<(usize, usize)>,
+    /// Values fed back in the next evaluation, one column per pair of `feedback`.
+    pub previous: DMatrix<f64>,
+}
+
+impl<E: Evaluator> FeedbackEvaluator<E> {
+    pub fn new(inner: E, feedback: Vec<(usize, usize)>) -> Self {
+        let previous = DMatrix::zeros(1, feedback.len());
+        Self {
+            inner,
+            feedback,
+            previous,
+        }
+    }
+}
+
+impl<E: Evaluator> StatefulEvaluator for FeedbackEvaluator<E> {
+    fn evaluate<T: NetworkIO>(&mut self, input: T) -> T {
+        let input = NetworkIO::input(input);
+        if self.previous.nrows() != input.nrows() {
+            self.previous = DMatrix::zeros(input.nrows(), self.feedback.len());
+        }
+
+        let width = input.ncols() + self.feedback.len();
+        let mut inner_input = DMatrix::zeros(input.nrows(), width);
+        let mut external = 0;
+        for position in 0..width {
+            let column = match self
+                .feedback
+                .iter()
+                .position(|&(_, fed_position)| fed_position == position)
+            {
+                Some(pair) => self.previous.column(pair),
+                None => {
+                    external += 1;
+                    input.column(external - 1)
+                }
+            };
+            inner_input.set_column(position, &column);
+        }
+
+        let output: DMatrix<f64> = self.inner.evaluate(inner_input);
+        for (pair, &(output_position, _)) in self.feedback.iter().enumerate() {
+            self.previous
+                .set_column(pair, &output.column(output_position));
+        }
+
+        NetworkIO::output(output)
+    }
+
+    fn reset_internal_state(&mut self) {
+        self.previous.fill(0.0);
+    }
+
+    fn map_internal_state(&mut self, f: &mut dyn FnMut(f64) -> f64) {
+        self.previous.apply(|value| *value = f(*value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FeedbackEvaluator;
+    use crate::{
+        edges,
+        network::{net::Net, Fabricator, StatefulEvaluator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn previous_output_influences_next_evaluation() {
+        // output is input 0 plus half of input 1, which receives the previous output
+        let some_net = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--0.5->2));
+        let mut evaluator = FeedbackEvaluator::new(
+            MatrixFeedforwardFabricator::fabricate(&some_net).unwrap(),
+            vec![(0, 1)],
+        );
+
+        assert_eq!(evaluator.evaluate(vec![2.0]), vec![2.0]);
+        assert_eq!(evaluator.evaluate(vec![2.0]), vec![3.0]);
+        assert_eq!(evaluator.evaluate(vec![0.0]), vec![1.5]);
+
+        evaluator.reset_internal_state();
+        assert_eq!(evaluator.evaluate(vec![2.0]), vec![2.0]);
+    }
+}
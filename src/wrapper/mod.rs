@@ -0,0 +1,6 @@
+//! Evaluators that wrap other evaluators to adjust their behavior.
+
+pub mod clamped;
+pub mod feedback;
+pub mod saturating;
+pub mod softmax;
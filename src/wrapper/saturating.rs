@@ -0,0 +1,69 @@
+use crate::network::{NetworkIO, StatefulEvaluator};
+
+/// Clamps the internal state of the wrapped [`StatefulEvaluator`] into `[-limit, limit]` after every evaluation.
+///
+/// Keeps long rollouts of recurrent nets stable that would otherwise diverge, e.g. through linear self-recurrence.
+/// The output of an evaluation is returned as computed, only the state carried into the next evaluation is clamped.
+/// Only evaluators overriding [`StatefulEvaluator::map_internal_state`] have their state clamped.
+#[derive(Debug, Clone)]
+pub struct SaturatingRecurrentEvaluator<E: StatefulEvaluator> {
+    pub inner: E,
+    pub limit: f64,
+}
+
+impl<E: StatefulEvaluator> SaturatingRecurrentEvaluator<E> {
+    pub fn new(inner: E, limit: f64) -> Self {
+        Self { inner, limit }
+    }
+}
+
+impl<E: StatefulEvaluator> StatefulEvaluator for SaturatingRecurrentEvaluator<E> {
+    fn evaluate<T: NetworkIO>(&mut self, input: T) -> T {
+        let output = self.inner.evaluate(input);
+        let limit = self.limit;
+        self.inner
+            .map_internal_state(&mut |value| value.clamp(-limit, limit));
+        output
+    }
+
+    fn reset_internal_state(&mut self) {
+        self.inner.reset_internal_state();
+    }
+
+    fn map_internal_state(&mut self, f: &mut dyn FnMut(f64) -> f64) {
+        self.inner.map_internal_state(f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SaturatingRecurrentEvaluator;
+    use crate::{
+        edges,
+        network::{net::Net, StatefulEvaluator, StatefulFabricator},
+        nodes, MatrixRecurrentFabricator,
+    };
+
+    #[test]
+    fn keeps_divergent_state_within_limit() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+        some_net.set_recurrent_edges(edges!(1--1.5->1));
+
+        let mut evaluator = SaturatingRecurrentEvaluator::new(
+            MatrixRecurrentFabricator::fabricate(&some_net).unwrap(),
+            10.0,
+        );
+
+        for _ in 0..1000 {
+            evaluator.evaluate(vec![1.0]);
+            assert!(evaluator
+                .inner
+                .internal
+                .iter()
+                .all(|value| value.abs() <= 10.0));
+        }
+
+        // saturated state feeds back as 1.0 + 1.5 * 10.0
+        assert_eq!(evaluator.evaluate(vec![1.0]), vec![16.0]);
+    }
+}
@@ -0,0 +1,72 @@
+use nalgebra::DMatrix;
+
+use crate::{
+    matrix::feedforward::vector_activation::softmax_with_temperature,
+    network::{Evaluator, NetworkIO},
+};
+
+/// Applies a softmax to the output of the wrapped [`Evaluator`], turning it into probabilities.
+///
+/// Every row of the output is normalized on its own. The maximum of a row is subtracted before exponentiating,
+/// which leaves the result unchanged but keeps large outputs from overflowing.
+/// Outputs are divided by `temperature` first, higher temperatures flatten the distribution, lower ones sharpen it.
+#[derive(Debug, Clone)]
+pub struct SoftmaxEvaluator<E: Evaluator> {
+    pub inner: E,
+    pub temperature: f64,
+}
+
+impl<E: Evaluator> SoftmaxEvaluator<E> {
+    pub fn new(inner: E) -> Self {
+        Self::with_temperature(inner, 1.0)
+    }
+
+    pub fn with_temperature(inner: E, temperature: f64) -> Self {
+        Self { inner, temperature }
+    }
+}
+
+impl<E: Evaluator> Evaluator for SoftmaxEvaluator<E> {
+    fn evaluate<T: NetworkIO>(&self, input: T) -> T {
+        let mut output: DMatrix<f64> = self.inner.evaluate(NetworkIO::input(input));
+
+        softmax_with_temperature(&mut output, self.temperature);
+
+        NetworkIO::output(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SoftmaxEvaluator;
+    use crate::{
+        edges,
+        network::{net::Net, Evaluator, Fabricator},
+        nodes, MatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn outputs_probabilities_ordered_like_logits() {
+        let some_net = Net::new(
+            1,
+            3,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(
+                0--1.0->1,
+                0--2.0->2,
+                0---1.0->3
+            ),
+        );
+        let evaluator =
+            SoftmaxEvaluator::new(MatrixFeedforwardFabricator::fabricate(&some_net).unwrap());
+
+        // large logits would overflow without subtracting the maximum
+        for input in &[1.0, 300.0] {
+            let probabilities = evaluator.evaluate(vec![*input]);
+
+            assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+            assert!(probabilities[1] > probabilities[0]);
+            assert!(probabilities[0] > probabilities[2]);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use favannat::network::{
+    net::{activations, Edge, Net, Node},
+    ActivationKind, NetworkLike, NodeLike,
+};
+
+// compiled as its own crate, the built-in activations have to be recognized across crate boundaries
+#[test]
+fn recognizes_built_in_activations_of_other_crates() {
+    let built_in = [
+        (activations::LINEAR, ActivationKind::Linear),
+        (activations::SIGMOID, ActivationKind::Sigmoid),
+        (activations::TANH, ActivationKind::Tanh),
+        (activations::GAUSSIAN, ActivationKind::Gaussian),
+        (activations::INVERSE, ActivationKind::Inverse),
+        (activations::RELU, ActivationKind::Relu),
+        (activations::SQUARED, ActivationKind::Squared),
+    ];
+
+    for (id, &(activation, kind)) in built_in.iter().enumerate() {
+        assert_eq!(Node::new(id, activation).activation_kind(), kind);
+        assert_eq!(activations::to_code(activation), kind.code());
+    }
+    assert_eq!(
+        Node::new(0, |val| val * 3.0).activation_kind(),
+        ActivationKind::Custom
+    );
+}
+
+#[test]
+fn reports_activation_codes_of_other_crates() {
+    let net = Net::new(
+        1,
+        1,
+        vec![
+            Node::new(0, activations::LINEAR),
+            Node::new(1, activations::SIGMOID),
+        ],
+        vec![Edge::new(0, 1, 1.0)],
+    );
+
+    assert_eq!(
+        net.activation_codes(),
+        vec![
+            ActivationKind::Linear.code(),
+            ActivationKind::Sigmoid.code()
+        ]
+    );
+}